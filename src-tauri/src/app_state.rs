@@ -1,8 +1,28 @@
 use std::sync::{Arc, Mutex};
+use notify_debouncer_full::notify::RecommendedWatcher;
+use notify_debouncer_full::{Debouncer, RecommendedCache};
+use crate::core::codex_config::CodexConfigCache;
+use crate::core::fs_watcher::FsWatcher;
+use crate::core::large_file::LargeFileManager;
 use crate::core::process_manager::ProcessManager;
+use crate::core::project_search::SearchManager;
+use crate::core::settings::SettingsSnapshot;
+use crate::core::workspace_index::WorkspaceIndex;
 
 pub struct AppState {
     pub process_manager: Arc<Mutex<ProcessManager>>,
+    pub large_files: Arc<Mutex<LargeFileManager>>,
+    pub fs_watcher: Arc<Mutex<FsWatcher>>,
+    pub searches: Arc<Mutex<SearchManager>>,
+    pub workspace_index: Arc<Mutex<WorkspaceIndex>>,
+    /// Path of the open `.editrion-workspace` file
+    pub current_workspace: Arc<Mutex<Option<String>>>,
+    /// Codex `config.toml` and `ai_templates.json` as last read
+    pub codex_config: Arc<Mutex<CodexConfigCache>>,
+    /// Keeps the Codex config directory watched while the app runs
+    pub codex_watcher: Arc<Mutex<Option<Debouncer<RecommendedWatcher, RecommendedCache>>>>,
+    /// Settings as last sent to the windows, to tell what changed
+    pub last_settings: Arc<Mutex<Option<SettingsSnapshot>>>,
     pub startup_paths: Vec<String>,
 }
 
@@ -10,6 +30,14 @@ impl AppState {
     pub fn new_with_paths(paths: Vec<String>) -> Self {
         Self {
             process_manager: Arc::new(Mutex::new(ProcessManager::new())),
+            large_files: Arc::new(Mutex::new(LargeFileManager::new())),
+            fs_watcher: Arc::new(Mutex::new(FsWatcher::new())),
+            searches: Arc::new(Mutex::new(SearchManager::new())),
+            workspace_index: Arc::new(Mutex::new(WorkspaceIndex::new())),
+            current_workspace: Arc::new(Mutex::new(None)),
+            codex_config: Arc::new(Mutex::new(CodexConfigCache::new())),
+            codex_watcher: Arc::new(Mutex::new(None)),
+            last_settings: Arc::new(Mutex::new(None)),
             startup_paths: paths,
         }
     }
@@ -1,329 +1,335 @@
 use std::collections::HashMap;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read};
 use std::path::Path;
-use std::process::{Child, Command, Stdio};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use tauri::{Emitter, State, Window};
+use std::thread::JoinHandle;
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, State, Window};
 
 use crate::app_state::AppState;
-use crate::core::process_manager::{resolve_binary_path, strip_ansi};
+use crate::commands::settings::current_settings;
+use crate::core::ai_events::{AiEvent, Answer};
+use crate::core::ai_provider::{self, AiProvider, ExecRequest, PromptInput, ProviderInfo, RunLifecycle, RunStatus};
+use crate::core::process_manager::{
+    exit_details, isolate_process_group, resolve_binary_path, shell_quote, strip_ansi, wait_or_cancel, ProcessManager,
+    RunHandle, CANCEL_GRACE_PERIOD,
+};
+use crate::core::settings;
 use crate::error::{AppError, Result};
 
+/// Run `prompt` through an AI provider (`codex`, `claude` or `custom`). Output
+/// arrives raw as `ai-stream` events on the `stdout` and `stderr` channels and
+/// parsed as `ai-event` events, progress as `ai-lifecycle` events, and the run
+/// ends with an `ai-complete` event;
+/// `ai_cancel` with the same `run_id` stops it early, which completes with the
+/// `cancelled` status rather than an error.
 #[tauri::command]
-pub async fn codex_exec_stream(
+#[allow(clippy::too_many_arguments)]
+pub async fn ai_exec_stream(
+    app: AppHandle,
     state: State<'_, AppState>,
     window: Window,
+    provider: String,
     prompt: String,
     cwd: Option<String>,
     run_id: String,
     model: Option<String>,
     config: Option<HashMap<String, String>>,
 ) -> Result<()> {
+    let provider = ai_provider::find_provider(&provider, custom_command(&app, &state)?)?;
+    let request = ExecRequest { prompt, model, config: config.unwrap_or_default() };
     let process_manager = state.process_manager.clone();
     tauri::async_runtime::spawn_blocking(move || {
-        run_external_cli_stream(
-            process_manager,
-            window,
-            "codex",
-            prompt,
-            cwd,
-            run_id,
-            model,
-            config,
-        )
+        run_external_cli_stream(process_manager, window, provider, request, cwd, run_id)
     })
     .await
-    .map_err(|e| AppError::Command(format!("Failed to join codex stream worker: {}", e)))?
+    .map_err(|e| AppError::Command(format!("Failed to join AI stream worker: {}", e)))?
 }
 
 #[tauri::command]
-pub async fn claude_exec_stream(
-    state: State<'_, AppState>,
-    window: Window,
-    prompt: String,
-    cwd: Option<String>,
-    run_id: String,
-    model: Option<String>,
-    config: Option<HashMap<String, String>>,
-) -> Result<()> {
-    let process_manager = state.process_manager.clone();
-    tauri::async_runtime::spawn_blocking(move || {
-        run_external_cli_stream(
-            process_manager,
-            window,
-            "claude",
-            prompt,
-            cwd,
-            run_id,
-            model,
-            config,
-        )
-    })
-    .await
-    .map_err(|e| AppError::Command(format!("Failed to join claude stream worker: {}", e)))?
-}
-
-#[tauri::command]
-pub async fn codex_login_stream(window: Window, run_id: String) -> Result<()> {
-    tauri::async_runtime::spawn_blocking(move || run_external_cli_login_stream(window, "codex", run_id))
+pub async fn ai_login_stream(app: AppHandle, state: State<'_, AppState>, window: Window, provider: String, run_id: String) -> Result<()> {
+    let provider = ai_provider::find_provider(&provider, custom_command(&app, &state)?)?;
+    tauri::async_runtime::spawn_blocking(move || run_external_cli_login_stream(window, provider.as_ref(), run_id))
         .await
-        .map_err(|e| AppError::Command(format!("Failed to join codex login worker: {}", e)))?
+        .map_err(|e| AppError::Command(format!("Failed to join AI login worker: {}", e)))?
 }
 
 #[tauri::command]
-pub async fn claude_login_stream(window: Window, run_id: String) -> Result<()> {
-    tauri::async_runtime::spawn_blocking(move || run_external_cli_login_stream(window, "claude", run_id))
-        .await
-        .map_err(|e| AppError::Command(format!("Failed to join claude login worker: {}", e)))?
+pub fn ai_cancel(state: State<'_, AppState>, run_id: String) -> Result<()> {
+    if let Ok(mut manager) = state.process_manager.lock() {
+        manager.cancel_process(&run_id)
+    } else {
+        Err(AppError::ProcessNotFound(run_id))
+    }
 }
 
+/// Every provider with whether its executable can be found
 #[tauri::command]
-pub fn codex_cancel(state: State<'_, AppState>, run_id: String) -> Result<()> {
-    cancel_process(state, run_id)
+pub async fn list_providers(app: AppHandle, state: State<'_, AppState>) -> Result<Vec<ProviderInfo>> {
+    let custom = custom_command(&app, &state)?;
+    // Resolving binaries may start a login shell
+    tauri::async_runtime::spawn_blocking(move || {
+        ai_provider::providers(custom).iter().map(|p| ProviderInfo::of(p.as_ref())).collect()
+    })
+    .await
+    .map_err(|e| AppError::Command(format!("Failed to join provider lookup: {}", e)))
 }
 
-#[tauri::command]
-pub fn claude_cancel(state: State<'_, AppState>, run_id: String) -> Result<()> {
-    cancel_process(state, run_id)
+fn custom_command(app: &AppHandle, state: &AppState) -> Result<Vec<String>> {
+    let snapshot = current_settings(app, state)?;
+    let argv = settings::get_path(&snapshot.settings, "ai.customCommand")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default();
+    Ok(argv)
 }
 
-fn cancel_process(state: State<'_, AppState>, run_id: String) -> Result<()> {
-    if let Ok(mut manager) = state.process_manager.lock() {
-        manager.cancel_process(&run_id)
+/// Start `args` with the provider's executable. On macOS/Linux it runs through a
+/// login shell so PATH (node, brew, etc.) is loaded. Runs get their own process
+/// group so cancelling reaches the CLI behind the shell; interactive logins stay
+/// in the foreground group to keep reading from the terminal.
+fn spawn_cli(provider: &dyn AiProvider, args: &[String], cwd: Option<&str>, stdin: Stdio, isolate: bool) -> std::io::Result<Child> {
+    let mut cmd = if cfg!(target_os = "windows") {
+        // Fall back to the name as-is and rely on PATH
+        let mut cmd = Command::new(resolve_binary_path(provider.binary()).unwrap_or_else(|| provider.binary().into()));
+        cmd.args(args);
+        cmd
     } else {
-        Err(AppError::ProcessNotFound(run_id))
+        let cmdline = std::iter::once(provider.binary())
+            .chain(args.iter().map(String::as_str))
+            .map(shell_quote)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut cmd = Command::new("/bin/zsh");
+        cmd.arg("-lc").arg(&cmdline);
+        cmd
+    };
+    if let Some(dir) = cwd { if Path::new(dir).is_dir() { let _ = cmd.current_dir(dir); } }
+    cmd.stdin(stdin).stdout(Stdio::piped()).stderr(Stdio::piped());
+    if isolate {
+        isolate_process_group(&mut cmd);
     }
+    cmd.spawn()
 }
 
-fn run_external_cli_stream(
-    process_manager: std::sync::Arc<std::sync::Mutex<crate::core::process_manager::ProcessManager>>,
+/// Sends the events of one run to the window
+#[derive(Clone)]
+struct RunEvents {
     window: Window,
-    cli_name: &str,
-    prompt: String,
-    cwd: Option<String>,
     run_id: String,
-    model: Option<String>,
-    config: Option<HashMap<String, String>>,
-) -> Result<()> {
-    let spawn = || -> std::io::Result<Child> {
-        let mut pre_flags: Vec<String> = Vec::new();
-        if let Some(m) = model.as_ref() {
-            pre_flags.push("--model".into());
-            pre_flags.push(m.clone());
-        }
-        if let Some(cfg) = config.as_ref() {
-            for (k, v) in cfg.iter() {
-                pre_flags.push("-c".into());
-                pre_flags.push(format!("{}={}", k, v));
-            }
+    provider: &'static str,
+    started: Instant,
+    first_byte: Arc<AtomicBool>,
+}
+
+impl RunEvents {
+    fn new(window: Window, run_id: String, provider: &'static str) -> Self {
+        Self { window, run_id, provider, started: Instant::now(), first_byte: Arc::new(AtomicBool::new(false)) }
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    fn stream(&self, channel: &str, data: String) {
+        let _ = self.window.emit("ai-stream", &serde_json::json!({
+            "runId": self.run_id,
+            "provider": self.provider,
+            "channel": channel,
+            "data": data,
+        }));
+    }
+
+    fn ai_event(&self, event: &AiEvent) {
+        let _ = self.window.emit("ai-event", &serde_json::json!({
+            "runId": self.run_id,
+            "provider": self.provider,
+            "event": event,
+        }));
+    }
+
+    fn lifecycle(&self, event: RunLifecycle) {
+        let _ = self.window.emit("ai-lifecycle", &serde_json::json!({
+            "runId": self.run_id,
+            "provider": self.provider,
+            "event": event,
+        }));
+    }
+
+    /// Reported once, for whichever stream produces output first
+    fn output_started(&self) {
+        if !self.first_byte.swap(true, Ordering::SeqCst) {
+            self.lifecycle(RunLifecycle::FirstByte { latency_ms: self.elapsed_ms() });
         }
+    }
 
-        if cfg!(target_os = "windows") {
-            // On Windows, avoid exceeding command-line length limits by sending prompt via stdin
-            if let Some(bin_path) = resolve_binary_path(cli_name) {
-                let mut cmd = Command::new(&bin_path);
-                cmd.arg("exec").arg("--skip-git-repo-check");
-                for a in &pre_flags { cmd.arg(a); }
-                if let Some(ref dir) = cwd { if Path::new(dir).is_dir() { let _ = cmd.current_dir(dir); } }
-                cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped());
-                return cmd.spawn();
+    /// `text` is the answer; `stdout` and `stderr` are the raw output
+    fn exited(&self, status: &ExitStatus, run_status: RunStatus, text: String, stdout: String, stderr: String) {
+        let (code, signal) = exit_details(status);
+        let duration_ms = self.elapsed_ms();
+        self.lifecycle(RunLifecycle::Exited { code, signal, duration_ms });
+        let _ = self.window.emit("ai-complete", &serde_json::json!({
+            "runId": self.run_id,
+            "provider": self.provider,
+            "ok": run_status == RunStatus::Succeeded,
+            "status": run_status,
+            "text": text,
+            "stdout": stdout,
+            "stderr": stderr,
+            "exitCode": code,
+            "signal": signal,
+            "durationMs": duration_ms,
+        }));
+    }
+}
+
+/// Stream `pipe` line by line on `channel` from its own thread, keeping a copy in
+/// `buf` and handing each line to `on_line`
+fn stream_lines(
+    pipe: impl Read + Send + 'static,
+    channel: &'static str,
+    events: RunEvents,
+    buf: Arc<Mutex<String>>,
+    mut on_line: impl FnMut(&str) + Send + 'static,
+) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        if reader.fill_buf().map(|b| !b.is_empty()).unwrap_or(false) {
+            events.output_started();
+        }
+        for line in reader.lines() {
+            let Ok(line) = line else { break };
+            let cleaned = strip_ansi(&line);
+            if let Ok(mut b) = buf.lock() {
+                b.push_str(&cleaned);
+                b.push('\n');
             }
-            // Fallback to using the name as-is; rely on PATH
-            let mut cmd = Command::new(cli_name);
-            cmd.arg("exec").arg("--skip-git-repo-check");
-            for a in &pre_flags { cmd.arg(a); }
-            if let Some(ref dir) = cwd { if Path::new(dir).is_dir() { let _ = cmd.current_dir(dir); } }
-            cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped());
-            return cmd.spawn();
-        } else {
-            // On macOS/Linux, always run via login shell so PATH (node, brew, etc.) is loaded.
-            let flags = if pre_flags.is_empty() { String::new() } else { format!("{} ", pre_flags.join(" ")) };
-            // Prefer sending prompt via stdin as well to avoid ARG_MAX issues on very large inputs
-            let cmdline = format!(
-                "{} exec --skip-git-repo-check {}",
-                cli_name,
-                flags,
-            );
-            let mut cmd = Command::new("/bin/zsh");
-            cmd.arg("-lc").arg(&cmdline);
-            if let Some(ref dir) = cwd { if Path::new(dir).is_dir() { let _ = cmd.current_dir(dir); } }
-            cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped());
-            return cmd.spawn();
+            events.stream(channel, format!("{}\n", cleaned));
+            on_line(&cleaned);
         }
-    };
+    })
+}
 
-    let child = spawn().map_err(AppError::Io)?;
-    let child_arc = Arc::new(Mutex::new(child));
+fn take_buffer(buf: &Mutex<String>) -> String {
+    buf.lock().map(|b| b.clone()).unwrap_or_default()
+}
 
-    // Register process for cancellation
-    {
-        if let Ok(mut manager) = process_manager.lock() {
-            manager.processes.insert(run_id.clone(), child_arc.clone());
-        }
+fn run_external_cli_stream(
+    process_manager: Arc<Mutex<ProcessManager>>,
+    window: Window,
+    provider: Arc<dyn AiProvider>,
+    request: ExecRequest,
+    cwd: Option<String>,
+    run_id: String,
+) -> Result<()> {
+    if provider.binary().is_empty() {
+        return Err(AppError::Config(format!("No command configured for the {} provider", provider.id())));
+    }
+    let args = provider.exec_args(&request);
+    let events = RunEvents::new(window, run_id.clone(), provider.id());
+    let mut child = spawn_cli(provider.as_ref(), &args, cwd.as_deref(), Stdio::piped(), true).map_err(AppError::Io)?;
+    events.lifecycle(RunLifecycle::Spawned { pid: child.id() });
+
+    // Register process for cancellation; the child itself stays with this thread
+    let handle = RunHandle::new(child.id());
+    if let Ok(mut manager) = process_manager.lock() {
+        manager.add_process(run_id.clone(), handle.clone());
     }
 
     let stdout_buf = Arc::new(Mutex::new(String::new()));
+    let stderr_buf = Arc::new(Mutex::new(String::new()));
     let mut join_handles = vec![];
 
-    // Feed prompt to child's stdin (for large inputs and Windows safety)
+    // Feed the prompt to stdin; closing it tells the CLI the prompt is complete
     {
-        let prompt_clone = prompt.clone();
-        let mut stdin = { child_arc.lock().ok().and_then(|mut c| c.stdin.take()) };
-        if let Some(mut pipe) = stdin.take() {
+        let prompt = match provider.prompt_input() {
+            PromptInput::Stdin => Some(request.prompt.clone()),
+            PromptInput::Args => None,
+        };
+        if let Some(mut pipe) = child.stdin.take() {
             let h = std::thread::spawn(move || {
                 use std::io::Write;
-                let _ = pipe.write_all(prompt_clone.as_bytes());
-                let _ = pipe.flush();
+                if let Some(prompt) = prompt {
+                    let _ = pipe.write_all(prompt.as_bytes());
+                    let _ = pipe.flush();
+                }
                 // drop(pipe) closes stdin
             });
             join_handles.push(h);
         }
     }
 
-    let mut out = { child_arc.lock().ok().and_then(|mut c| c.stdout.take()) };
-    if let Some(out) = out.take() {
-        let win = window.clone();
-        let rid = run_id.clone();
-        let buf = stdout_buf.clone();
-        let stream_event_name = format!("{}-stream", cli_name);
-
-        let h = std::thread::spawn(move || {
-            let reader = BufReader::new(out);
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    let cleaned_line = strip_ansi(&line);
-                    if let Ok(mut b) = buf.lock() {
-                        b.push_str(&cleaned_line);
-                        b.push('\n');
-                    }
-                    let _ = win.emit(&stream_event_name, &serde_json::json!({
-                        "runId": rid,
-                        "channel": "stdout",
-                        "data": format!("{}\n", cleaned_line),
-                    }));
-                }
-            }
-        });
-        join_handles.push(h);
-    }
-
-    let mut err = { child_arc.lock().ok().and_then(|mut c| c.stderr.take()) };
-    if let Some(err) = err.take() {
-        let buf = stdout_buf.clone();
-        let h = std::thread::spawn(move || {
-            let reader = BufReader::new(err);
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    if let Ok(mut b) = buf.lock() {
-                        b.push_str(&strip_ansi(&line));
-                        b.push('\n');
-                    }
+    let answer = Arc::new(Mutex::new(Answer::default()));
+    if let Some(out) = child.stdout.take() {
+        let mut parser = provider.output_parser();
+        let (emitter, answer) = (events.clone(), answer.clone());
+        let on_line = move |line: &str| {
+            for event in parser.parse_line(line) {
+                if let Ok(mut a) = answer.lock() {
+                    a.record(&event);
                 }
+                emitter.ai_event(&event);
             }
-        });
-        join_handles.push(h);
+        };
+        join_handles.push(stream_lines(out, "stdout", events.clone(), stdout_buf.clone(), on_line));
     }
 
-    let status = {
-        child_arc
-            .lock()
-            .map_err(|e| AppError::Command(e.to_string()))?
-            .wait()
-            .map_err(AppError::Io)?
-    };
-    for h in join_handles {
-        let _ = h.join();
+    if let Some(err) = child.stderr.take() {
+        join_handles.push(stream_lines(err, "stderr", events.clone(), stderr_buf.clone(), |_| {}));
     }
 
+    let status = wait_or_cancel(&mut child, &handle, CANCEL_GRACE_PERIOD);
     if let Ok(mut manager) = process_manager.lock() {
         manager.remove_process(&run_id);
     }
+    let status = status.map_err(AppError::Io)?;
+    for h in join_handles {
+        let _ = h.join();
+    }
 
-    let output_text = if let Ok(b) = stdout_buf.lock() {
-        b.clone()
+    let run_status = if handle.is_cancelled() {
+        RunStatus::Cancelled
+    } else if status.success() {
+        RunStatus::Succeeded
     } else {
-        String::new()
+        RunStatus::Failed
     };
-    
-    let complete_event_name = format!("{}-complete", cli_name);
-
-    if status.success() {
-        let _ = window.emit(&complete_event_name, &serde_json::json!({
-            "runId": run_id,
-            "ok": true,
-            "output": output_text,
-        }));
-        Ok(())
-    } else {
-        let _ = window.emit(&complete_event_name, &serde_json::json!({
-            "runId": run_id,
-            "ok": false,
-            "error": output_text,
-        }));
-        Err(AppError::Command(format!("{} exec failed", cli_name)))
+    let text = answer.lock().map(|mut a| std::mem::take(&mut *a).into_text()).unwrap_or_default();
+    events.exited(&status, run_status, text, take_buffer(&stdout_buf), take_buffer(&stderr_buf));
+    match run_status {
+        RunStatus::Failed => Err(AppError::Command(format!("{} exec failed", provider.id()))),
+        _ => Ok(()),
     }
 }
 
-fn run_external_cli_login_stream(window: Window, cli_name: &str, run_id: String) -> Result<()> {
-    let spawn = || -> std::io::Result<Child> {
-        if let Some(bin_path) = resolve_binary_path(cli_name) {
-            let mut cmd = Command::new(&bin_path);
-            cmd.arg("login");
-            cmd.stdin(Stdio::inherit()).stdout(Stdio::piped()).stderr(Stdio::piped());
-            return cmd.spawn();
-        }
-        let mut cmd = Command::new("/bin/zsh");
-        cmd.arg("-lc").arg(format!("{} login", cli_name));
-        cmd.stdin(Stdio::inherit()).stdout(Stdio::piped()).stderr(Stdio::piped());
-        cmd.spawn()
-    };
-
-    let mut child = spawn().map_err(AppError::Io)?;
+fn run_external_cli_login_stream(window: Window, provider: &dyn AiProvider, run_id: String) -> Result<()> {
+    let args = provider
+        .login_args()
+        .ok_or_else(|| AppError::Command(format!("{} has no login command", provider.name())))?;
+    let events = RunEvents::new(window, run_id, provider.id());
+    let mut child = spawn_cli(provider, &args, None, Stdio::inherit(), false).map_err(AppError::Io)?;
+    events.lifecycle(RunLifecycle::Spawned { pid: child.id() });
     let stdout_buf = Arc::new(Mutex::new(String::new()));
+    let stderr_buf = Arc::new(Mutex::new(String::new()));
     let mut join_handles = vec![];
 
     if let Some(out) = child.stdout.take() {
-        let win = window.clone();
-        let rid = run_id.clone();
-        let buf = stdout_buf.clone();
-        let stream_event_name = format!("{}-stream", cli_name);
-        
-        let h = std::thread::spawn(move || {
-            let reader = BufReader::new(out);
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    let cleaned = strip_ansi(&line);
-                     if let Ok(mut b) = buf.lock() { b.push_str(&cleaned); b.push('\n'); }
-                    let _ = win.emit(&stream_event_name, &serde_json::json!({
-                        "runId": rid,
-                        "channel": "stdout",
-                        "data": format!("{}\n", cleaned),
-                    }));
-                }
-            }
-        });
-        join_handles.push(h);
+        join_handles.push(stream_lines(out, "stdout", events.clone(), stdout_buf.clone(), |_| {}));
+    }
+    if let Some(err) = child.stderr.take() {
+        join_handles.push(stream_lines(err, "stderr", events.clone(), stderr_buf.clone(), |_| {}));
     }
-    
+
     let status = child.wait().map_err(AppError::Io)?;
     for h in join_handles { let _ = h.join(); }
 
-    let output = if let Ok(b) = stdout_buf.lock() { b.clone() } else { String::new() };
-    let complete_event_name = format!("{}-complete", cli_name);
-
+    let stdout = take_buffer(&stdout_buf);
+    let run_status = if status.success() { RunStatus::Succeeded } else { RunStatus::Failed };
+    events.exited(&status, run_status, stdout.clone(), stdout, take_buffer(&stderr_buf));
     if status.success() {
-        let _ = window.emit(&complete_event_name, &serde_json::json!({
-            "runId": run_id,
-            "ok": true,
-            "output": output,
-        }));
         Ok(())
     } else {
-        let _ = window.emit(&complete_event_name, &serde_json::json!({
-            "runId": run_id,
-            "ok": false,
-            "error": output,
-        }));
-        Err(AppError::Command(format!("{} login failed", cli_name)))
+        Err(AppError::Command(format!("{} login failed", provider.id())))
     }
 }
@@ -1,39 +1,142 @@
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::core::atomic_write::write_atomic;
+use crate::core::dir_listing::{self, DirEntry, ReadDirOptions};
+use crate::core::encoding::{self, DecodedText};
+use crate::core::file_ops::{self, OverwritePolicy};
+use crate::core::file_probe::{self, FileStat, DEFAULT_MAX_READ_BYTES};
+use crate::core::file_version::{self, FileVersion};
+use crate::core::line_endings::{self, LineEnding, LineEndingInfo};
+use crate::core::local_history::LocalHistory;
+use crate::core::trash::{self, Removal, Trash};
 use crate::error::{AppError, Result};
 
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionedText {
+    pub content: String,
+    /// Pass back to `write_file` as `expected_version` to detect concurrent edits
+    pub version: FileVersion,
+}
+
+/// Result of a save
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedFile {
+    /// Pass back as `expected_version` on the next save
+    pub version: FileVersion,
+    /// The file was saved, but its previous contents could not be kept in local history
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history_error: Option<AppError>,
+}
+
+/// Decoded file contents plus the formatting details needed to save it back unchanged
+#[derive(Debug, Clone, Serialize)]
+pub struct TextFile {
+    #[serde(flatten)]
+    pub decoded: DecodedText,
+    #[serde(flatten)]
+    pub line_endings: LineEndingInfo,
+    pub version: FileVersion,
+}
+
+/// Read at most `max_bytes` (default `DEFAULT_MAX_READ_BYTES`); see `file_version::read_limited`
+fn read_limited(path: &str, max_bytes: Option<u64>, truncate: Option<bool>) -> Result<(Vec<u8>, FileVersion)> {
+    file_version::read_limited(
+        Path::new(path),
+        max_bytes.unwrap_or(DEFAULT_MAX_READ_BYTES),
+        truncate.unwrap_or(false),
+    )
+}
+
+/// Local history for saves; `None` when the app data directory is unknown
+fn local_history(app: &AppHandle) -> Option<LocalHistory> {
+    app.path().app_data_dir().ok().map(|dir| LocalHistory::in_app_data(&dir))
+}
+
+/// Check the precondition, snapshot the old contents into local history, write
+/// atomically and return the version now on disk
+fn write_versioned(
+    history: Option<&LocalHistory>,
+    path: &str,
+    bytes: &[u8],
+    expected_version: Option<FileVersion>,
+) -> Result<SavedFile> {
+    let path = Path::new(path);
+    if let Some(expected) = expected_version.as_ref() {
+        file_version::ensure_unchanged(path, expected)?;
+    }
+    // A history failure must never prevent saving; the caller is told instead
+    let history_error = match (history, std::path::absolute(path)) {
+        (Some(history), Ok(key)) => history.record_current(&key).err(),
+        _ => None,
+    };
+    write_atomic(path, bytes)?;
+    let version = FileVersion::from_bytes(&std::fs::metadata(path)?, bytes);
+    Ok(SavedFile { version, history_error })
+}
+
 #[tauri::command]
-pub fn read_file(path: String) -> Result<String> {
-    let bytes = std::fs::read(&path).map_err(|e| AppError::Io(e))?;
-    Ok(String::from_utf8_lossy(&bytes).to_string())
+pub fn read_file(path: String, max_bytes: Option<u64>, truncate: Option<bool>) -> Result<VersionedText> {
+    let (bytes, version) = read_limited(&path, max_bytes, truncate)?;
+    Ok(VersionedText {
+        content: String::from_utf8_lossy(&bytes).to_string(),
+        version,
+    })
 }
 
 #[tauri::command]
-pub fn write_file(path: String, content: String) -> Result<()> {
-    std::fs::write(path, content)?;
-    Ok(())
+pub fn stat_file(path: String) -> Result<FileStat> {
+    file_probe::stat(Path::new(&path))
 }
 
 #[tauri::command]
-pub fn read_dir(path: String) -> Result<Vec<serde_json::Value>> {
-    let mut entries = Vec::new();
-    let dir = std::fs::read_dir(path)?;
-
-    for entry in dir {
-        let entry = entry?;
-        let metadata = entry.metadata()?;
-        let path = entry.path();
-        let name = path.file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("")
-            .to_string();
-
-        entries.push(serde_json::json!({
-            "name": name,
-            "path": path.to_string_lossy(),
-            "is_dir": metadata.is_dir(),
-        }));
-    }
+pub fn write_file(
+    app: AppHandle,
+    path: String,
+    content: String,
+    line_ending: Option<LineEnding>,
+    final_newline: Option<bool>,
+    expected_version: Option<FileVersion>,
+) -> Result<SavedFile> {
+    let content = line_endings::apply(&content, line_ending, final_newline);
+    write_versioned(local_history(&app).as_ref(), &path, content.as_bytes(), expected_version)
+}
+
+#[tauri::command]
+pub fn read_file_with_encoding(
+    path: String,
+    max_bytes: Option<u64>,
+    truncate: Option<bool>,
+) -> Result<TextFile> {
+    let (bytes, version) = read_limited(&path, max_bytes, truncate)?;
+    let decoded = encoding::decode(&bytes);
+    let line_endings = line_endings::detect(&decoded.content);
+    Ok(TextFile { decoded, line_endings, version })
+}
 
-    Ok(entries)
+#[tauri::command]
+pub fn write_file_with_encoding(
+    app: AppHandle,
+    path: String,
+    content: String,
+    encoding: Option<String>,
+    bom: Option<bool>,
+    line_ending: Option<LineEnding>,
+    final_newline: Option<bool>,
+    expected_version: Option<FileVersion>,
+) -> Result<SavedFile> {
+    let content = line_endings::apply(&content, line_ending, final_newline);
+    let label = encoding.unwrap_or_else(|| "UTF-8".to_string());
+    let bytes = encoding::encode(&content, &label, bom.unwrap_or(false))?;
+    write_versioned(local_history(&app).as_ref(), &path, &bytes, expected_version)
+}
+
+#[tauri::command]
+pub fn read_dir(path: String, options: Option<ReadDirOptions>) -> Result<Vec<DirEntry>> {
+    dir_listing::read_dir(Path::new(&path), &options.unwrap_or_default())
 }
 
 #[tauri::command]
@@ -44,22 +147,44 @@ pub fn create_new_file() -> Result<String> {
     Ok(temp_name)
 }
 
+/// Move a file or folder to the trash, or delete it for good when `permanent` is set or
+/// the platform has no trash. Returns the trash entry so the deletion can be undone, and
+/// whether it was permanent; a missing path is not an error.
 #[tauri::command]
-pub fn remove_file(path: String) -> Result<()> {
-    match std::fs::remove_file(&path) {
-        Ok(_) => Ok(()),
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
-        Err(e) => Err(e.into()),
-    }
+pub fn remove_file(path: String, permanent: Option<bool>) -> Result<Removal> {
+    let trash = Trash::for_deletion(permanent.unwrap_or(false))?;
+    trash::remove(Path::new(&path), trash.as_ref())
 }
 
+/// Empty a folder, keeping the folder itself; its old contents are removed like
+/// `remove_file` does
 #[tauri::command]
-pub fn clear_dir(path: String) -> Result<()> {
-    match std::fs::remove_dir_all(&path) {
-        Ok(_) => {},
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {},
-        Err(e) => return Err(e.into()),
-    }
+pub fn clear_dir(path: String, permanent: Option<bool>) -> Result<Removal> {
+    let trash = Trash::for_deletion(permanent.unwrap_or(false))?;
+    let removal = trash::remove(Path::new(&path), trash.as_ref())?;
     std::fs::create_dir_all(&path)?;
-    Ok(())
+    Ok(removal)
+}
+
+#[tauri::command]
+pub fn create_dir(path: String) -> Result<()> {
+    file_ops::create_dir(Path::new(&path))
+}
+
+/// Rename in place or move to another folder
+#[tauri::command]
+pub fn rename_path(from: String, to: String, overwrite: Option<OverwritePolicy>) -> Result<()> {
+    file_ops::move_path(Path::new(&from), Path::new(&to), overwrite.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn copy_path(from: String, to: String, overwrite: Option<OverwritePolicy>) -> Result<()> {
+    file_ops::copy_path(Path::new(&from), Path::new(&to), overwrite.unwrap_or_default())
+}
+
+/// Returns the path of the new copy
+#[tauri::command]
+pub fn duplicate_path(path: String) -> Result<String> {
+    let copy = file_ops::duplicate_path(Path::new(&path))?;
+    Ok(copy.to_string_lossy().to_string())
 }
@@ -0,0 +1,40 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Manager};
+
+use crate::core::encoding::{self, DecodedText};
+use crate::core::file_version::FileVersion;
+use crate::core::local_history::{HistoryEntry, LocalHistory};
+use crate::error::Result;
+
+fn local_history(app: &AppHandle) -> Result<LocalHistory> {
+    Ok(LocalHistory::in_app_data(&app.path().app_data_dir()?))
+}
+
+/// Timelines are keyed by absolute path
+fn history_key(path: &str) -> Result<PathBuf> {
+    Ok(std::path::absolute(Path::new(path))?)
+}
+
+/// Snapshots of `path` taken before each save, newest first
+#[tauri::command]
+pub fn list_file_history(app: AppHandle, path: String) -> Result<Vec<HistoryEntry>> {
+    local_history(&app)?.timeline(&history_key(&path)?)
+}
+
+#[tauri::command]
+pub fn read_history_snapshot(app: AppHandle, path: String, id: String) -> Result<DecodedText> {
+    let bytes = local_history(&app)?.read(&history_key(&path)?, &id)?;
+    Ok(encoding::decode(&bytes))
+}
+
+/// Put a snapshot back on disk; the replaced contents become a new snapshot
+#[tauri::command]
+pub fn restore_history_snapshot(
+    app: AppHandle,
+    path: String,
+    id: String,
+    expected_version: Option<FileVersion>,
+) -> Result<FileVersion> {
+    local_history(&app)?.restore(&history_key(&path)?, &id, expected_version.as_ref())
+}
@@ -0,0 +1,98 @@
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::{Emitter, State, Window};
+
+use crate::app_state::AppState;
+use crate::core::large_file::{IndexStatus, LargeFile, SearchHit};
+use crate::error::{AppError, Result};
+
+/// Emit index progress at most once per this many bytes scanned
+const PROGRESS_STEP: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LargeFileHandle {
+    pub id: String,
+    pub path: String,
+    pub size: u64,
+}
+
+fn get_file(state: &State<'_, AppState>, id: &str) -> Result<std::sync::Arc<LargeFile>> {
+    state
+        .large_files
+        .lock()
+        .map_err(|e| AppError::Command(e.to_string()))?
+        .get_file(id)
+}
+
+/// Open a file for paged reading and start indexing its lines in the background.
+/// Progress is reported with `large-file-index` events.
+#[tauri::command]
+pub fn large_file_open(state: State<'_, AppState>, window: Window, path: String) -> Result<LargeFileHandle> {
+    let file = LargeFile::open(Path::new(&path))?;
+    let size = file.size;
+    let id = state
+        .large_files
+        .lock()
+        .map_err(|e| AppError::Command(e.to_string()))?
+        .add_file(file.clone());
+
+    let rid = id.clone();
+    std::thread::spawn(move || {
+        let mut next_report = 0u64;
+        let result = file.build_index(|status| {
+            if status.complete || status.indexed_bytes >= next_report {
+                next_report = status.indexed_bytes + PROGRESS_STEP;
+                let _ = window.emit("large-file-index", &serde_json::json!({
+                    "id": rid,
+                    "status": status,
+                }));
+            }
+        });
+        if let Err(e) = result {
+            let _ = window.emit("large-file-index", &serde_json::json!({
+                "id": rid,
+                "error": e.to_string(),
+            }));
+        }
+    });
+
+    Ok(LargeFileHandle { id, path, size })
+}
+
+/// Lines `start..end` (0-based, end exclusive)
+#[tauri::command]
+pub fn large_file_lines(state: State<'_, AppState>, id: String, start: u64, end: u64) -> Result<Vec<String>> {
+    get_file(&state, &id)?.read_lines(start, end)
+}
+
+#[tauri::command]
+pub fn large_file_status(state: State<'_, AppState>, id: String) -> Result<IndexStatus> {
+    Ok(get_file(&state, &id)?.status())
+}
+
+#[tauri::command]
+pub async fn large_file_search(
+    state: State<'_, AppState>,
+    id: String,
+    query: String,
+    from_offset: Option<u64>,
+    case_sensitive: Option<bool>,
+) -> Result<Option<SearchHit>> {
+    let file = get_file(&state, &id)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        file.search(&query, from_offset.unwrap_or(0), case_sensitive.unwrap_or(true))
+    })
+    .await
+    .map_err(|e| AppError::Command(format!("Failed to join large file search worker: {}", e)))?
+}
+
+#[tauri::command]
+pub fn large_file_close(state: State<'_, AppState>, id: String) -> Result<()> {
+    state
+        .large_files
+        .lock()
+        .map_err(|e| AppError::Command(e.to_string()))?
+        .close_file(&id)
+}
@@ -1,3 +1,13 @@
 pub mod app;
 pub mod external_cli;
-pub mod file_system;
\ No newline at end of file
+pub mod file_system;
+pub mod history;
+pub mod large_file;
+pub mod replace;
+pub mod search;
+pub mod session;
+pub mod settings;
+pub mod trash;
+pub mod watcher;
+pub mod workspace;
+pub mod workspace_index;
\ No newline at end of file
@@ -0,0 +1,59 @@
+use std::path::Path;
+
+use tauri::{AppHandle, Manager, State};
+
+use crate::app_state::AppState;
+use crate::commands::workspace::apply_workspace_exclude;
+use crate::core::project_replace::{self, ApprovedFile, ReplaceBackup, ReplaceBackups, ReplaceOutcome, ReplacePreview};
+use crate::core::project_search::SearchQuery;
+use crate::error::{AppError, Result};
+
+fn backups(app: &AppHandle) -> Result<ReplaceBackups> {
+    Ok(ReplaceBackups::new(app.path().app_data_dir()?.join("replace-backups")))
+}
+
+/// Every edit replacing `query` with `replacement` under `root` would make, as diff hunks.
+/// Nothing is written.
+#[tauri::command]
+pub async fn replace_preview(
+    state: State<'_, AppState>,
+    root: String,
+    mut query: SearchQuery,
+    replacement: String,
+) -> Result<ReplacePreview> {
+    apply_workspace_exclude(&state, &mut query)?;
+    tauri::async_runtime::spawn_blocking(move || project_replace::preview(Path::new(&root), &query, &replacement))
+        .await
+        .map_err(|e| AppError::Command(format!("Failed to join replace worker: {}", e)))?
+}
+
+/// Apply the approved edits from a preview, all or nothing
+#[tauri::command]
+pub async fn replace_apply(
+    app: AppHandle,
+    query: SearchQuery,
+    replacement: String,
+    files: Vec<ApprovedFile>,
+) -> Result<ReplaceOutcome> {
+    let backups = backups(&app)?;
+    tauri::async_runtime::spawn_blocking(move || backups.apply(&query, &replacement, &files))
+        .await
+        .map_err(|e| AppError::Command(format!("Failed to join replace worker: {}", e)))?
+}
+
+/// Revert a whole replace; returns the restored paths
+#[tauri::command]
+pub fn replace_undo(app: AppHandle, backup_id: String) -> Result<Vec<String>> {
+    backups(&app)?.undo(&backup_id)
+}
+
+#[tauri::command]
+pub fn list_replace_backups(app: AppHandle) -> Result<Vec<ReplaceBackup>> {
+    backups(&app)?.list()
+}
+
+/// Drop a replace backup without undoing it
+#[tauri::command]
+pub fn discard_replace_backup(app: AppHandle, backup_id: String) -> Result<()> {
+    backups(&app)?.discard(&backup_id)
+}
@@ -0,0 +1,71 @@
+use std::path::Path;
+
+use tauri::{Emitter, State, Window};
+
+use crate::app_state::AppState;
+use crate::commands::workspace::apply_workspace_exclude;
+use crate::core::project_search::{self, SearchQuery, SearchSummary};
+use crate::error::{AppError, Result};
+
+/// Find `query` in every file under `root`. Matches arrive in batches as
+/// `search-stream` events and the run ends with a `search-complete` event;
+/// `search_cancel` with the same `run_id` stops it early.
+#[tauri::command]
+pub async fn search_in_files(
+    state: State<'_, AppState>,
+    window: Window,
+    run_id: String,
+    root: String,
+    mut query: SearchQuery,
+) -> Result<SearchSummary> {
+    apply_workspace_exclude(&state, &mut query)?;
+    let searches = state.searches.clone();
+    let cancelled = searches
+        .lock()
+        .map_err(|e| AppError::Command(e.to_string()))?
+        .add_search(run_id.clone());
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let win = window.clone();
+        let rid = run_id.clone();
+        let result = project_search::search(Path::new(&root), &query, &cancelled, |files| {
+            let _ = win.emit("search-stream", &serde_json::json!({
+                "runId": rid,
+                "files": files,
+            }));
+        });
+
+        if let Ok(mut manager) = searches.lock() {
+            manager.remove_search(&run_id);
+        }
+
+        match &result {
+            Ok(summary) => {
+                let _ = window.emit("search-complete", &serde_json::json!({
+                    "runId": run_id,
+                    "ok": true,
+                    "summary": summary,
+                }));
+            }
+            Err(e) => {
+                let _ = window.emit("search-complete", &serde_json::json!({
+                    "runId": run_id,
+                    "ok": false,
+                    "error": e,
+                }));
+            }
+        }
+        result
+    })
+    .await
+    .map_err(|e| AppError::Command(format!("Failed to join search worker: {}", e)))?
+}
+
+#[tauri::command]
+pub fn search_cancel(state: State<'_, AppState>, run_id: String) -> Result<()> {
+    state
+        .searches
+        .lock()
+        .map_err(|e| AppError::Command(e.to_string()))?
+        .cancel_search(&run_id)
+}
@@ -0,0 +1,57 @@
+use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize, Window};
+
+use crate::core::session::{Session, SessionStore, WindowGeometry};
+use crate::error::Result;
+
+fn session_store(app: &AppHandle) -> Result<SessionStore> {
+    Ok(SessionStore::new(app.path().app_data_dir()?))
+}
+
+fn window_geometry(window: &Window) -> Option<WindowGeometry> {
+    let position = window.outer_position().ok()?;
+    let size = window.inner_size().ok()?;
+    Some(WindowGeometry {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        maximized: window.is_maximized().unwrap_or(false),
+        fullscreen: window.is_fullscreen().unwrap_or(false),
+    })
+}
+
+/// Persist the session; the window geometry is taken from the calling window
+/// unless the session already carries one
+#[tauri::command]
+pub fn save_session(app: AppHandle, window: Window, mut session: Session) -> Result<()> {
+    if session.window.is_none() {
+        session.window = window_geometry(&window);
+    }
+    session_store(&app)?.save(&session)
+}
+
+/// The previous session, migrated from the old drafts directory if needed, with its
+/// window geometry applied to the calling window
+#[tauri::command]
+pub fn restore_session(app: AppHandle, window: Window) -> Result<Option<Session>> {
+    let legacy_drafts = app.path().app_data_dir()?.join("drafts");
+    let session = session_store(&app)?.load(Some(&legacy_drafts))?;
+    if let Some(geometry) = session.as_ref().and_then(|s| s.window.as_ref()) {
+        if geometry.width > 0 && geometry.height > 0 {
+            let _ = window.set_size(PhysicalSize::new(geometry.width, geometry.height));
+            let _ = window.set_position(PhysicalPosition::new(geometry.x, geometry.y));
+        }
+        if geometry.maximized {
+            let _ = window.maximize();
+        }
+        if geometry.fullscreen {
+            let _ = window.set_fullscreen(true);
+        }
+    }
+    Ok(session)
+}
+
+#[tauri::command]
+pub fn discard_session(app: AppHandle) -> Result<()> {
+    session_store(&app)?.discard()
+}
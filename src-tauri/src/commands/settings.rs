@@ -0,0 +1,166 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::app_state::AppState;
+use crate::config::codex_config_path;
+use crate::core::codex_config::{self, TEMPLATES_NAME};
+use crate::core::settings::{self, SettingsSnapshot, SettingsStore};
+use crate::core::workspace;
+use crate::error::{AppError, Result};
+
+const SETTINGS_NAME: &str = "settings.json";
+
+fn settings_store(app: &AppHandle) -> Result<SettingsStore> {
+    Ok(SettingsStore::new(app.path().app_config_dir()?.join(SETTINGS_NAME)))
+}
+
+/// Effective settings with the current workspace's overrides on top, and the Codex config
+pub(crate) fn current_settings(app: &AppHandle, state: &AppState) -> Result<SettingsSnapshot> {
+    let current = state
+        .current_workspace
+        .lock()
+        .map_err(|e| AppError::Command(e.to_string()))?
+        .clone();
+    // A workspace that cannot be read any more just stops contributing overrides
+    let opened = current.and_then(|path| workspace::load(Path::new(&path)).ok());
+    let overrides = opened.as_ref().map(|o| (o.path.as_str(), &o.workspace.settings));
+    let mut snapshot = settings::snapshot(&settings_store(app)?, overrides)?;
+    let codex = state.codex_config.lock().map_err(|e| AppError::Command(e.to_string()))?;
+    snapshot.codex = codex.config().clone();
+    snapshot.problems.extend(codex.problems().iter().cloned());
+    Ok(snapshot)
+}
+
+/// Send the effective settings to every window, with the keys changed since the
+/// last time. Nothing is sent when neither the values nor the problems changed.
+pub(crate) fn broadcast_settings(app: &AppHandle, state: &AppState) -> Result<SettingsSnapshot> {
+    let mut snapshot = current_settings(app, state)?;
+    let mut last = state.last_settings.lock().map_err(|e| AppError::Command(e.to_string()))?;
+    snapshot.changed = match last.as_ref() {
+        Some(last) => {
+            let mut changed = settings::changed_keys(&last.settings, &snapshot.settings);
+            let codex = settings::changed_keys(&last.codex, &snapshot.codex);
+            changed.extend(codex.into_iter().map(|key| format!("codex.{}", key)));
+            changed
+        }
+        None => Vec::new(),
+    };
+    let same_problems = last.as_ref().map(|l| l.problems == snapshot.problems).unwrap_or(false);
+    if !same_problems || !snapshot.changed.is_empty() {
+        let _ = app.emit("settings-changed", &snapshot);
+    }
+    *last = Some(snapshot.clone());
+    Ok(snapshot)
+}
+
+/// Pick up a save of `config.toml` or `ai_templates.json`. Valid templates replace
+/// the `ai.templates` setting; invalid files are reported and otherwise ignored.
+fn reload_codex_file(app: &AppHandle, path: &Path) -> Result<()> {
+    let state = app.state::<AppState>();
+    let templates = state
+        .codex_config
+        .lock()
+        .map_err(|e| AppError::Command(e.to_string()))?
+        .reload(path)?;
+    if let Some(templates) = templates {
+        let value = serde_json::to_value(templates)?;
+        let store = settings_store(app)?;
+        let (user, _) = store.load()?;
+        if settings::get_path(&Value::Object(user), "ai.templates") != Some(&value) {
+            store.set("ai.templates", value)?;
+        }
+    }
+    broadcast_settings(app, &state)?;
+    Ok(())
+}
+
+/// Show a failure to read or watch a Codex file with the other settings problems
+fn report_codex_problem(app: &AppHandle, path: &Path, error: &AppError) {
+    let state = app.state::<AppState>();
+    if let Ok(mut codex) = state.codex_config.lock() {
+        codex.report(path, error.to_string());
+    }
+    // Without a way to send it there is nobody left to tell
+    let _ = broadcast_settings(app, &state);
+}
+
+/// Read the Codex config and templates, then reload them whenever they are saved.
+/// Hot reload is a convenience: when it cannot start, the reason is reported as a
+/// settings problem and the app goes on without it.
+pub(crate) fn watch_codex_config(app: &AppHandle) {
+    let config = match codex_config_path() {
+        Ok(path) => PathBuf::from(path),
+        Err(e) => return report_codex_problem(app, Path::new(codex_config::CONFIG_NAME), &e),
+    };
+    let dir = config.parent().map(Path::to_path_buf).unwrap_or_default();
+    if let Err(e) = start_codex_watch(app, &config, &dir) {
+        report_codex_problem(app, &dir, &e);
+    }
+}
+
+/// The Codex directory is created if needed so files Codex writes later are seen
+fn start_codex_watch(app: &AppHandle, config: &Path, dir: &Path) -> Result<()> {
+    for path in [config.to_path_buf(), dir.join(TEMPLATES_NAME)] {
+        if let Err(e) = reload_codex_file(app, &path) {
+            report_codex_problem(app, &path, &e);
+        }
+    }
+    std::fs::create_dir_all(dir)?;
+
+    let handle = app.clone();
+    let debouncer = codex_config::watch(dir, move |path| {
+        if let Err(e) = reload_codex_file(&handle, &path) {
+            report_codex_problem(&handle, &path, &e);
+        }
+    })?;
+    *app
+        .state::<AppState>()
+        .codex_watcher
+        .lock()
+        .map_err(|e| AppError::Command(e.to_string()))? = Some(debouncer);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn settings_get(app: AppHandle, state: State<'_, AppState>) -> Result<SettingsSnapshot> {
+    current_settings(&app, &state)
+}
+
+#[tauri::command]
+pub fn settings_set(app: AppHandle, state: State<'_, AppState>, key: String, value: Value) -> Result<SettingsSnapshot> {
+    settings_store(&app)?.set(&key, value)?;
+    broadcast_settings(&app, &state)
+}
+
+/// Reset one setting, or all of them when `key` is omitted
+#[tauri::command]
+pub fn settings_reset(app: AppHandle, state: State<'_, AppState>, key: Option<String>) -> Result<SettingsSnapshot> {
+    settings_store(&app)?.reset(key.as_deref())?;
+    broadcast_settings(&app, &state)
+}
+
+/// One-time move of settings the frontend used to keep in localStorage.
+/// Ignored once the settings file exists.
+#[tauri::command]
+pub fn settings_import(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    values: Map<String, Value>,
+) -> Result<SettingsSnapshot> {
+    let store = settings_store(&app)?;
+    if store.exists() {
+        return current_settings(&app, &state);
+    }
+    let skipped = store.import(&values)?;
+    let mut snapshot = broadcast_settings(&app, &state)?;
+    snapshot.problems.extend(skipped);
+    Ok(snapshot)
+}
+
+/// Location of the user settings file, for opening it in an editor tab
+#[tauri::command]
+pub fn settings_path(app: AppHandle) -> Result<String> {
+    Ok(settings_store(&app)?.path().to_string_lossy().to_string())
+}
@@ -0,0 +1,28 @@
+use std::path::Path;
+
+use crate::core::file_ops::OverwritePolicy;
+use crate::core::trash::{Trash, TrashItem};
+use crate::error::Result;
+
+#[tauri::command]
+pub fn trash_path(path: String) -> Result<TrashItem> {
+    Trash::for_user()?.trash(Path::new(&path))
+}
+
+#[tauri::command]
+pub fn list_trash() -> Result<Vec<TrashItem>> {
+    Trash::for_user()?.list()
+}
+
+/// Returns the path the item was restored to
+#[tauri::command]
+pub fn restore_from_trash(id: String, overwrite: Option<OverwritePolicy>) -> Result<String> {
+    let path = Trash::for_user()?.restore(&id, overwrite.unwrap_or_default())?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Delete one trashed item for good
+#[tauri::command]
+pub fn purge_from_trash(id: String) -> Result<()> {
+    Trash::for_user()?.purge(&id)
+}
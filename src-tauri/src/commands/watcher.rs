@@ -0,0 +1,38 @@
+use tauri::{AppHandle, Emitter, State};
+
+use crate::app_state::AppState;
+use crate::error::{AppError, Result};
+
+/// Watch files (open tabs) and directories (expanded in the explorer) for changes.
+/// Changes arrive in every window as debounced `fs-changed`, `fs-removed` and `fs-renamed` events.
+/// Subscriptions are reference counted, so every `watch_paths` needs a matching `unwatch_paths`.
+#[tauri::command]
+pub fn watch_paths(app: AppHandle, state: State<'_, AppState>, paths: Vec<String>) -> Result<()> {
+    let mut watcher = state
+        .fs_watcher
+        .lock()
+        .map_err(|e| AppError::Command(e.to_string()))?;
+
+    if !watcher.is_started() {
+        watcher.start(move |event| {
+            let _ = app.emit(event.event_name(), &event);
+        })?;
+    }
+
+    for path in paths {
+        watcher.subscribe(&path)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn unwatch_paths(state: State<'_, AppState>, paths: Vec<String>) -> Result<()> {
+    let mut watcher = state
+        .fs_watcher
+        .lock()
+        .map_err(|e| AppError::Command(e.to_string()))?;
+    for path in paths {
+        watcher.unsubscribe(&path)?;
+    }
+    Ok(())
+}
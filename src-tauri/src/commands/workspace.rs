@@ -0,0 +1,130 @@
+use std::path::Path;
+
+use tauri::{AppHandle, Manager, State};
+
+use crate::app_state::AppState;
+use crate::commands::settings::broadcast_settings;
+use crate::core::project_search::SearchQuery;
+use crate::core::session::Session;
+use crate::core::workspace::{self, OpenedWorkspace, RecentWorkspace, RecentWorkspaces, Workspace};
+use crate::error::{AppError, Result};
+
+fn recent_workspaces(app: &AppHandle) -> Result<RecentWorkspaces> {
+    Ok(RecentWorkspaces::new(&app.path().app_data_dir()?))
+}
+
+/// Make `opened` the current workspace and move it to the top of the recent list.
+/// Its settings overrides take effect in every window.
+fn make_current(app: &AppHandle, state: &AppState, opened: &OpenedWorkspace) -> Result<()> {
+    *state
+        .current_workspace
+        .lock()
+        .map_err(|e| AppError::Command(e.to_string()))? = Some(opened.path.clone());
+    recent_workspaces(app)?.touch(&opened.path, chrono::Utc::now().timestamp_millis() as u64)?;
+    broadcast_settings(app, state)?;
+    Ok(())
+}
+
+/// Store `session` in the current workspace file, if a workspace is open
+fn leave_current(state: &AppState, session: Option<Session>) -> Result<()> {
+    let current = state
+        .current_workspace
+        .lock()
+        .map_err(|e| AppError::Command(e.to_string()))?
+        .clone();
+    if let (Some(path), Some(session)) = (current, session) {
+        let mut opened = workspace::load(Path::new(&path))?;
+        opened.workspace.session = Some(session);
+        workspace::save(Path::new(&path), &opened.workspace)?;
+    }
+    Ok(())
+}
+
+/// Add the current workspace's excludes to a project-wide search or replace
+pub(crate) fn apply_workspace_exclude(state: &AppState, query: &mut SearchQuery) -> Result<()> {
+    let current = state
+        .current_workspace
+        .lock()
+        .map_err(|e| AppError::Command(e.to_string()))?
+        .clone();
+    // A workspace that cannot be read any more just stops contributing excludes
+    if let Some(opened) = current.and_then(|path| workspace::load(Path::new(&path)).ok()) {
+        opened.workspace.apply_exclude(query);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn open_workspace(app: AppHandle, state: State<'_, AppState>, path: String) -> Result<OpenedWorkspace> {
+    let opened = workspace::load(Path::new(&path))?;
+    make_current(&app, &state, &opened)?;
+    Ok(opened)
+}
+
+/// Save to `path`, or to the current workspace file when omitted ("Save Workspace")
+#[tauri::command]
+pub fn save_workspace(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: Option<String>,
+    workspace: Workspace,
+) -> Result<OpenedWorkspace> {
+    let path = match path {
+        Some(path) => path,
+        None => state
+            .current_workspace
+            .lock()
+            .map_err(|e| AppError::Command(e.to_string()))?
+            .clone()
+            .ok_or_else(|| AppError::Config("no workspace is open".to_string()))?,
+    };
+    let saved = workspace::save(Path::new(&path), &workspace)?;
+    make_current(&app, &state, &saved)?;
+    Ok(saved)
+}
+
+/// Leave the current workspace, remembering `session` in it, and open the one at `path`
+#[tauri::command]
+pub fn switch_workspace(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    session: Option<Session>,
+) -> Result<OpenedWorkspace> {
+    // Load first so a bad target leaves the current workspace untouched
+    let opened = workspace::load(Path::new(&path))?;
+    leave_current(&state, session)?;
+    make_current(&app, &state, &opened)?;
+    Ok(opened)
+}
+
+#[tauri::command]
+pub fn close_workspace(app: AppHandle, state: State<'_, AppState>, session: Option<Session>) -> Result<()> {
+    leave_current(&state, session)?;
+    *state
+        .current_workspace
+        .lock()
+        .map_err(|e| AppError::Command(e.to_string()))? = None;
+    broadcast_settings(&app, &state)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn current_workspace(state: State<'_, AppState>) -> Result<Option<OpenedWorkspace>> {
+    let current = state
+        .current_workspace
+        .lock()
+        .map_err(|e| AppError::Command(e.to_string()))?
+        .clone();
+    current.map(|path| workspace::load(Path::new(&path))).transpose()
+}
+
+#[tauri::command]
+pub fn list_recent_workspaces(app: AppHandle) -> Result<Vec<RecentWorkspace>> {
+    Ok(recent_workspaces(&app)?.list())
+}
+
+#[tauri::command]
+pub fn remove_recent_workspace(app: AppHandle, path: String) -> Result<Vec<RecentWorkspace>> {
+    recent_workspaces(&app)?.remove(&path)
+}
@@ -0,0 +1,46 @@
+use tauri::{AppHandle, Emitter, State};
+
+use crate::app_state::AppState;
+use crate::core::workspace_index::{self, FileMatch, WorkspaceIndexStatus, DEFAULT_QUERY_LIMIT};
+use crate::error::{AppError, Result};
+
+/// Index the opened folders for "Go to File". Replaces the previous set of roots;
+/// scans run in the background and report through `workspace-index` events.
+#[tauri::command]
+pub fn index_workspace(app: AppHandle, state: State<'_, AppState>, roots: Vec<String>) -> Result<Vec<WorkspaceIndexStatus>> {
+    let mut index = state
+        .workspace_index
+        .lock()
+        .map_err(|e| AppError::Command(e.to_string()))?;
+
+    if !index.is_started() {
+        index.start(move |status| {
+            let _ = app.emit("workspace-index", &status);
+        })?;
+    }
+    index.set_roots(&roots)
+}
+
+#[tauri::command]
+pub fn workspace_index_status(state: State<'_, AppState>) -> Result<Vec<WorkspaceIndexStatus>> {
+    Ok(state
+        .workspace_index
+        .lock()
+        .map_err(|e| AppError::Command(e.to_string()))?
+        .status())
+}
+
+/// Fuzzy-match file paths in the indexed folders, best first (at most `limit`, default 100)
+#[tauri::command]
+pub async fn find_files(state: State<'_, AppState>, query: String, limit: Option<usize>) -> Result<Vec<FileMatch>> {
+    let roots = state
+        .workspace_index
+        .lock()
+        .map_err(|e| AppError::Command(e.to_string()))?
+        .roots();
+    tauri::async_runtime::spawn_blocking(move || {
+        workspace_index::query(&roots, &query, limit.unwrap_or(DEFAULT_QUERY_LIMIT))
+    })
+    .await
+    .map_err(|e| AppError::Command(format!("Failed to join file search worker: {}", e)))
+}
@@ -1,3 +1,7 @@
+use std::path::Path;
+
+use crate::core::atomic_write::write_atomic;
+use crate::core::toml_doc::TomlDocument;
 use crate::error::{AppError, Result};
 
 fn value_is_literal(val: &str) -> bool {
@@ -9,37 +13,29 @@ fn value_is_literal(val: &str) -> bool {
     false
 }
 
-fn update_toml_key(original: &str, key: &str, value: &str) -> String {
-    let mut out = String::with_capacity(original.len() + key.len() + value.len() + 8);
-    let mut replaced = false;
-    for line in original.lines() {
-        let trimmed = line.trim_start();
-        if trimmed.starts_with(key) {
-            let rest = &trimmed[key.len()..];
-            let mut idx = 0usize;
-            let rest_bytes = rest.as_bytes();
-            while idx < rest_bytes.len() && rest_bytes[idx].is_ascii_whitespace() { idx += 1; }
-            if idx < rest_bytes.len() && rest_bytes[idx] == b'=' {
-                out.push_str(key);
-                out.push_str(" = ");
-                if value_is_literal(value) { out.push_str(value.trim()); } else { out.push('"'); out.push_str(value); out.push('"'); }
-                out.push('\n');
-                replaced = true;
-                continue;
-            }
-        }
-        out.push_str(line);
-        out.push('\n');
+/// Interpret a value typed by the user: TOML literals keep their type, anything
+/// else is stored as a string
+fn parse_toml_value(key: &str, val: &str) -> Result<toml_edit::Value> {
+    let v = val.trim();
+    if !value_is_literal(v) {
+        return Ok(toml_edit::Value::from(val));
+    }
+    // TOML booleans are lowercase only
+    if v.eq_ignore_ascii_case("true") || v.eq_ignore_ascii_case("false") {
+        return Ok(toml_edit::Value::from(v.eq_ignore_ascii_case("true")));
     }
-    if !replaced {
-        let mut pref = String::new();
-        pref.push_str(key);
-        pref.push_str(" = ");
-        if value_is_literal(value) { pref.push_str(value.trim()); } else { pref.push('"'); pref.push_str(value); pref.push('"'); }
-        pref.push('\n');
-        pref.push_str(original);
-        pref
-    } else { out }
+    v
+        .parse::<toml_edit::Value>()
+        .map_err(|e| AppError::Config(format!("Invalid value for {}: {}", key, e.message())))
+}
+
+fn read_codex_config(path: &str) -> Result<TomlDocument> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    TomlDocument::parse(&text, path)
 }
 
 #[tauri::command]
@@ -52,13 +48,55 @@ pub fn codex_config_path() -> Result<String> {
     Ok(cfg.to_string_lossy().to_string())
 }
 
+/// Value of a key path such as `model` or `profiles.work.model`, or null when unset
+#[tauri::command]
+pub fn codex_config_get(key: String) -> Result<serde_json::Value> {
+    let doc = read_codex_config(&codex_config_path()?)?;
+    Ok(doc.get(&key)?.unwrap_or(serde_json::Value::Null))
+}
+
+/// The whole `config.toml` as JSON. A broken file fails with `invalidToml`,
+/// which carries the line and column.
+#[tauri::command]
+pub fn codex_config_read() -> Result<serde_json::Value> {
+    Ok(read_codex_config(&codex_config_path()?)?.to_json())
+}
+
+/// Names of the `[profiles.<name>]` tables, sorted
+#[tauri::command]
+pub fn codex_config_profiles() -> Result<Vec<String>> {
+    profile_names(&read_codex_config(&codex_config_path()?)?)
+}
+
+fn profile_names(doc: &TomlDocument) -> Result<Vec<String>> {
+    let profiles = match doc.get("profiles")? {
+        Some(serde_json::Value::Object(profiles)) => profiles,
+        _ => return Ok(Vec::new()),
+    };
+    Ok(profiles.into_iter().filter(|(_, v)| v.is_object()).map(|(name, _)| name).collect())
+}
+
+/// Set a key path such as `model` or `profiles.work.model`, leaving the rest of
+/// the file as it was. Returns the value as it now reads back.
+#[tauri::command]
+pub fn codex_config_set(key: String, value: String) -> Result<serde_json::Value> {
+    let path = codex_config_path()?;
+    let mut doc = read_codex_config(&path)?;
+    doc.set(&key, parse_toml_value(&key, &value)?)?;
+    write_atomic(Path::new(&path), doc.to_string().as_bytes())?;
+    Ok(doc.get(&key)?.unwrap_or(serde_json::Value::Null))
+}
+
+/// Remove a key path; returns whether it was set
 #[tauri::command]
-pub fn codex_config_set(key: String, value: String) -> Result<()> {
+pub fn codex_config_unset(key: String) -> Result<bool> {
     let path = codex_config_path()?;
-    let existing = std::fs::read_to_string(&path).unwrap_or_else(|_| String::new());
-    let updated = update_toml_key(&existing, &key, &value);
-    std::fs::write(&path, updated)?;
-    Ok(())
+    let mut doc = read_codex_config(&path)?;
+    if !doc.remove(&key)? {
+        return Ok(false);
+    }
+    write_atomic(Path::new(&path), doc.to_string().as_bytes())?;
+    Ok(true)
 }
 
 #[cfg(test)]
@@ -99,29 +137,22 @@ mod tests {
     }
 
     #[test]
-    fn test_update_toml_key_new_key() {
-        let original = "existing_key = \"value\"";
-        let result = update_toml_key(original, "new_key", "new_value");
-        assert!(result.contains("new_key = \"new_value\""));
-        assert!(result.contains("existing_key = \"value\""));
-    }
-
-    #[test]
-    fn test_update_toml_key_update_existing() {
-        let original = "key1 = \"old_value\"\nkey2 = 42";
-        let result = update_toml_key(original, "key1", "new_value");
-        assert!(result.contains("key1 = \"new_value\""));
-        assert!(result.contains("key2 = 42"));
-        assert!(!result.contains("old_value"));
+    fn test_profile_names() {
+        let text = "profile = \"work\"\n\n[profiles.work]\nmodel = \"o3\"\n\n[profiles.fast]\nmodel = \"o4-mini\"\n";
+        let doc = TomlDocument::parse(text, "config.toml").unwrap();
+        assert_eq!(profile_names(&doc).unwrap(), vec!["fast", "work"]);
+        assert!(profile_names(&TomlDocument::parse("model = \"o3\"\n", "config.toml").unwrap()).unwrap().is_empty());
     }
 
     #[test]
-    fn test_update_toml_key_literal_values() {
-        let original = "";
-        let result = update_toml_key(original, "bool_key", "true");
-        assert!(result.contains("bool_key = true"));
-        
-        let result = update_toml_key(original, "num_key", "42");
-        assert!(result.contains("num_key = 42"));
+    fn test_parse_toml_value() {
+        assert_eq!(parse_toml_value("k", "true").unwrap().as_bool(), Some(true));
+        assert_eq!(parse_toml_value("k", "FALSE").unwrap().as_bool(), Some(false));
+        assert_eq!(parse_toml_value("k", "42").unwrap().as_integer(), Some(42));
+        assert_eq!(parse_toml_value("k", "o3").unwrap().as_str(), Some("o3"));
+        assert_eq!(parse_toml_value("k", "'quoted'").unwrap().as_str(), Some("quoted"));
+        assert!(parse_toml_value("k", "[\"a\", \"b\"]").unwrap().is_array());
+        assert!(parse_toml_value("k", "{ inherit = \"core\" }").unwrap().is_inline_table());
+        assert_eq!(parse_toml_value("k", "{key}").unwrap_err().kind(), "config");
     }
 }
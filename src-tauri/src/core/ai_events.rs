@@ -0,0 +1,441 @@
+use serde::Serialize;
+use serde_json::Value;
+
+/// Something an AI CLI reported while running, sent as `ai-event` events
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum AiEvent {
+    /// Next piece of the answer
+    TextDelta { text: String },
+    /// Next piece of the model's reasoning, for display only
+    ReasoningDelta { text: String },
+    /// The tool ran a command or called a tool
+    ToolCall { id: Option<String>, name: String, input: Value },
+    /// The tool wants to add, update or delete a file
+    FileEdit { path: String, kind: FileEditKind, diff: Option<String> },
+    /// Token counts so far
+    Usage { input_tokens: u64, cached_input_tokens: u64, output_tokens: u64 },
+    /// The complete answer
+    FinalMessage { text: String },
+    Error { message: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileEditKind {
+    Add,
+    Update,
+    Delete,
+}
+
+/// The answer of a run: the final message, or the text deltas if there was none
+#[derive(Debug, Default)]
+pub struct Answer {
+    streamed: String,
+    final_message: Option<String>,
+}
+
+impl Answer {
+    pub fn record(&mut self, event: &AiEvent) {
+        match event {
+            AiEvent::TextDelta { text } => self.streamed.push_str(text),
+            AiEvent::FinalMessage { text } => self.final_message = Some(text.clone()),
+            _ => {}
+        }
+    }
+
+    pub fn into_text(self) -> String {
+        self.final_message.unwrap_or(self.streamed)
+    }
+}
+
+/// Turns a CLI's stdout into events, one line at a time
+pub trait OutputParser: Send {
+    fn parse_line(&mut self, line: &str) -> Vec<AiEvent>;
+}
+
+/// Output that is the answer itself
+#[derive(Debug, Default)]
+pub struct PlainTextParser;
+
+impl OutputParser for PlainTextParser {
+    fn parse_line(&mut self, line: &str) -> Vec<AiEvent> {
+        vec![AiEvent::TextDelta { text: format!("{}\n", line) }]
+    }
+}
+
+/// `codex exec --json`. Understands both the item-based format (`item.completed`,
+/// `turn.completed`) and the older `{ "msg": { "type": ... } }` one.
+#[derive(Debug, Default)]
+pub struct CodexJsonParser {
+    /// Last agent message, reported as the final one when the turn ends
+    last_message: Option<String>,
+    /// Messages also arrive whole after their deltas in the older format
+    streamed: bool,
+}
+
+impl OutputParser for CodexJsonParser {
+    fn parse_line(&mut self, line: &str) -> Vec<AiEvent> {
+        let Ok(value) = serde_json::from_str::<Value>(line) else { return Vec::new() };
+        match value.get("msg") {
+            Some(msg) => self.parse_msg(msg),
+            None => self.parse_item_event(&value),
+        }
+    }
+}
+
+impl CodexJsonParser {
+    fn parse_item_event(&mut self, value: &Value) -> Vec<AiEvent> {
+        match str_at(value, "type") {
+            Some("item.started") => match value.get("item") {
+                Some(item) => self.started_item(item),
+                None => Vec::new(),
+            },
+            Some("item.completed") => match value.get("item") {
+                Some(item) => self.completed_item(item),
+                None => Vec::new(),
+            },
+            Some("turn.completed") => {
+                let mut events: Vec<AiEvent> = value.get("usage").map(codex_usage).into_iter().collect();
+                events.extend(self.last_message.take().map(|text| AiEvent::FinalMessage { text }));
+                events
+            }
+            Some("turn.failed") => error_event(value.get("error").unwrap_or(&Value::Null)),
+            Some("error") => error_event(value),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Tool calls are reported when they start, so the user sees what is running
+    fn started_item(&mut self, item: &Value) -> Vec<AiEvent> {
+        let id = str_at(item, "id").map(str::to_string);
+        match str_at(item, "type") {
+            Some("command_execution") => vec![AiEvent::ToolCall {
+                id,
+                name: "shell".to_string(),
+                input: serde_json::json!({ "command": item.get("command") }),
+            }],
+            Some("mcp_tool_call") => vec![AiEvent::ToolCall {
+                id,
+                name: format!("{}.{}", str_at(item, "server").unwrap_or(""), str_at(item, "tool").unwrap_or("")),
+                input: item.get("arguments").cloned().unwrap_or(Value::Null),
+            }],
+            Some("web_search") => vec![AiEvent::ToolCall {
+                id,
+                name: "web_search".to_string(),
+                input: serde_json::json!({ "query": item.get("query") }),
+            }],
+            _ => Vec::new(),
+        }
+    }
+
+    fn completed_item(&mut self, item: &Value) -> Vec<AiEvent> {
+        let text = str_at(item, "text").unwrap_or("").to_string();
+        match str_at(item, "type") {
+            Some("agent_message") => {
+                self.last_message = Some(text.clone());
+                vec![AiEvent::TextDelta { text }]
+            }
+            Some("reasoning") => vec![AiEvent::ReasoningDelta { text }],
+            Some("file_change") => item
+                .get("changes")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|change| {
+                    Some(AiEvent::FileEdit {
+                        path: str_at(change, "path")?.to_string(),
+                        kind: edit_kind(str_at(change, "kind")?)?,
+                        diff: None,
+                    })
+                })
+                .collect(),
+            Some("error") => vec![AiEvent::Error { message: str_at(item, "message").unwrap_or("").to_string() }],
+            _ => Vec::new(),
+        }
+    }
+
+    fn parse_msg(&mut self, msg: &Value) -> Vec<AiEvent> {
+        match str_at(msg, "type") {
+            Some("agent_message_delta") => {
+                self.streamed = true;
+                delta(msg).map(|text| AiEvent::TextDelta { text }).into_iter().collect()
+            }
+            Some("agent_reasoning_delta") => delta(msg).map(|text| AiEvent::ReasoningDelta { text }).into_iter().collect(),
+            Some("agent_message") => {
+                let text = str_at(msg, "message").unwrap_or("").to_string();
+                self.last_message = Some(text.clone());
+                if std::mem::take(&mut self.streamed) {
+                    Vec::new()
+                } else {
+                    vec![AiEvent::TextDelta { text }]
+                }
+            }
+            Some("exec_command_begin") => vec![AiEvent::ToolCall {
+                id: str_at(msg, "call_id").map(str::to_string),
+                name: "shell".to_string(),
+                input: serde_json::json!({ "command": msg.get("command") }),
+            }],
+            Some("mcp_tool_call_begin") => {
+                let invocation = msg.get("invocation").unwrap_or(&Value::Null);
+                vec![AiEvent::ToolCall {
+                    id: str_at(msg, "call_id").map(str::to_string),
+                    name: format!("{}.{}", str_at(invocation, "server").unwrap_or(""), str_at(invocation, "tool").unwrap_or("")),
+                    input: invocation.get("arguments").cloned().unwrap_or(Value::Null),
+                }]
+            }
+            Some("apply_patch_approval_request") | Some("patch_apply_begin") => msg
+                .get("changes")
+                .and_then(Value::as_object)
+                .into_iter()
+                .flatten()
+                .filter_map(|(path, change)| {
+                    let (kind, body) = change.as_object()?.iter().next()?;
+                    Some(AiEvent::FileEdit {
+                        path: path.clone(),
+                        kind: edit_kind(kind)?,
+                        diff: str_at(body, "unified_diff").or_else(|| str_at(body, "content")).map(str::to_string),
+                    })
+                })
+                .collect(),
+            Some("token_count") => msg
+                .get("info")
+                .and_then(|info| info.get("total_token_usage"))
+                .map(codex_usage)
+                .into_iter()
+                .collect(),
+            Some("task_complete") => {
+                let text = str_at(msg, "last_agent_message").map(str::to_string).or_else(|| self.last_message.take());
+                text.map(|text| AiEvent::FinalMessage { text }).into_iter().collect()
+            }
+            Some("error") | Some("stream_error") => error_event(msg),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// `claude --print --output-format stream-json --verbose --include-partial-messages`
+#[derive(Debug, Default)]
+pub struct ClaudeStreamParser {
+    /// Whole assistant messages repeat text that already arrived as deltas
+    streamed: bool,
+}
+
+impl OutputParser for ClaudeStreamParser {
+    fn parse_line(&mut self, line: &str) -> Vec<AiEvent> {
+        let Ok(value) = serde_json::from_str::<Value>(line) else { return Vec::new() };
+        match str_at(&value, "type") {
+            Some("stream_event") => self.stream_event(value.get("event").unwrap_or(&Value::Null)),
+            Some("assistant") => self.assistant_message(value.get("message").unwrap_or(&Value::Null)),
+            Some("result") => {
+                let mut events: Vec<AiEvent> = value.get("usage").map(claude_usage).into_iter().collect();
+                if value.get("is_error").and_then(Value::as_bool).unwrap_or(false) {
+                    let message = str_at(&value, "result").or_else(|| str_at(&value, "subtype")).unwrap_or("");
+                    events.push(AiEvent::Error { message: message.to_string() });
+                } else if let Some(text) = str_at(&value, "result") {
+                    events.push(AiEvent::FinalMessage { text: text.to_string() });
+                }
+                events
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl ClaudeStreamParser {
+    fn stream_event(&mut self, event: &Value) -> Vec<AiEvent> {
+        if str_at(event, "type") != Some("content_block_delta") {
+            return Vec::new();
+        }
+        let delta = event.get("delta").unwrap_or(&Value::Null);
+        match str_at(delta, "type") {
+            Some("text_delta") => {
+                self.streamed = true;
+                vec![AiEvent::TextDelta { text: str_at(delta, "text").unwrap_or("").to_string() }]
+            }
+            Some("thinking_delta") => {
+                vec![AiEvent::ReasoningDelta { text: str_at(delta, "thinking").unwrap_or("").to_string() }]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn assistant_message(&mut self, message: &Value) -> Vec<AiEvent> {
+        let streamed = std::mem::take(&mut self.streamed);
+        let blocks = message.get("content").and_then(Value::as_array).into_iter().flatten();
+        let mut events = Vec::new();
+        for block in blocks {
+            match str_at(block, "type") {
+                Some("text") if !streamed => {
+                    events.push(AiEvent::TextDelta { text: str_at(block, "text").unwrap_or("").to_string() });
+                }
+                Some("thinking") if !streamed => {
+                    events.push(AiEvent::ReasoningDelta { text: str_at(block, "thinking").unwrap_or("").to_string() });
+                }
+                Some("tool_use") => {
+                    let name = str_at(block, "name").unwrap_or("").to_string();
+                    let input = block.get("input").cloned().unwrap_or(Value::Null);
+                    if let Some(edit) = claude_file_edit(&name, &input) {
+                        events.push(edit);
+                    }
+                    events.push(AiEvent::ToolCall { id: str_at(block, "id").map(str::to_string), name, input });
+                }
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+/// Claude edits files through its Write and Edit tools
+fn claude_file_edit(tool: &str, input: &Value) -> Option<AiEvent> {
+    let kind = match tool {
+        "Write" => FileEditKind::Add,
+        "Edit" | "MultiEdit" => FileEditKind::Update,
+        _ => return None,
+    };
+    Some(AiEvent::FileEdit { path: str_at(input, "file_path")?.to_string(), kind, diff: None })
+}
+
+fn str_at<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value.get(key).and_then(Value::as_str)
+}
+
+fn u64_at(value: &Value, key: &str) -> u64 {
+    value.get(key).and_then(Value::as_u64).unwrap_or(0)
+}
+
+fn delta(msg: &Value) -> Option<String> {
+    str_at(msg, "delta").map(str::to_string)
+}
+
+fn edit_kind(kind: &str) -> Option<FileEditKind> {
+    match kind {
+        "add" => Some(FileEditKind::Add),
+        "update" => Some(FileEditKind::Update),
+        "delete" => Some(FileEditKind::Delete),
+        _ => None,
+    }
+}
+
+fn error_event(value: &Value) -> Vec<AiEvent> {
+    vec![AiEvent::Error { message: str_at(value, "message").unwrap_or("").to_string() }]
+}
+
+fn codex_usage(usage: &Value) -> AiEvent {
+    AiEvent::Usage {
+        input_tokens: u64_at(usage, "input_tokens"),
+        cached_input_tokens: u64_at(usage, "cached_input_tokens"),
+        output_tokens: u64_at(usage, "output_tokens"),
+    }
+}
+
+fn claude_usage(usage: &Value) -> AiEvent {
+    AiEvent::Usage {
+        input_tokens: u64_at(usage, "input_tokens"),
+        cached_input_tokens: u64_at(usage, "cache_read_input_tokens"),
+        output_tokens: u64_at(usage, "output_tokens"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse_all(parser: &mut dyn OutputParser, lines: &[&str]) -> Vec<AiEvent> {
+        lines.iter().flat_map(|line| parser.parse_line(line)).collect()
+    }
+
+    fn text(text: &str) -> AiEvent {
+        AiEvent::TextDelta { text: text.to_string() }
+    }
+
+    #[test]
+    fn test_codex_items() {
+        let events = parse_all(&mut CodexJsonParser::default(), &[
+            r#"{"type":"thread.started","thread_id":"t1"}"#,
+            "[2025-01-01T00:00:00] codex",
+            r#"{"type":"item.completed","item":{"id":"item_0","type":"reasoning","text":"Thinking"}}"#,
+            r#"{"type":"item.started","item":{"id":"item_1","type":"command_execution","command":"ls","status":"in_progress"}}"#,
+            r#"{"type":"item.completed","item":{"id":"item_2","type":"file_change","changes":[{"path":"a.rs","kind":"update"}],"status":"completed"}}"#,
+            r#"{"type":"item.completed","item":{"id":"item_3","type":"agent_message","text":"Done."}}"#,
+            r#"{"type":"turn.completed","usage":{"input_tokens":10,"cached_input_tokens":4,"output_tokens":3}}"#,
+        ]);
+        assert_eq!(events, vec![
+            AiEvent::ReasoningDelta { text: "Thinking".into() },
+            AiEvent::ToolCall { id: Some("item_1".into()), name: "shell".into(), input: json!({ "command": "ls" }) },
+            AiEvent::FileEdit { path: "a.rs".into(), kind: FileEditKind::Update, diff: None },
+            text("Done."),
+            AiEvent::Usage { input_tokens: 10, cached_input_tokens: 4, output_tokens: 3 },
+            AiEvent::FinalMessage { text: "Done.".into() },
+        ]);
+    }
+
+    #[test]
+    fn test_codex_msg_format() {
+        let events = parse_all(&mut CodexJsonParser::default(), &[
+            r#"{"id":"0","msg":{"type":"agent_message_delta","delta":"Hel"}}"#,
+            r#"{"id":"0","msg":{"type":"agent_message_delta","delta":"lo"}}"#,
+            r#"{"id":"0","msg":{"type":"agent_message","message":"Hello"}}"#,
+            r#"{"id":"0","msg":{"type":"patch_apply_begin","call_id":"c1","changes":{"b.txt":{"add":{"content":"hi\n"}}}}}"#,
+            r#"{"id":"0","msg":{"type":"token_count","info":null}}"#,
+            r#"{"id":"0","msg":{"type":"task_complete","last_agent_message":"Hello"}}"#,
+        ]);
+        assert_eq!(events, vec![
+            text("Hel"),
+            text("lo"),
+            AiEvent::FileEdit { path: "b.txt".into(), kind: FileEditKind::Add, diff: Some("hi\n".into()) },
+            AiEvent::FinalMessage { text: "Hello".into() },
+        ]);
+    }
+
+    #[test]
+    fn test_claude_stream() {
+        let events = parse_all(&mut ClaudeStreamParser::default(), &[
+            r#"{"type":"system","subtype":"init","session_id":"s1"}"#,
+            r#"{"type":"stream_event","event":{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hi"}}}"#,
+            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Hi"},{"type":"tool_use","id":"tu1","name":"Edit","input":{"file_path":"/a.rs","old_string":"x","new_string":"y"}}]}}"#,
+            r#"{"type":"result","subtype":"success","is_error":false,"result":"Hi","usage":{"input_tokens":5,"cache_read_input_tokens":2,"output_tokens":1}}"#,
+        ]);
+        assert_eq!(events, vec![
+            text("Hi"),
+            AiEvent::FileEdit { path: "/a.rs".into(), kind: FileEditKind::Update, diff: None },
+            AiEvent::ToolCall {
+                id: Some("tu1".into()),
+                name: "Edit".into(),
+                input: json!({ "file_path": "/a.rs", "old_string": "x", "new_string": "y" }),
+            },
+            AiEvent::Usage { input_tokens: 5, cached_input_tokens: 2, output_tokens: 1 },
+            AiEvent::FinalMessage { text: "Hi".into() },
+        ]);
+
+        // Without partial messages the text comes with the whole message
+        let events = ClaudeStreamParser::default()
+            .parse_line(r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Hi"}]}}"#);
+        assert_eq!(events, vec![text("Hi")]);
+    }
+
+    #[test]
+    fn test_answer() {
+        let mut answer = Answer::default();
+        answer.record(&text("Hel"));
+        answer.record(&text("lo"));
+        answer.record(&AiEvent::ReasoningDelta { text: "hmm".into() });
+        assert_eq!(answer.into_text(), "Hello");
+        let mut answer = Answer::default();
+        answer.record(&text("Draft"));
+        answer.record(&AiEvent::FinalMessage { text: "Final".into() });
+        assert_eq!(answer.into_text(), "Final");
+    }
+
+    #[test]
+    fn test_event_payload() {
+        let usage = AiEvent::Usage { input_tokens: 1, cached_input_tokens: 0, output_tokens: 2 };
+        assert_eq!(
+            serde_json::to_value(usage).unwrap(),
+            json!({ "type": "usage", "inputTokens": 1, "cachedInputTokens": 0, "outputTokens": 2 })
+        );
+        assert_eq!(PlainTextParser.parse_line("abc"), vec![text("abc\n")]);
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::core::ai_events::{ClaudeStreamParser, CodexJsonParser, OutputParser, PlainTextParser};
+use crate::core::process_manager::resolve_binary_path;
+use crate::error::{AppError, Result};
+
+/// Placeholders understood in a custom command's arguments
+const PROMPT_PLACEHOLDER: &str = "{prompt}";
+const MODEL_PLACEHOLDER: &str = "{model}";
+
+/// One AI run as requested by the frontend
+#[derive(Debug, Clone, Default)]
+pub struct ExecRequest {
+    pub prompt: String,
+    pub model: Option<String>,
+    /// Provider-specific overrides, e.g. Codex `-c key=value` pairs
+    pub config: HashMap<String, String>,
+}
+
+/// How the prompt reaches the process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptInput {
+    /// Written to stdin, which keeps large prompts clear of command-line limits
+    Stdin,
+    /// Already part of the arguments from `exec_args`
+    Args,
+}
+
+/// A command-line AI tool the editor can run
+pub trait AiProvider: Send + Sync {
+    /// Stable identifier used by the frontend and in events
+    fn id(&self) -> &'static str;
+
+    fn name(&self) -> &'static str;
+
+    /// Executable, looked up with `resolve_binary_path`
+    fn binary(&self) -> &str;
+
+    /// Arguments after the executable for a run
+    fn exec_args(&self, request: &ExecRequest) -> Vec<String>;
+
+    fn prompt_input(&self) -> PromptInput {
+        PromptInput::Stdin
+    }
+
+    /// Arguments that start an interactive login, if the tool has one
+    fn login_args(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    /// Reads the events of one run from stdout
+    fn output_parser(&self) -> Box<dyn OutputParser> {
+        Box::new(PlainTextParser)
+    }
+}
+
+/// OpenAI Codex CLI, run non-interactively with `codex exec --json`
+pub struct Codex;
+
+impl AiProvider for Codex {
+    fn id(&self) -> &'static str {
+        "codex"
+    }
+
+    fn name(&self) -> &'static str {
+        "Codex"
+    }
+
+    fn binary(&self) -> &str {
+        "codex"
+    }
+
+    fn exec_args(&self, request: &ExecRequest) -> Vec<String> {
+        let mut args = vec!["exec".to_string(), "--json".to_string(), "--skip-git-repo-check".to_string()];
+        if let Some(model) = request.model.as_ref() {
+            args.push("--model".into());
+            args.push(model.clone());
+        }
+        // Sorted so the command line is stable between runs
+        let mut config: Vec<_> = request.config.iter().collect();
+        config.sort();
+        for (key, value) in config {
+            args.push("-c".into());
+            args.push(format!("{}={}", key, value));
+        }
+        args
+    }
+
+    fn login_args(&self) -> Option<Vec<String>> {
+        Some(vec!["login".to_string()])
+    }
+
+    fn output_parser(&self) -> Box<dyn OutputParser> {
+        Box::new(CodexJsonParser::default())
+    }
+}
+
+/// Anthropic Claude Code CLI in print mode, which answers once and exits,
+/// streaming JSON including partial messages
+pub struct Claude;
+
+impl AiProvider for Claude {
+    fn id(&self) -> &'static str {
+        "claude"
+    }
+
+    fn name(&self) -> &'static str {
+        "Claude"
+    }
+
+    fn binary(&self) -> &str {
+        "claude"
+    }
+
+    /// Claude has no per-run config overrides, so `request.config` is not used
+    fn exec_args(&self, request: &ExecRequest) -> Vec<String> {
+        let mut args: Vec<String> =
+            ["--print", "--output-format", "stream-json", "--verbose", "--include-partial-messages"]
+                .iter()
+                .map(|a| a.to_string())
+                .collect();
+        if let Some(model) = request.model.as_ref() {
+            args.push("--model".into());
+            args.push(model.clone());
+        }
+        args
+    }
+
+    fn login_args(&self) -> Option<Vec<String>> {
+        Some(vec!["setup-token".to_string()])
+    }
+
+    fn output_parser(&self) -> Box<dyn OutputParser> {
+        Box::new(ClaudeStreamParser::default())
+    }
+}
+
+/// Any other tool, from the `ai.customCommand` setting: the executable followed by
+/// its arguments. Its stdout is taken as the answer. `{model}` is replaced with the requested model and `{prompt}`
+/// with the prompt; without a `{prompt}` argument the prompt goes to stdin.
+pub struct CustomCommand {
+    argv: Vec<String>,
+}
+
+impl CustomCommand {
+    pub fn new(argv: Vec<String>) -> Self {
+        Self { argv }
+    }
+}
+
+impl AiProvider for CustomCommand {
+    fn id(&self) -> &'static str {
+        "custom"
+    }
+
+    fn name(&self) -> &'static str {
+        "Custom command"
+    }
+
+    fn binary(&self) -> &str {
+        self.argv.first().map(String::as_str).unwrap_or("")
+    }
+
+    fn exec_args(&self, request: &ExecRequest) -> Vec<String> {
+        let model = request.model.as_deref().unwrap_or("");
+        self.argv
+            .iter()
+            .skip(1)
+            .map(|arg| arg.replace(MODEL_PLACEHOLDER, model).replace(PROMPT_PLACEHOLDER, &request.prompt))
+            .collect()
+    }
+
+    fn prompt_input(&self) -> PromptInput {
+        if self.argv.iter().skip(1).any(|arg| arg.contains(PROMPT_PLACEHOLDER)) {
+            PromptInput::Args
+        } else {
+            PromptInput::Stdin
+        }
+    }
+}
+
+/// Progress of one run, sent as `ai-lifecycle` events
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "phase", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum RunLifecycle {
+    Spawned { pid: u32 },
+    /// First output on stdout or stderr, counted from the spawn
+    FirstByte { latency_ms: u64 },
+    /// `code` is missing when the process was killed by `signal` (Unix only)
+    Exited { code: Option<i32>, signal: Option<i32>, duration_ms: u64 },
+}
+
+/// How a run ended, reported with `ai-complete`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RunStatus {
+    Succeeded,
+    Failed,
+    /// Stopped through `ai_cancel`; not an error
+    Cancelled,
+}
+
+/// What `list_providers` reports for each provider
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderInfo {
+    pub id: String,
+    pub name: String,
+    /// Whether the executable was found
+    pub available: bool,
+    pub path: Option<String>,
+    pub can_login: bool,
+}
+
+impl ProviderInfo {
+    pub fn of(provider: &dyn AiProvider) -> Self {
+        let path = match provider.binary() {
+            "" => None,
+            binary => resolve_binary_path(binary),
+        };
+        Self {
+            id: provider.id().to_string(),
+            name: provider.name().to_string(),
+            available: path.is_some(),
+            path: path.map(|p| p.to_string_lossy().to_string()),
+            can_login: provider.login_args().is_some(),
+        }
+    }
+}
+
+/// Every provider, the custom one built from the `ai.customCommand` setting
+pub fn providers(custom_command: Vec<String>) -> Vec<Arc<dyn AiProvider>> {
+    vec![Arc::new(Codex), Arc::new(Claude), Arc::new(CustomCommand::new(custom_command))]
+}
+
+pub fn find_provider(id: &str, custom_command: Vec<String>) -> Result<Arc<dyn AiProvider>> {
+    providers(custom_command)
+        .into_iter()
+        .find(|p| p.id() == id)
+        .ok_or_else(|| AppError::ProviderNotFound(id.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(model: Option<&str>, config: &[(&str, &str)]) -> ExecRequest {
+        ExecRequest {
+            prompt: "fix it".to_string(),
+            model: model.map(str::to_string),
+            config: config.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+        }
+    }
+
+    #[test]
+    fn test_codex_args() {
+        let args = Codex.exec_args(&request(Some("o3"), &[("model_reasoning_effort", "high"), ("a", "1")]));
+        assert_eq!(
+            args,
+            vec!["exec", "--json", "--skip-git-repo-check", "--model", "o3", "-c", "a=1", "-c", "model_reasoning_effort=high"]
+        );
+        assert_eq!(Codex.prompt_input(), PromptInput::Stdin);
+    }
+
+    #[test]
+    fn test_claude_args() {
+        // No Codex-only flags, and config overrides are not passed on
+        let args = Claude.exec_args(&request(Some("sonnet"), &[("model_reasoning_effort", "high")]));
+        assert_eq!(args[..2], ["--print", "--output-format"]);
+        assert_eq!(args[args.len() - 2..], ["--model", "sonnet"]);
+        assert!(!args.iter().any(|a| a == "exec" || a == "-c"));
+    }
+
+    #[test]
+    fn test_custom_command_placeholders() {
+        let custom = CustomCommand::new(vec!["ollama".into(), "run".into(), "{model}".into()]);
+        assert_eq!(custom.binary(), "ollama");
+        assert_eq!(custom.exec_args(&request(Some("llama3"), &[])), vec!["run", "llama3"]);
+        assert_eq!(custom.prompt_input(), PromptInput::Stdin);
+        assert!(custom.login_args().is_none());
+
+        let custom = CustomCommand::new(vec!["llm".into(), "--prompt={prompt}".into()]);
+        assert_eq!(custom.exec_args(&request(None, &[])), vec!["--prompt=fix it"]);
+        assert_eq!(custom.prompt_input(), PromptInput::Args);
+    }
+
+    #[test]
+    fn test_lifecycle_payload() {
+        let exited = RunLifecycle::Exited { code: None, signal: Some(15), duration_ms: 1200 };
+        assert_eq!(
+            serde_json::to_value(exited).unwrap(),
+            serde_json::json!({ "phase": "exited", "code": null, "signal": 15, "durationMs": 1200 })
+        );
+        assert_eq!(serde_json::to_value(RunStatus::Cancelled).unwrap(), "cancelled");
+    }
+
+    #[test]
+    fn test_find_provider() {
+        assert_eq!(find_provider("claude", Vec::new()).unwrap().id(), "claude");
+        assert!(matches!(find_provider("gemini", Vec::new()), Err(AppError::ProviderNotFound(_))));
+        let info = ProviderInfo::of(&CustomCommand::new(Vec::new()));
+        assert!(!info.available && info.path.is_none());
+    }
+}
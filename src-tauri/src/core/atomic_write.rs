@@ -0,0 +1,279 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::{AppError, Result};
+
+/// Maximum number of symlink hops followed before giving up (matches Linux's MAXSYMLINKS)
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Atomically replace the contents of `path` with `bytes`.
+///
+/// The data is written to a temporary file in the same directory, flushed to disk and
+/// renamed over the target, so readers either see the old or the new contents, never a
+/// truncated file. Symlinks are resolved first so the link target is updated in place.
+/// Permissions, ownership and extended attributes of an existing file are carried over
+/// before any data is written, and a file that may not be written is left alone.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    let target = resolve_symlinks(path)?;
+    let dir = match target.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let existing = fs::metadata(&target).ok();
+    if let Some(meta) = existing.as_ref().filter(|m| m.is_file()) {
+        // Renaming only needs access to the directory; honour the file's own permissions
+        if meta.permissions().readonly() {
+            return Err(AppError::Io(io::Error::from(io::ErrorKind::PermissionDenied)));
+        }
+        OpenOptions::new().write(true).open(&target)?;
+    }
+
+    let (tmp_path, mut tmp) = create_temp_file(&dir, &target, existing.as_ref())?;
+    let written = (|| -> io::Result<()> {
+        // Restrict the temporary file before it holds the new contents
+        if let Some(meta) = existing.as_ref() {
+            copy_metadata(&target, &tmp, meta)?;
+        }
+        tmp.write_all(bytes)?;
+        tmp.sync_all()
+    })();
+    drop(tmp);
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(AppError::Io(e));
+    }
+
+    if let Err(e) = fs::rename(&tmp_path, &target) {
+        let _ = fs::remove_file(&tmp_path);
+        if is_non_atomic_rename(&e) {
+            return Err(AppError::AtomicWrite {
+                path: target.to_string_lossy().to_string(),
+                reason: e.to_string(),
+            });
+        }
+        return Err(AppError::Io(e));
+    }
+
+    sync_dir(&dir);
+    Ok(())
+}
+
+/// Follow symlinks (including dangling ones) to the path that should actually be written.
+pub fn resolve_symlinks(path: &Path) -> Result<PathBuf> {
+    let mut current = path.to_path_buf();
+    for _ in 0..MAX_SYMLINK_HOPS {
+        match fs::symlink_metadata(&current) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                let link = fs::read_link(&current)?;
+                current = if link.is_absolute() {
+                    link
+                } else {
+                    current.parent().map(|p| p.join(&link)).unwrap_or(link)
+                };
+            }
+            _ => return Ok(current),
+        }
+    }
+    Err(AppError::Io(io::Error::other(format!(
+        "Too many levels of symbolic links: {}",
+        path.display()
+    ))))
+}
+
+fn create_temp_file(dir: &Path, target: &Path, existing: Option<&fs::Metadata>) -> Result<(PathBuf, File)> {
+    let name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "file".to_string());
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+
+    let mut last_err = None;
+    for attempt in 0..16u32 {
+        let tmp_path = dir.join(format!(
+            ".{}.editrion-{}-{}-{}.tmp",
+            name,
+            std::process::id(),
+            nanos,
+            attempt
+        ));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        if let Some(meta) = existing {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            options.mode(meta.permissions().mode() & 0o777);
+        }
+        #[cfg(not(unix))]
+        let _ = existing;
+        match options.open(&tmp_path) {
+            Ok(file) => return Ok((tmp_path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => last_err = Some(e),
+            Err(e) => return Err(AppError::Io(e)),
+        }
+    }
+    Err(AppError::Io(last_err.unwrap_or_else(|| {
+        io::Error::new(io::ErrorKind::AlreadyExists, "could not create temporary file")
+    })))
+}
+
+fn copy_metadata(original: &Path, tmp: &File, meta: &fs::Metadata) -> io::Result<()> {
+    tmp.set_permissions(meta.permissions())?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        // Changing the owner needs privileges; keeping at least the group is still useful
+        if std::os::unix::fs::fchown(tmp, Some(meta.uid()), Some(meta.gid())).is_err() {
+            let _ = std::os::unix::fs::fchown(tmp, None, Some(meta.gid()));
+        }
+        copy_xattrs(original, tmp);
+    }
+    #[cfg(not(unix))]
+    let _ = original;
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_xattrs(original: &Path, tmp: &File) {
+    use xattr::FileExt;
+    // Best effort: some namespaces (e.g. security.*) are not writable by regular users
+    if let Ok(names) = xattr::list(original) {
+        for name in names {
+            if let Ok(Some(value)) = xattr::get(original, &name) {
+                let _ = tmp.set_xattr(&name, &value);
+            }
+        }
+    }
+}
+
+fn sync_dir(dir: &Path) {
+    // Persist the rename itself; directories cannot be opened this way on Windows
+    #[cfg(unix)]
+    if let Ok(d) = File::open(dir) {
+        let _ = d.sync_all();
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+}
+
+fn is_non_atomic_rename(e: &io::Error) -> bool {
+    #[cfg(unix)]
+    if e.raw_os_error() == Some(libc::EBUSY) {
+        return true;
+    }
+    is_cross_device(e)
+}
+
+/// Whether a rename failed because `from` and `to` are on different file systems
+pub fn is_cross_device(e: &io::Error) -> bool {
+    #[cfg(unix)]
+    return e.raw_os_error() == Some(libc::EXDEV);
+    #[cfg(windows)]
+    return e.raw_os_error() == Some(17 /* ERROR_NOT_SAME_DEVICE */);
+    #[cfg(not(any(unix, windows)))]
+    return false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("editrion-atomic-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_write_atomic_creates_and_replaces() {
+        let dir = temp_dir("replace");
+        let file = dir.join("a.txt");
+
+        write_atomic(&file, b"first").unwrap();
+        assert_eq!(fs::read(&file).unwrap(), b"first");
+
+        write_atomic(&file, b"second").unwrap();
+        assert_eq!(fs::read(&file).unwrap(), b"second");
+
+        // No temporary files left behind
+        let leftovers: Vec<_> = fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(leftovers.len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = temp_dir("perms");
+        let file = dir.join("script.sh");
+        fs::write(&file, "old").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o750)).unwrap();
+
+        write_atomic(&file, b"new").unwrap();
+        let mode = fs::metadata(&file).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o750);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_never_exposes_private_contents() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = temp_dir("private");
+        let file = dir.join("secret.txt");
+        fs::write(&file, "old").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o600)).unwrap();
+
+        let meta = fs::metadata(&file).unwrap();
+        let (tmp_path, tmp) = create_temp_file(&dir, &file, Some(&meta)).unwrap();
+        let mode = tmp.metadata().unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+        fs::remove_file(&tmp_path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_refuses_read_only_files() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = temp_dir("readonly");
+        let file = dir.join("locked.txt");
+        fs::write(&file, "old").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o444)).unwrap();
+
+        let result = write_atomic(&file, b"new");
+        assert!(matches!(result, Err(AppError::Io(ref e)) if e.kind() == io::ErrorKind::PermissionDenied));
+        assert_eq!(fs::read(&file).unwrap(), b"old");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_updates_symlink_target() {
+        let dir = temp_dir("symlink");
+        let real = dir.join("real.txt");
+        let link = dir.join("link.txt");
+        fs::write(&real, "old").unwrap();
+        std::os::unix::fs::symlink("real.txt", &link).unwrap();
+
+        write_atomic(&link, b"new").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read(&real).unwrap(), b"new");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_atomic_missing_dir_fails() {
+        let dir = temp_dir("missing");
+        let result = write_atomic(&dir.join("nope").join("a.txt"), b"x");
+        assert!(matches!(result, Err(AppError::Io(_))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
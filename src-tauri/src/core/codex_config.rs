@@ -0,0 +1,289 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use notify_debouncer_full::notify::{EventKind, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, Debouncer, RecommendedCache};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use toml_edit::{DocumentMut, Item, Table};
+
+use crate::core::settings::{line_column, SettingsProblem};
+use crate::error::{AppError, Result};
+
+pub const CONFIG_NAME: &str = "config.toml";
+pub const TEMPLATES_NAME: &str = "ai_templates.json";
+
+/// Quiet period before a save is picked up; editors often write in several steps
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Effort {
+    Minimal,
+    Low,
+    Medium,
+    High,
+}
+
+/// One entry of `ai_templates.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AiTemplate {
+    /// Filled in from the position when missing, so hand-written entries get actions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    pub instruction: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effort: Option<Effort>,
+}
+
+/// Parse `config.toml` into JSON, or say where it is broken
+pub fn parse_config(text: &str, file: &str) -> std::result::Result<Value, SettingsProblem> {
+    let doc: DocumentMut = text.parse().map_err(|e: toml_edit::TomlError| {
+        let position = e.span().map(|span| line_column(text, span.start));
+        SettingsProblem {
+            file: file.to_string(),
+            key: None,
+            line: position.map(|p| p.0),
+            column: position.map(|p| p.1),
+            message: e.message().to_string(),
+        }
+    })?;
+    Ok(table_to_json(doc.as_table()))
+}
+
+pub fn table_to_json(table: &Table) -> Value {
+    Value::Object(table.iter().map(|(key, item)| (key.to_string(), item_to_json(item))).collect())
+}
+
+pub fn item_to_json(item: &Item) -> Value {
+    match item {
+        Item::None => Value::Null,
+        Item::Value(value) => value_to_json(value),
+        Item::Table(table) => table_to_json(table),
+        Item::ArrayOfTables(tables) => Value::Array(tables.iter().map(table_to_json).collect()),
+    }
+}
+
+pub fn value_to_json(value: &toml_edit::Value) -> Value {
+    use toml_edit::Value as Toml;
+    match value {
+        Toml::String(s) => Value::String(s.value().clone()),
+        Toml::Integer(i) => Value::from(*i.value()),
+        // JSON has no NaN or infinity
+        Toml::Float(f) => serde_json::Number::from_f64(*f.value()).map(Value::Number).unwrap_or(Value::Null),
+        Toml::Boolean(b) => Value::Bool(*b.value()),
+        Toml::Datetime(d) => Value::String(d.value().to_string()),
+        Toml::Array(items) => Value::Array(items.iter().map(value_to_json).collect()),
+        Toml::InlineTable(table) => Value::Object(
+            table.iter().map(|(key, value)| (key.to_string(), value_to_json(value))).collect::<Map<_, _>>(),
+        ),
+    }
+}
+
+/// Parse `ai_templates.json`, or say where it is broken
+pub fn parse_templates(text: &str, file: &str) -> std::result::Result<Vec<AiTemplate>, SettingsProblem> {
+    let mut templates: Vec<AiTemplate> = serde_json::from_str(text).map_err(|e| {
+        let message = e.to_string();
+        SettingsProblem {
+            file: file.to_string(),
+            key: None,
+            line: Some(e.line()),
+            column: Some(e.column()),
+            // The position is reported separately
+            message: message.split(" at line ").next().unwrap_or(&message).to_string(),
+        }
+    })?;
+    for (index, template) in templates.iter_mut().enumerate() {
+        if template.id.as_deref().map(str::is_empty).unwrap_or(true) {
+            template.id = Some(format!("template-{}", index + 1));
+        }
+    }
+    Ok(templates)
+}
+
+/// The Codex files as last read. An edit that breaks a file leaves the previous
+/// valid contents in place and is reported through `problems` until it is fixed.
+#[derive(Debug)]
+pub struct CodexConfigCache {
+    config: Value,
+    problems: Vec<SettingsProblem>,
+    /// Whether `ai_templates.json` existed when it was last read
+    had_templates: bool,
+}
+
+impl Default for CodexConfigCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CodexConfigCache {
+    pub fn new() -> Self {
+        Self { config: Value::Object(Map::new()), problems: Vec::new(), had_templates: false }
+    }
+
+    pub fn config(&self) -> &Value {
+        &self.config
+    }
+
+    pub fn problems(&self) -> &[SettingsProblem] {
+        &self.problems
+    }
+
+    /// Report that `file` could not be read or watched, replacing earlier problems with it.
+    /// The message stays until the file is read successfully.
+    pub fn report(&mut self, file: &Path, message: String) {
+        let file = file.to_string_lossy().to_string();
+        self.problems.retain(|p| p.file != file);
+        self.problems.push(SettingsProblem { file, key: None, line: None, column: None, message });
+    }
+
+    /// Re-read `path` if it is `config.toml` or `ai_templates.json`. Returns the
+    /// templates when the templates file was read successfully, and no templates
+    /// when it was deleted. A templates file that never existed returns `None`.
+    pub fn reload(&mut self, path: &Path) -> Result<Option<Vec<AiTemplate>>> {
+        let Some(name) = watched_name(path) else { return Ok(None) };
+        let file = path.to_string_lossy().to_string();
+        let text = match fs::read_to_string(path) {
+            Ok(text) => Some(text),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        self.problems.retain(|p| p.file != file);
+
+        if name == CONFIG_NAME {
+            // A deleted config means Codex runs on its defaults
+            match parse_config(text.as_deref().unwrap_or(""), &file) {
+                Ok(config) => self.config = config,
+                Err(problem) => self.problems.push(problem),
+            }
+            return Ok(None);
+        }
+        let existed = std::mem::replace(&mut self.had_templates, text.is_some());
+        let Some(text) = text else {
+            // Like a deleted config, a deleted templates file means there are none
+            return Ok(existed.then(Vec::new));
+        };
+        match parse_templates(&text, &file) {
+            Ok(templates) => Ok(Some(templates)),
+            Err(problem) => {
+                self.problems.push(problem);
+                Ok(None)
+            }
+        }
+    }
+}
+
+fn watched_name(path: &Path) -> Option<&'static str> {
+    match path.file_name()?.to_str()? {
+        CONFIG_NAME => Some(CONFIG_NAME),
+        TEMPLATES_NAME => Some(TEMPLATES_NAME),
+        _ => None,
+    }
+}
+
+/// Call `on_change` with the path of `config.toml` or `ai_templates.json` in `dir`
+/// whenever one of them is saved, created or removed
+pub fn watch(
+    dir: &Path,
+    on_change: impl Fn(PathBuf) + Send + 'static,
+) -> Result<Debouncer<RecommendedWatcher, RecommendedCache>> {
+    let mut debouncer = new_debouncer(DEBOUNCE_TIMEOUT, None, move |result: DebounceEventResult| {
+        let Ok(events) = result else { return };
+        let mut seen: Vec<PathBuf> = Vec::new();
+        // Reading the files on reload must not trigger another one
+        for event in events.iter().filter(|e| !matches!(e.kind, EventKind::Access(_))) {
+            for path in &event.paths {
+                if watched_name(path).is_some() && !seen.contains(path) {
+                    seen.push(path.clone());
+                }
+            }
+        }
+        for path in seen {
+            on_change(path);
+        }
+    })
+    .map_err(watch_error)?;
+    debouncer.watch(dir, RecursiveMode::NonRecursive).map_err(watch_error)?;
+    Ok(debouncer)
+}
+
+fn watch_error(e: impl std::fmt::Display) -> AppError {
+    AppError::Command(format!("File watcher error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_config_and_errors() {
+        let text = "model = \"o3\"\n\n[profiles.work]\nmodel_reasoning_effort = \"high\"\nretries = 3\n";
+        let config = parse_config(text, "config.toml").unwrap();
+        assert_eq!(config, json!({ "model": "o3", "profiles": { "work": { "model_reasoning_effort": "high", "retries": 3 } } }));
+
+        let problem = parse_config("model = \"o3\"\napproval_policy = \n", "config.toml").unwrap_err();
+        assert_eq!((problem.line, problem.column), (Some(2), Some(19)));
+    }
+
+    #[test]
+    fn test_reload_keeps_last_valid_config() {
+        let dir = std::env::temp_dir().join(format!("editrion-codex-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let (config, templates) = (dir.join(CONFIG_NAME), dir.join(TEMPLATES_NAME));
+        let mut cache = CodexConfigCache::new();
+
+        fs::write(&config, "model = \"o3\"\n").unwrap();
+        cache.reload(&config).unwrap();
+        fs::write(&config, "model = \"gpt-5\n").unwrap();
+        cache.reload(&config).unwrap();
+        assert_eq!(cache.config()["model"], "o3");
+        assert_eq!(cache.problems()[0].line, Some(1));
+        fs::write(&config, "model = \"gpt-5\"\n").unwrap();
+        cache.reload(&config).unwrap();
+        assert_eq!(cache.config()["model"], "gpt-5");
+        assert!(cache.problems().is_empty());
+
+        fs::write(&templates, "[\n  { \"name\": \"Fix\", \"instruction\": \"fix it\", \"effort\": \"huge\" }\n]").unwrap();
+        assert_eq!(cache.reload(&templates).unwrap(), None);
+        assert_eq!(cache.problems()[0].line, Some(2));
+        fs::write(&templates, r#"[{ "name": "Fix", "instruction": "fix it", "effort": "low" }]"#).unwrap();
+        let loaded = cache.reload(&templates).unwrap().unwrap();
+        assert_eq!(loaded[0].id.as_deref(), Some("template-1"));
+        assert!(cache.problems().is_empty());
+
+        // Failures outside parsing are reported until the next good read
+        cache.report(&templates, "permission denied".to_string());
+        cache.report(&templates, "still denied".to_string());
+        assert_eq!(cache.problems().len(), 1);
+        assert_eq!(cache.problems()[0].line, None);
+        cache.reload(&templates).unwrap();
+        assert!(cache.problems().is_empty());
+        assert_eq!(cache.reload(&dir.join("other.toml")).unwrap(), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_deleted_templates_clear_the_list() {
+        let dir = std::env::temp_dir().join(format!("editrion-codex-delete-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let templates = dir.join(TEMPLATES_NAME);
+        let mut cache = CodexConfigCache::new();
+
+        // Templates kept in the settings are left alone when there never was a file
+        assert_eq!(cache.reload(&templates).unwrap(), None);
+        fs::write(&templates, r#"[{ "name": "Fix", "instruction": "fix it" }]"#).unwrap();
+        assert_eq!(cache.reload(&templates).unwrap().map(|t| t.len()), Some(1));
+        fs::remove_file(&templates).unwrap();
+        assert_eq!(cache.reload(&templates).unwrap(), Some(Vec::new()));
+        assert_eq!(cache.reload(&templates).unwrap(), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
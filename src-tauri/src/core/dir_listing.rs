@@ -0,0 +1,256 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirEntry {
+    pub name: String,
+    pub path: String,
+    /// True for directories and for links that point at one
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    pub symlink_target: Option<String>,
+    pub size: u64,
+    /// Milliseconds since the Unix epoch
+    pub modified: Option<u64>,
+    pub readonly: bool,
+    /// Unix permission bits, `None` on other platforms
+    pub mode: Option<u32>,
+    pub hidden: bool,
+    /// Excluded by `.gitignore`, `.ignore` or git's exclude files
+    pub ignored: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DirSort {
+    /// By name with digit runs compared as numbers ("file2" before "file10")
+    #[default]
+    Natural,
+    Name,
+    /// Most recently modified first
+    Modified,
+    /// Largest first
+    Size,
+    /// Keep the order the OS returned
+    None,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReadDirOptions {
+    pub sort: DirSort,
+    pub folders_first: bool,
+    pub show_hidden: bool,
+    /// Leave out entries matched by ignore files instead of only flagging them
+    pub hide_ignored: bool,
+}
+
+impl Default for ReadDirOptions {
+    fn default() -> Self {
+        Self {
+            sort: DirSort::Natural,
+            folders_first: true,
+            show_hidden: true,
+            hide_ignored: false,
+        }
+    }
+}
+
+pub fn read_dir(path: &Path, options: &ReadDirOptions) -> Result<Vec<DirEntry>> {
+    let visible = not_ignored(path);
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let entry_path = entry.path();
+        let link_meta = entry.metadata()?;
+        let is_symlink = link_meta.file_type().is_symlink();
+        // Dangling links have no target metadata; describe the link itself
+        let meta = if is_symlink { fs::metadata(&entry_path).unwrap_or(link_meta) } else { link_meta };
+
+        let name = entry.file_name().to_string_lossy().to_string();
+        let hidden = is_hidden(&name, &meta);
+        let ignored = !visible.contains(&entry.file_name());
+        if (hidden && !options.show_hidden) || (ignored && options.hide_ignored) {
+            continue;
+        }
+
+        entries.push(DirEntry {
+            path: entry_path.to_string_lossy().to_string(),
+            is_dir: meta.is_dir(),
+            is_symlink,
+            symlink_target: if is_symlink {
+                fs::read_link(&entry_path).ok().map(|p| p.to_string_lossy().to_string())
+            } else {
+                None
+            },
+            size: meta.len(),
+            modified: meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as u64),
+            readonly: meta.permissions().readonly(),
+            mode: mode(&meta),
+            hidden,
+            ignored,
+            name,
+        });
+    }
+    sort_entries(&mut entries, options);
+    Ok(entries)
+}
+
+/// Names in `dir` that survive the ignore rules of the directory and its parents
+pub fn not_ignored(dir: &Path) -> HashSet<OsString> {
+    ignore::WalkBuilder::new(dir)
+        .max_depth(Some(1))
+        .hidden(false)
+        .parents(true)
+        .build()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.depth() == 1)
+        .map(|e| e.file_name().to_os_string())
+        .collect()
+}
+
+fn sort_entries(entries: &mut [DirEntry], options: &ReadDirOptions) {
+    entries.sort_by(|a, b| {
+        let folders = if options.folders_first { b.is_dir.cmp(&a.is_dir) } else { Ordering::Equal };
+        folders.then_with(|| match options.sort {
+            DirSort::Natural => natural_cmp(&a.name, &b.name),
+            DirSort::Name => a.name.cmp(&b.name),
+            DirSort::Modified => b.modified.cmp(&a.modified).then_with(|| natural_cmp(&a.name, &b.name)),
+            DirSort::Size => b.size.cmp(&a.size).then_with(|| natural_cmp(&a.name, &b.name)),
+            DirSort::None => Ordering::Equal,
+        })
+    });
+}
+
+/// Case-insensitive comparison that orders runs of digits by their numeric value
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut x, mut y) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        match (x.peek().copied(), y.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(c), Some(d)) if c.is_ascii_digit() && d.is_ascii_digit() => {
+                let (m, n) = (take_digits(&mut x), take_digits(&mut y));
+                let (m_trim, n_trim) = (m.trim_start_matches('0'), n.trim_start_matches('0'));
+                let ord = m_trim.len().cmp(&n_trim.len()).then_with(|| m_trim.cmp(n_trim));
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            (Some(c), Some(d)) => {
+                let ord = c.to_lowercase().cmp(d.to_lowercase());
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+                x.next();
+                y.next();
+            }
+        }
+    }
+}
+
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        digits.push(c);
+    }
+    digits
+}
+
+#[cfg(windows)]
+fn is_hidden(name: &str, meta: &fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    name.starts_with('.') || meta.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0
+}
+
+#[cfg(not(windows))]
+fn is_hidden(name: &str, _meta: &fs::Metadata) -> bool {
+    name.starts_with('.')
+}
+
+#[cfg(unix)]
+fn mode(meta: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(meta.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn mode(_meta: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("editrion-listing-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn names(entries: &[DirEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut v = vec!["file10.txt", "File2.txt", "file1.txt", "file01.txt", "a", "B"];
+        v.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(v, vec!["a", "B", "file01.txt", "file1.txt", "File2.txt", "file10.txt"]);
+    }
+
+    #[test]
+    fn test_folders_first_and_hidden() {
+        let dir = temp_dir("sort");
+        fs::create_dir(dir.join("zeta")).unwrap();
+        fs::write(dir.join("a10.rs"), "").unwrap();
+        fs::write(dir.join("a9.rs"), "").unwrap();
+        fs::write(dir.join(".env"), "").unwrap();
+
+        let all = read_dir(&dir, &ReadDirOptions::default()).unwrap();
+        assert_eq!(names(&all), vec!["zeta", ".env", "a9.rs", "a10.rs"]);
+        assert!(all[1].hidden);
+
+        let options = ReadDirOptions { show_hidden: false, folders_first: false, ..Default::default() };
+        assert_eq!(names(&read_dir(&dir, &options).unwrap()), vec!["a9.rs", "a10.rs", "zeta"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_ignore_files() {
+        let dir = temp_dir("ignore");
+        fs::create_dir(dir.join(".git")).unwrap();
+        fs::write(dir.join(".gitignore"), "target/\n*.log\n").unwrap();
+        fs::create_dir(dir.join("target")).unwrap();
+        fs::create_dir(dir.join("src")).unwrap();
+        fs::write(dir.join("build.log"), "").unwrap();
+        fs::write(dir.join("src/.ignore"), "generated.rs\n").unwrap();
+        fs::write(dir.join("src/generated.rs"), "").unwrap();
+        fs::write(dir.join("src/main.rs"), "").unwrap();
+
+        let entries = read_dir(&dir, &ReadDirOptions::default()).unwrap();
+        let ignored: Vec<&str> = entries.iter().filter(|e| e.ignored).map(|e| e.name.as_str()).collect();
+        assert_eq!(ignored, vec!["target", "build.log"]);
+
+        let options = ReadDirOptions { hide_ignored: true, ..Default::default() };
+        assert_eq!(names(&read_dir(&dir, &options).unwrap()), vec![".git", "src", ".gitignore"]);
+        assert_eq!(names(&read_dir(&dir.join("src"), &options).unwrap()), vec![".ignore", "main.rs"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,231 @@
+use encoding_rs::{EncoderResult, Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+
+/// How many leading bytes are inspected by the UTF-16 heuristic
+const UTF16_SAMPLE_LEN: usize = 4096;
+
+/// Text decoded from disk together with what is needed to write it back unchanged
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedText {
+    pub content: String,
+    /// WHATWG encoding name, e.g. `UTF-8`, `windows-1251`, `Shift_JIS`, `UTF-16LE`
+    pub encoding: String,
+    pub has_bom: bool,
+    /// True when some bytes were not valid in the detected encoding and got replaced
+    pub had_errors: bool,
+}
+
+/// Detect the encoding of `bytes` and decode them.
+///
+/// Order of checks: byte order mark, BOM-less UTF-16 (NUL byte distribution), strict UTF-8,
+/// then a statistical guess among legacy single- and multi-byte code pages.
+pub fn decode(bytes: &[u8]) -> DecodedText {
+    let (encoding, bom_len) = match Encoding::for_bom(bytes) {
+        Some((enc, len)) => (enc, len),
+        None => (detect_without_bom(bytes), 0),
+    };
+    let (content, had_errors) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+    DecodedText {
+        content: content.into_owned(),
+        encoding: encoding.name().to_string(),
+        has_bom: bom_len > 0,
+        had_errors,
+    }
+}
+
+fn detect_without_bom(bytes: &[u8]) -> &'static Encoding {
+    if let Some(enc) = sniff_utf16(bytes) {
+        return enc;
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return UTF_8;
+    }
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, true);
+    detector.guess(None, true)
+}
+
+/// BOM-less UTF-16 is recognised by NUL bytes concentrated at odd (LE) or even (BE) offsets,
+/// which is what mostly-ASCII text looks like in that encoding.
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(UTF16_SAMPLE_LEN)];
+    let pairs = sample.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+    let (mut even_nul, mut odd_nul) = (0usize, 0usize);
+    for pair in sample.chunks_exact(2) {
+        if pair[0] == 0 { even_nul += 1; }
+        if pair[1] == 0 { odd_nul += 1; }
+    }
+    let many = pairs * 3 / 10;
+    let few = pairs / 20;
+    if odd_nul > many && even_nul <= few {
+        Some(UTF_16LE)
+    } else if even_nul > many && odd_nul <= few {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// True for UTF-16 text, with or without a byte order mark
+pub fn looks_like_utf16(bytes: &[u8]) -> bool {
+    match Encoding::for_bom(bytes) {
+        Some((enc, _)) => enc == UTF_16LE || enc == UTF_16BE,
+        None => sniff_utf16(bytes).is_some(),
+    }
+}
+
+/// Look up an encoding by any WHATWG label (`latin1`, `cp1251`, `sjis`, `utf-16`, ...)
+pub fn encoding_for_label(label: &str) -> Result<&'static Encoding> {
+    let enc = Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| AppError::UnsupportedEncoding(label.to_string()))?;
+    // The "replacement" encoding only exists to neuter dangerous labels; it cannot be written
+    if enc == encoding_rs::REPLACEMENT {
+        return Err(AppError::UnsupportedEncoding(label.to_string()));
+    }
+    Ok(enc)
+}
+
+/// Encode `text` with the encoding named by `label`, optionally prefixed with a BOM.
+///
+/// Fails with `AppError::Unencodable` pointing at the first character that has no
+/// representation in the target encoding instead of silently substituting it.
+pub fn encode(text: &str, label: &str, with_bom: bool) -> Result<Vec<u8>> {
+    let enc = encoding_for_label(label)?;
+
+    if enc == UTF_16LE || enc == UTF_16BE {
+        // encoding_rs follows WHATWG and never produces UTF-16, so do it by hand
+        let le = enc == UTF_16LE;
+        let mut out = Vec::with_capacity(text.len() * 2 + 2);
+        let units = with_bom.then_some(0xFEFFu16).into_iter().chain(text.encode_utf16());
+        for unit in units {
+            out.extend_from_slice(&if le { unit.to_le_bytes() } else { unit.to_be_bytes() });
+        }
+        return Ok(out);
+    }
+
+    let mut out = Vec::with_capacity(text.len() + 3);
+    if enc == UTF_8 {
+        if with_bom { out.extend_from_slice(b"\xEF\xBB\xBF"); }
+        out.extend_from_slice(text.as_bytes());
+        return Ok(out);
+    }
+
+    let mut encoder = enc.new_encoder();
+    let mut read = 0usize;
+    loop {
+        let remaining = &text[read..];
+        out.reserve(
+            encoder
+                .max_buffer_length_from_utf8_without_replacement(remaining.len())
+                .unwrap_or(remaining.len() * 4 + 16),
+        );
+        let (result, consumed) = encoder.encode_from_utf8_to_vec_without_replacement(remaining, &mut out, true);
+        read += consumed;
+        match result {
+            EncoderResult::InputEmpty => return Ok(out),
+            EncoderResult::OutputFull => continue,
+            EncoderResult::Unmappable(ch) => {
+                let offset = read - ch.len_utf8();
+                let (line, column) = line_and_column(text, offset);
+                return Err(AppError::Unencodable {
+                    encoding: enc.name().to_string(),
+                    character: ch.to_string(),
+                    line,
+                    column,
+                });
+            }
+        }
+    }
+}
+
+/// 1-based line and column (in characters) of a byte offset
+fn line_and_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_utf8_and_bom() {
+        let plain = decode("привіт".as_bytes());
+        assert_eq!(plain.encoding, "UTF-8");
+        assert!(!plain.has_bom);
+
+        let with_bom = decode(b"\xEF\xBB\xBFhello");
+        assert_eq!(with_bom.encoding, "UTF-8");
+        assert!(with_bom.has_bom);
+        assert_eq!(with_bom.content, "hello");
+    }
+
+    #[test]
+    fn test_decode_utf16_without_bom() {
+        let le: Vec<u8> = "key = value\n".encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        let decoded = decode(&le);
+        assert_eq!(decoded.encoding, "UTF-16LE");
+        assert_eq!(decoded.content, "key = value\n");
+
+        let be: Vec<u8> = "key = value\n".encode_utf16().flat_map(|u| u.to_be_bytes()).collect();
+        assert_eq!(decode(&be).encoding, "UTF-16BE");
+    }
+
+    #[test]
+    fn test_decode_legacy_code_pages() {
+        let text = "Параметр конфигурации = значение по умолчанию\n";
+        let (bytes, _, _) = encoding_rs::WINDOWS_1251.encode(text);
+        let decoded = decode(&bytes);
+        assert_eq!(decoded.encoding, "windows-1251");
+        assert_eq!(decoded.content, text);
+
+        let text = "設定ファイルの値を変更してください。日本語のテキストです。\n";
+        let (bytes, _, _) = encoding_rs::SHIFT_JIS.encode(text);
+        let decoded = decode(&bytes);
+        assert_eq!(decoded.encoding, "Shift_JIS");
+        assert_eq!(decoded.content, text);
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        for (label, text, bom) in [
+            ("windows-1251", "ключ = значення", false),
+            ("shift_jis", "設定", false),
+            ("latin1", "café", false),
+            ("utf-16le", "abc", true),
+            ("utf-16be", "abc", false),
+            ("utf-8", "abc", true),
+        ] {
+            let bytes = encode(text, label, bom).unwrap();
+            let decoded = decode(&bytes);
+            assert_eq!(decoded.content, text, "label {}", label);
+            assert_eq!(decoded.has_bom, bom, "label {}", label);
+        }
+    }
+
+    #[test]
+    fn test_encode_unrepresentable_character() {
+        match encode("ok\nabЖc", "windows-1252", false) {
+            Err(AppError::Unencodable { character, line, column, .. }) => {
+                assert_eq!(character, "Ж");
+                assert_eq!((line, column), (2, 3));
+            }
+            other => panic!("Expected Unencodable, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_encoding_for_label_rejects_unknown() {
+        assert!(matches!(encoding_for_label("klingon"), Err(AppError::UnsupportedEncoding(_))));
+        assert!(matches!(encoding_for_label("iso-2022-kr"), Err(AppError::UnsupportedEncoding(_))));
+        assert_eq!(encoding_for_label("cp1251").unwrap().name(), "windows-1251");
+    }
+}
@@ -0,0 +1,362 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::core::atomic_write::is_cross_device;
+use crate::error::{AppError, Result};
+
+/// What to do when the destination of a move or copy already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverwritePolicy {
+    /// Refuse with `AppError::TargetExists`
+    #[default]
+    Fail,
+    /// Replace the destination; the old one is only deleted once the
+    /// replacement is in place
+    Overwrite,
+}
+
+/// Attach the offending path to io errors the explorer reports specially
+pub fn path_error(e: io::Error, path: &Path) -> AppError {
+    let path = path.to_string_lossy().to_string();
+    match e.kind() {
+        io::ErrorKind::NotFound => AppError::NotFound(path),
+        io::ErrorKind::PermissionDenied => AppError::PermissionDenied(path),
+        io::ErrorKind::AlreadyExists => AppError::TargetExists(path),
+        _ => AppError::Io(e),
+    }
+}
+
+/// Create `path` and any missing parents; an existing directory is fine
+pub fn create_dir(path: &Path) -> Result<()> {
+    if path.exists() && !path.is_dir() {
+        return Err(AppError::TargetExists(path.to_string_lossy().to_string()));
+    }
+    fs::create_dir_all(path).map_err(|e| path_error(e, path))
+}
+
+/// Rename or move `from` to `to`, falling back to copy and delete across file systems
+pub fn move_path(from: &Path, to: &Path, policy: OverwritePolicy) -> Result<()> {
+    fs::symlink_metadata(from).map_err(|e| path_error(e, from))?;
+    // Case-only renames on case-insensitive file systems see `to` as existing
+    let replace = !is_same_file(from, to) && check_target(from, to, policy)?;
+    if !replace {
+        return move_into_place(from, to);
+    }
+    // rename(2) swaps one file for another in a single step
+    #[cfg(unix)]
+    if !is_dir(from) && !is_dir(to) {
+        match fs::rename(from, to) {
+            Ok(()) => return Ok(()),
+            Err(e) if !is_cross_device(&e) => return Err(path_error(e, from)),
+            Err(_) => {}
+        }
+    }
+    SetAside::new(to)?.finish(move_into_place(from, to))
+}
+
+/// Rename `from` to the free path `to`, or copy and delete across file systems
+fn move_into_place(from: &Path, to: &Path) -> Result<()> {
+    match fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(e) if is_cross_device(&e) => {
+            copy_into_place(from, to)?;
+            remove_any(from).map_err(|e| path_error(e, from))
+        }
+        Err(e) => Err(path_error(e, from)),
+    }
+}
+
+/// Copy `from` to the free path `to`, removing a partial copy when it fails
+fn copy_into_place(from: &Path, to: &Path) -> Result<()> {
+    copy_recursive(from, to).map_err(|e| {
+        let _ = remove_any(to);
+        path_error(e, to)
+    })
+}
+
+/// An existing destination moved out of the way while it is replaced
+struct SetAside {
+    original: PathBuf,
+    aside: PathBuf,
+}
+
+impl SetAside {
+    /// Rename `path` to a hidden ".<name>.editrion-old" sibling
+    fn new(path: &Path) -> Result<Self> {
+        let parent = path.parent().unwrap_or_else(|| Path::new(""));
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let mut aside = parent.join(format!(".{}.editrion-old", name));
+        let mut n = 2;
+        while fs::symlink_metadata(&aside).is_ok() {
+            aside = parent.join(format!(".{}.editrion-old-{}", name, n));
+            n += 1;
+        }
+        fs::rename(path, &aside).map_err(|e| path_error(e, path))?;
+        Ok(Self { original: path.to_path_buf(), aside })
+    }
+
+    /// Delete the old destination if `result` succeeded, otherwise put it back
+    fn finish(self, result: Result<()>) -> Result<()> {
+        match result {
+            Ok(()) => {
+                let _ = remove_any(&self.aside);
+                Ok(())
+            }
+            Err(e) => {
+                if fs::symlink_metadata(&self.original).is_ok() {
+                    let _ = remove_any(&self.original);
+                }
+                let _ = fs::rename(&self.aside, &self.original);
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Copy a file or a whole directory tree; symlinks are copied as links on Unix
+pub fn copy_path(from: &Path, to: &Path, policy: OverwritePolicy) -> Result<()> {
+    fs::symlink_metadata(from).map_err(|e| path_error(e, from))?;
+    if is_same_file(from, to) {
+        return Err(AppError::InvalidTarget {
+            path: to.to_string_lossy().to_string(),
+            reason: "source and destination are the same".to_string(),
+        });
+    }
+    if check_target(from, to, policy)? {
+        SetAside::new(to)?.finish(copy_into_place(from, to))
+    } else {
+        copy_into_place(from, to)
+    }
+}
+
+/// Copy `path` next to itself as "name copy.ext", "name copy 2.ext", ... and return the new path
+pub fn duplicate_path(path: &Path) -> Result<PathBuf> {
+    fs::symlink_metadata(path).map_err(|e| path_error(e, path))?;
+    let target = unique_copy_name(path);
+    copy_recursive(path, &target).map_err(|e| path_error(e, &target))?;
+    Ok(target)
+}
+
+/// Whether `to` exists and is to be replaced; errors when it may not be
+fn check_target(from: &Path, to: &Path, policy: OverwritePolicy) -> Result<bool> {
+    let from_abs = fs::canonicalize(from).unwrap_or_else(|_| from.to_path_buf());
+    let to_parent = to
+        .parent()
+        .and_then(|p| fs::canonicalize(p).ok())
+        .unwrap_or_else(|| to.to_path_buf());
+    if from.is_dir() && to_parent.starts_with(&from_abs) {
+        return Err(AppError::InvalidTarget {
+            path: to.to_string_lossy().to_string(),
+            reason: "a folder cannot be moved or copied into itself".to_string(),
+        });
+    }
+    if fs::symlink_metadata(to).is_err() {
+        return Ok(false);
+    }
+    // Overwriting a folder that contains the source would delete the source too
+    if fs::canonicalize(to).map(|t| from_abs.starts_with(t)).unwrap_or(false) {
+        return Err(AppError::InvalidTarget {
+            path: to.to_string_lossy().to_string(),
+            reason: "the destination contains the item being moved or copied".to_string(),
+        });
+    }
+    match policy {
+        OverwritePolicy::Fail => Err(AppError::TargetExists(to.to_string_lossy().to_string())),
+        OverwritePolicy::Overwrite => Ok(true),
+    }
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    let link_meta = fs::symlink_metadata(from)?;
+    #[cfg(unix)]
+    if link_meta.file_type().is_symlink() {
+        return std::os::unix::fs::symlink(fs::read_link(from)?, to);
+    }
+    // Creating links needs extra privileges on Windows; copy what the link points to
+    let meta = if link_meta.file_type().is_symlink() { fs::metadata(from)? } else { link_meta };
+    if meta.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        fs::set_permissions(to, meta.permissions())
+    } else {
+        fs::copy(from, to).map(|_| ())
+    }
+}
+
+fn remove_any(path: &Path) -> io::Result<()> {
+    let meta = fs::symlink_metadata(path)?;
+    if meta.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Directories themselves, not links to them
+#[cfg(unix)]
+fn is_dir(path: &Path) -> bool {
+    fs::symlink_metadata(path).map(|m| m.is_dir()).unwrap_or(false)
+}
+
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn unique_copy_name(path: &Path) -> PathBuf {
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    // Keep the extension for files; "archive.tar.gz" becomes "archive copy.tar.gz"
+    let (stem, ext) = match name.find('.') {
+        Some(i) if i > 0 && !path.is_dir() => (&name[..i], &name[i..]),
+        _ => (name.as_str(), ""),
+    };
+    let mut n = 1;
+    loop {
+        let candidate = if n == 1 {
+            format!("{} copy{}", stem, ext)
+        } else {
+            format!("{} copy {}{}", stem, n, ext)
+        };
+        let target = parent.join(candidate);
+        if fs::symlink_metadata(&target).is_err() {
+            return target;
+        }
+        n += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("editrion-ops-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_create_dir_recursive() {
+        let dir = temp_dir("mkdir");
+        let nested = dir.join("a/b/c");
+        create_dir(&nested).unwrap();
+        assert!(nested.is_dir());
+        create_dir(&nested).unwrap();
+
+        let file = dir.join("file");
+        fs::write(&file, "x").unwrap();
+        assert!(matches!(create_dir(&file), Err(AppError::TargetExists(_))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_move_respects_policy() {
+        let dir = temp_dir("move");
+        let (a, b) = (dir.join("a.txt"), dir.join("b.txt"));
+        fs::write(&a, "a").unwrap();
+        fs::write(&b, "b").unwrap();
+
+        assert!(matches!(move_path(&a, &b, OverwritePolicy::Fail), Err(AppError::TargetExists(_))));
+        assert_eq!(fs::read_to_string(&b).unwrap(), "b");
+
+        move_path(&a, &b, OverwritePolicy::Overwrite).unwrap();
+        assert!(!a.exists());
+        assert_eq!(fs::read_to_string(&b).unwrap(), "a");
+
+        assert!(matches!(
+            move_path(&a, &b, OverwritePolicy::Overwrite),
+            Err(AppError::NotFound(_))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_copy_tree() {
+        let dir = temp_dir("copy");
+        let src = dir.join("src");
+        fs::create_dir_all(src.join("sub")).unwrap();
+        fs::write(src.join("one.txt"), "1").unwrap();
+        fs::write(src.join("sub/two.txt"), "2").unwrap();
+
+        let dst = dir.join("dst");
+        copy_path(&src, &dst, OverwritePolicy::Fail).unwrap();
+        assert_eq!(fs::read_to_string(dst.join("sub/two.txt")).unwrap(), "2");
+        assert!(src.join("one.txt").exists());
+
+        assert!(matches!(
+            copy_path(&src, &src.join("sub/inner"), OverwritePolicy::Fail),
+            Err(AppError::InvalidTarget { .. })
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_overwrite_replaces_folders() {
+        let dir = temp_dir("replace");
+        let (src, dst) = (dir.join("src"), dir.join("dst"));
+        fs::create_dir(&src).unwrap();
+        fs::write(src.join("new.txt"), "new").unwrap();
+        fs::create_dir(&dst).unwrap();
+        fs::write(dst.join("old.txt"), "old").unwrap();
+
+        copy_path(&src, &dst, OverwritePolicy::Overwrite).unwrap();
+        assert!(dst.join("new.txt").exists() && !dst.join("old.txt").exists());
+        move_path(&src, &dst, OverwritePolicy::Overwrite).unwrap();
+        assert!(!src.exists() && dst.join("new.txt").exists());
+        // Nothing left over from setting the old destination aside
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failed_overwrite_keeps_destination() {
+        let dir = temp_dir("keep");
+        let (src, dst) = (dir.join("src"), dir.join("dst"));
+        fs::create_dir(&src).unwrap();
+        fs::write(src.join("a.txt"), "a").unwrap();
+        // Sockets cannot be copied, even with root's permissions
+        let _socket = std::os::unix::net::UnixListener::bind(src.join("z.sock")).unwrap();
+        fs::create_dir(&dst).unwrap();
+        fs::write(dst.join("old.txt"), "old").unwrap();
+
+        assert!(copy_path(&src, &dst, OverwritePolicy::Overwrite).is_err());
+        assert_eq!(fs::read_to_string(dst.join("old.txt")).unwrap(), "old");
+        assert!(!dst.join("a.txt").exists());
+        let mut names: Vec<_> = fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name()).collect();
+        names.sort();
+        assert_eq!(names, ["dst", "src"]);
+
+        // A fresh destination is not left half copied either
+        let fresh = dir.join("fresh");
+        assert!(copy_path(&src, &fresh, OverwritePolicy::Fail).is_err());
+        assert!(!fresh.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_duplicate_picks_unique_names() {
+        let dir = temp_dir("dup");
+        let file = dir.join("notes.tar.gz");
+        fs::write(&file, "x").unwrap();
+
+        assert_eq!(duplicate_path(&file).unwrap(), dir.join("notes copy.tar.gz"));
+        assert_eq!(duplicate_path(&file).unwrap(), dir.join("notes copy 2.tar.gz"));
+
+        let folder = dir.join("v1.0");
+        fs::create_dir(&folder).unwrap();
+        assert_eq!(duplicate_path(&folder).unwrap(), dir.join("v1.0 copy"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,185 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::core::encoding::looks_like_utf16;
+use crate::error::Result;
+
+/// Bytes read from the start of a file to decide whether it is text
+pub const SNIFF_LEN: usize = 8192;
+
+/// Files above this size are refused by `read_file` unless a different limit is passed
+pub const DEFAULT_MAX_READ_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Share of bytes in invalid UTF-8 sequences above which the sample is suspicious
+const MAX_INVALID_UTF8_RATIO: f64 = 0.3;
+
+/// Share of non-whitespace control bytes above which a suspicious sample counts as binary
+const MAX_CONTROL_RATIO: f64 = 0.05;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileStat {
+    pub path: String,
+    pub size: u64,
+    pub is_dir: bool,
+    pub is_symlink: bool,
+    pub symlink_target: Option<String>,
+    pub readonly: bool,
+    /// Milliseconds since the Unix epoch
+    pub modified: Option<u64>,
+    pub mime: String,
+    pub is_binary: bool,
+    pub invalid_utf8_ratio: f64,
+    pub exceeds_read_limit: bool,
+}
+
+/// Verdict on the first bytes of a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sniff {
+    pub mime: String,
+    pub is_binary: bool,
+    pub invalid_utf8_ratio: f64,
+}
+
+/// Preflight information about `path` without loading the whole file
+pub fn stat(path: &Path) -> Result<FileStat> {
+    let link_meta = fs::symlink_metadata(path)?;
+    let is_symlink = link_meta.file_type().is_symlink();
+    let symlink_target = if is_symlink {
+        fs::read_link(path).ok().map(|p| p.to_string_lossy().to_string())
+    } else {
+        None
+    };
+    // Dangling links have no target metadata; report the link itself
+    let meta = fs::metadata(path).unwrap_or(link_meta);
+
+    let sniff = if meta.is_file() {
+        let mut sample = Vec::with_capacity(SNIFF_LEN);
+        File::open(path)?.take(SNIFF_LEN as u64).read_to_end(&mut sample)?;
+        sniff(&sample)
+    } else {
+        Sniff {
+            mime: if meta.is_dir() { "inode/directory" } else { "application/octet-stream" }.to_string(),
+            is_binary: false,
+            invalid_utf8_ratio: 0.0,
+        }
+    };
+
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64);
+
+    Ok(FileStat {
+        path: path.to_string_lossy().to_string(),
+        size: meta.len(),
+        is_dir: meta.is_dir(),
+        is_symlink,
+        symlink_target,
+        readonly: meta.permissions().readonly(),
+        modified,
+        mime: sniff.mime,
+        is_binary: sniff.is_binary,
+        invalid_utf8_ratio: sniff.invalid_utf8_ratio,
+        exceeds_read_limit: meta.is_file() && meta.len() > DEFAULT_MAX_READ_BYTES,
+    })
+}
+
+/// Classify a sample by magic numbers, NUL bytes and how much of it is invalid UTF-8
+pub fn sniff(sample: &[u8]) -> Sniff {
+    let invalid_utf8_ratio = invalid_utf8_ratio(sample);
+
+    if let Some(kind) = infer::get(sample) {
+        return Sniff {
+            mime: kind.mime_type().to_string(),
+            is_binary: kind.matcher_type() != infer::MatcherType::Text,
+            invalid_utf8_ratio,
+        };
+    }
+
+    // UTF-16 text is full of NULs, so let the encoding sniffer vouch for it first
+    let has_nul = sample.contains(&0) && !looks_like_utf16(sample);
+    let control = sample.iter().filter(|&&b| is_control(b)).count();
+    let control_ratio = if sample.is_empty() { 0.0 } else { control as f64 / sample.len() as f64 };
+    let is_binary = has_nul || (invalid_utf8_ratio > MAX_INVALID_UTF8_RATIO && control_ratio > MAX_CONTROL_RATIO);
+
+    Sniff {
+        mime: if is_binary { "application/octet-stream" } else { "text/plain" }.to_string(),
+        is_binary,
+        invalid_utf8_ratio,
+    }
+}
+
+fn is_control(b: u8) -> bool {
+    (b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0B | 0x0C | 0x1B)) || b == 0x7F
+}
+
+/// Share of bytes that belong to invalid UTF-8 sequences (a sequence cut off by the end of
+/// the sample is not counted)
+fn invalid_utf8_ratio(sample: &[u8]) -> f64 {
+    if sample.is_empty() {
+        return 0.0;
+    }
+    let mut invalid = 0usize;
+    let mut rest = sample;
+    while !rest.is_empty() {
+        match std::str::from_utf8(rest) {
+            Ok(_) => break,
+            Err(e) => match e.error_len() {
+                Some(len) => {
+                    invalid += len;
+                    rest = &rest[e.valid_up_to() + len..];
+                }
+                None => break,
+            },
+        }
+    }
+    invalid as f64 / sample.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_plain_text() {
+        let s = sniff(b"fn main() {\n    println!(\"hi\");\n}\n");
+        assert!(!s.is_binary);
+        assert_eq!(s.mime, "text/plain");
+        assert_eq!(s.invalid_utf8_ratio, 0.0);
+    }
+
+    #[test]
+    fn test_sniff_png_is_binary() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01\0\0\0\x01\x08\x06\0\0\0";
+        let s = sniff(png);
+        assert!(s.is_binary);
+        assert_eq!(s.mime, "image/png");
+    }
+
+    #[test]
+    fn test_sniff_nul_bytes_and_utf16() {
+        assert!(sniff(b"abc\0\0\0def\x01\x02\x03").is_binary);
+        let utf16: Vec<u8> = "hello world\n".encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+        assert!(!sniff(&utf16).is_binary);
+    }
+
+    #[test]
+    fn test_sniff_legacy_encoding_is_text() {
+        let (cp1251, _, _) = encoding_rs::WINDOWS_1251.encode("Привет, мир! Это обычный текст.\n");
+        let s = sniff(&cp1251);
+        assert!(s.invalid_utf8_ratio > MAX_INVALID_UTF8_RATIO);
+        assert!(!s.is_binary);
+    }
+
+    #[test]
+    fn test_invalid_utf8_ratio_ignores_truncated_tail() {
+        // "é" cut in half at the end of the sample
+        assert_eq!(invalid_utf8_ratio(b"abc\xC3"), 0.0);
+        assert_eq!(invalid_utf8_ratio(b"\xFF\xFFab"), 0.5);
+    }
+}
@@ -0,0 +1,185 @@
+use std::fs::{File, Metadata};
+use std::io::{self, Read};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::{AppError, Result};
+
+/// Identifies the exact contents a file had when the editor read it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileVersion {
+    /// Modification time in milliseconds since the Unix epoch
+    pub mtime: Option<u64>,
+    pub size: u64,
+    /// Hex SHA-256 of the raw bytes on disk
+    pub hash: String,
+    /// Only the start of the file was read; such a buffer may not be saved over the file
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+impl FileVersion {
+    pub fn from_bytes(meta: &Metadata, bytes: &[u8]) -> Self {
+        Self {
+            mtime: mtime_millis(meta),
+            size: meta.len(),
+            hash: hash_bytes(bytes),
+            truncated: false,
+        }
+    }
+
+    /// Version of whatever is on disk now, `None` if the file does not exist
+    pub fn of_path(path: &Path) -> Result<Option<Self>> {
+        let mut file = match File::open(path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let meta = file.metadata()?;
+        let mut hasher = Sha256::new();
+        io::copy(&mut file, &mut hasher)?;
+        Ok(Some(Self {
+            mtime: mtime_millis(&meta),
+            size: meta.len(),
+            hash: hex(&hasher.finalize()),
+            truncated: false,
+        }))
+    }
+
+    /// Whether two versions describe the same bytes; the mtime is ignored so a `touch`
+    /// is not a conflict
+    fn same_contents(&self, other: &FileVersion) -> bool {
+        self.size == other.size && self.hash == other.hash
+    }
+}
+
+/// Read at most `limit` bytes of `path` with their version. Larger files are refused with
+/// `AppError::FileTooLarge`, or cut to the limit when `truncate` is set; the version of a
+/// cut read is marked `truncated` so the partial buffer cannot be saved over the file.
+pub fn read_limited(path: &Path, limit: u64, truncate: bool) -> Result<(Vec<u8>, FileVersion)> {
+    let file = File::open(path)?;
+    let meta = file.metadata()?;
+    let size = meta.len();
+    if size > limit && !truncate {
+        return Err(AppError::FileTooLarge { path: path.to_string_lossy().to_string(), size, limit });
+    }
+    let mut bytes = Vec::with_capacity(size.min(limit) as usize);
+    file.take(limit).read_to_end(&mut bytes)?;
+    let version = FileVersion {
+        truncated: bytes.len() as u64 != size,
+        ..FileVersion::from_bytes(&meta, &bytes)
+    };
+    Ok((bytes, version))
+}
+
+/// Fail with `AppError::Conflict` unless the file on disk still matches `expected`.
+/// Unchanged mtime and size are trusted without re-hashing the file. A truncated
+/// version never matches: writing its buffer back would cut the file short.
+pub fn ensure_unchanged(path: &Path, expected: &FileVersion) -> Result<()> {
+    if expected.truncated {
+        return Err(AppError::PartialContent(path.to_string_lossy().to_string()));
+    }
+    if let Ok(meta) = std::fs::metadata(path) {
+        if meta.len() == expected.size && expected.mtime.is_some() && mtime_millis(&meta) == expected.mtime {
+            return Ok(());
+        }
+    }
+    let current = FileVersion::of_path(path)?;
+    match current {
+        Some(ref v) if v.same_contents(expected) => Ok(()),
+        _ => Err(AppError::Conflict {
+            path: path.to_string_lossy().to_string(),
+            current,
+        }),
+    }
+}
+
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn mtime_millis(meta: &Metadata) -> Option<u64> {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("editrion-version-{}-{}", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_version_matches_bytes() {
+        let path = temp_file("bytes", "hello");
+        let from_disk = FileVersion::of_path(&path).unwrap().unwrap();
+        let meta = std::fs::metadata(&path).unwrap();
+        assert_eq!(from_disk, FileVersion::from_bytes(&meta, b"hello"));
+        assert!(ensure_unchanged(&path, &from_disk).is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_conflict_on_external_change() {
+        let path = temp_file("conflict", "hello");
+        let version = FileVersion::of_path(&path).unwrap().unwrap();
+        std::fs::write(&path, "hello, changed elsewhere").unwrap();
+
+        match ensure_unchanged(&path, &version) {
+            Err(AppError::Conflict { current: Some(current), .. }) => {
+                assert_eq!(current.size, 24);
+                assert_ne!(current.hash, version.hash);
+            }
+            other => panic!("Expected Conflict, got {:?}", other),
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_touch_is_not_a_conflict() {
+        let path = temp_file("touch", "same");
+        let mut version = FileVersion::of_path(&path).unwrap().unwrap();
+        version.mtime = Some(0);
+        assert!(ensure_unchanged(&path, &version).is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_truncated_read_cannot_be_saved() {
+        let path = temp_file("truncated", "0123456789");
+        let (bytes, version) = read_limited(&path, 4, true).unwrap();
+        assert_eq!(bytes, b"0123");
+        assert!(version.truncated);
+        assert!(matches!(ensure_unchanged(&path, &version), Err(AppError::PartialContent(_))));
+        assert!(matches!(read_limited(&path, 4, false), Err(AppError::FileTooLarge { size: 10, .. })));
+
+        let (_, whole) = read_limited(&path, 10, false).unwrap();
+        assert!(!whole.truncated);
+        assert!(ensure_unchanged(&path, &whole).is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_conflict_when_deleted() {
+        let path = temp_file("deleted", "x");
+        let version = FileVersion::of_path(&path).unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            ensure_unchanged(&path, &version),
+            Err(AppError::Conflict { current: None, .. })
+        ));
+    }
+}
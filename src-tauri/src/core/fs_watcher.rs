@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use notify_debouncer_full::notify::event::{ModifyKind, RenameMode};
+use notify_debouncer_full::notify::{EventKind, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, Debouncer, RecommendedCache};
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+
+/// Quiet period before a burst of file system events is reported
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// A change relevant to a subscribed file or directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum FsEvent {
+    /// File contents changed, or a directory's listing changed
+    Changed { path: String },
+    Removed { path: String },
+    Renamed { from: String, to: String },
+}
+
+impl FsEvent {
+    /// Window event name used for this change
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            FsEvent::Changed { .. } => "fs-changed",
+            FsEvent::Removed { .. } => "fs-removed",
+            FsEvent::Renamed { .. } => "fs-renamed",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Subscription {
+    /// Path as given by the frontend, echoed back in events so it matches tab paths
+    original: String,
+    /// Directory actually watched for this path
+    dir: PathBuf,
+    count: usize,
+}
+
+type Subscriptions = Arc<Mutex<HashMap<PathBuf, Subscription>>>;
+
+/// FsWatcher watches opened files and expanded explorer directories.
+///
+/// Files are watched through their parent directory so replacements by rename
+/// (editors, formatters, `git checkout`) are still seen.
+pub struct FsWatcher {
+    debouncer: Option<Debouncer<RecommendedWatcher, RecommendedCache>>,
+    subscriptions: Subscriptions,
+    watched_dirs: HashMap<PathBuf, usize>,
+}
+
+impl Default for FsWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FsWatcher {
+    pub fn new() -> Self {
+        Self {
+            debouncer: None,
+            subscriptions: Arc::new(Mutex::new(HashMap::new())),
+            watched_dirs: HashMap::new(),
+        }
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.debouncer.is_some()
+    }
+
+    /// Start delivering events to `on_event`; later calls are no-ops
+    pub fn start(&mut self, on_event: impl Fn(FsEvent) + Send + 'static) -> Result<()> {
+        if self.debouncer.is_some() {
+            return Ok(());
+        }
+        let subscriptions = self.subscriptions.clone();
+        let debouncer = new_debouncer(DEBOUNCE_TIMEOUT, None, move |result: DebounceEventResult| {
+            let Ok(events) = result else { return };
+            let Ok(subs) = subscriptions.lock() else { return };
+            let mut seen = Vec::new();
+            for event in events {
+                for fs_event in classify(&event.kind, &event.paths, &subs) {
+                    if !seen.contains(&fs_event) {
+                        seen.push(fs_event);
+                    }
+                }
+            }
+            drop(subs);
+            for fs_event in seen {
+                on_event(fs_event);
+            }
+        })
+        .map_err(watch_error)?;
+        self.debouncer = Some(debouncer);
+
+        // Directories registered before the watcher existed
+        let dirs: Vec<PathBuf> = self.watched_dirs.keys().cloned().collect();
+        if let Some(debouncer) = self.debouncer.as_mut() {
+            for dir in dirs {
+                debouncer.watch(&dir, RecursiveMode::NonRecursive).map_err(watch_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn subscribe(&mut self, path: &str) -> Result<()> {
+        let key = normalize(Path::new(path));
+        let dir = watch_target(&key);
+        let is_new = {
+            let mut subs = self.subscriptions.lock().map_err(|e| AppError::Command(e.to_string()))?;
+            let sub = subs.entry(key.clone()).or_insert_with(|| Subscription {
+                original: path.to_string(),
+                dir: dir.clone(),
+                count: 0,
+            });
+            sub.count += 1;
+            sub.count == 1
+        };
+        if is_new {
+            if let Err(e) = self.watch_dir(&dir) {
+                self.forget(path);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn unsubscribe(&mut self, path: &str) -> Result<()> {
+        if let Some(dir) = self.forget(path) {
+            self.unwatch_dir(&dir);
+        }
+        Ok(())
+    }
+
+    /// Drop one reference to a subscription; returns its watched directory when it is gone
+    fn forget(&mut self, path: &str) -> Option<PathBuf> {
+        let mut subs = self.subscriptions.lock().ok()?;
+        let key = normalize(Path::new(path));
+        // A parent that was renamed or removed changes the key; fall back to the spelling
+        let key = if subs.contains_key(&key) {
+            key
+        } else {
+            subs.iter().find(|(_, s)| s.original == path).map(|(k, _)| k.clone())?
+        };
+        let sub = subs.get_mut(&key)?;
+        if sub.count > 1 {
+            sub.count -= 1;
+            return None;
+        }
+        subs.remove(&key).map(|s| s.dir)
+    }
+
+    fn watch_dir(&mut self, dir: &Path) -> Result<()> {
+        let count = self.watched_dirs.entry(dir.to_path_buf()).or_insert(0);
+        *count += 1;
+        if *count == 1 {
+            if let Some(debouncer) = self.debouncer.as_mut() {
+                if let Err(e) = debouncer.watch(dir, RecursiveMode::NonRecursive) {
+                    self.watched_dirs.remove(dir);
+                    return Err(watch_error(e));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn unwatch_dir(&mut self, dir: &Path) {
+        if let Some(count) = self.watched_dirs.get_mut(dir) {
+            *count -= 1;
+            if *count == 0 {
+                self.watched_dirs.remove(dir);
+                if let Some(debouncer) = self.debouncer.as_mut() {
+                    let _ = debouncer.unwatch(dir);
+                }
+            }
+        }
+    }
+}
+
+fn watch_error(e: impl std::fmt::Display) -> AppError {
+    AppError::Command(format!("File watcher error: {}", e))
+}
+
+/// Canonical form used to match event paths (e.g. `/var` vs `/private/var` on macOS).
+/// A path that no longer exists is resolved through its nearest existing ancestor,
+/// so a deleted file still maps to the key it was subscribed under.
+fn normalize(path: &Path) -> PathBuf {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut missing = Vec::new();
+    let mut existing = path.as_path();
+    loop {
+        if let Ok(real) = std::fs::canonicalize(existing) {
+            return missing.iter().rev().fold(real, |p, name| p.join(name));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return path,
+        }
+    }
+}
+
+fn watch_target(path: &Path) -> PathBuf {
+    if path.is_dir() {
+        path.to_path_buf()
+    } else {
+        path.parent().map(Path::to_path_buf).unwrap_or_else(|| path.to_path_buf())
+    }
+}
+
+/// Turn one raw event into the changes subscribers care about
+fn classify(kind: &EventKind, paths: &[PathBuf], subs: &HashMap<PathBuf, Subscription>) -> Vec<FsEvent> {
+    let display = |p: &Path| {
+        subs.get(p)
+            .map(|s| s.original.clone())
+            .unwrap_or_else(|| p.to_string_lossy().to_string())
+    };
+    let subscribed = |p: &Path| subs.contains_key(p);
+    let parent_subscribed = |p: &Path| p.parent().map(|d| subs.contains_key(d)).unwrap_or(false);
+    let parent_changed = |p: &Path, out: &mut Vec<FsEvent>| {
+        if let Some(dir) = p.parent().filter(|d| subs.contains_key(*d)) {
+            out.push(FsEvent::Changed { path: display(dir) });
+        }
+    };
+
+    let mut out = Vec::new();
+    match kind {
+        EventKind::Access(_) | EventKind::Other => {}
+        EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+            for p in paths {
+                if subscribed(p) {
+                    out.push(FsEvent::Changed { path: display(p) });
+                }
+                parent_changed(p, &mut out);
+            }
+        }
+        EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+            for p in paths {
+                if subscribed(p) {
+                    out.push(FsEvent::Removed { path: display(p) });
+                }
+                parent_changed(p, &mut out);
+            }
+        }
+        EventKind::Modify(ModifyKind::Name(_)) if paths.len() == 2 => {
+            let (from, to) = (&paths[0], &paths[1]);
+            if subscribed(from) || parent_subscribed(from) || parent_subscribed(to) {
+                out.push(FsEvent::Renamed { from: display(from), to: display(to) });
+            }
+            // Something was moved over an open file: its contents were replaced
+            if subscribed(to) && !subscribed(from) {
+                out.push(FsEvent::Changed { path: display(to) });
+            }
+        }
+        EventKind::Modify(_) | EventKind::Any => {
+            for p in paths {
+                if subscribed(p) {
+                    out.push(FsEvent::Changed { path: display(p) });
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify_debouncer_full::notify::event::{CreateKind, DataChange, RemoveKind};
+
+    fn subs(paths: &[&str]) -> HashMap<PathBuf, Subscription> {
+        paths
+            .iter()
+            .map(|p| {
+                let sub = Subscription { original: p.to_string(), dir: PathBuf::from(p), count: 1 };
+                (PathBuf::from(p), sub)
+            })
+            .collect()
+    }
+
+    fn changed(p: &str) -> FsEvent {
+        FsEvent::Changed { path: p.to_string() }
+    }
+
+    #[test]
+    fn test_classify_content_change() {
+        let s = subs(&["/p/a.txt"]);
+        let kind = EventKind::Modify(ModifyKind::Data(DataChange::Content));
+        assert_eq!(classify(&kind, &[PathBuf::from("/p/a.txt")], &s), vec![changed("/p/a.txt")]);
+        assert!(classify(&kind, &[PathBuf::from("/p/b.txt")], &s).is_empty());
+    }
+
+    #[test]
+    fn test_classify_directory_listing() {
+        let s = subs(&["/p"]);
+        let create = EventKind::Create(CreateKind::File);
+        assert_eq!(classify(&create, &[PathBuf::from("/p/new.rs")], &s), vec![changed("/p")]);
+
+        let remove = EventKind::Remove(RemoveKind::File);
+        assert_eq!(classify(&remove, &[PathBuf::from("/p/old.rs")], &s), vec![changed("/p")]);
+    }
+
+    #[test]
+    fn test_classify_removed_file() {
+        let s = subs(&["/p/a.txt"]);
+        let remove = EventKind::Remove(RemoveKind::Any);
+        assert_eq!(
+            classify(&remove, &[PathBuf::from("/p/a.txt")], &s),
+            vec![FsEvent::Removed { path: "/p/a.txt".to_string() }]
+        );
+    }
+
+    #[test]
+    fn test_classify_rename() {
+        let s = subs(&["/p/a.txt"]);
+        let rename = EventKind::Modify(ModifyKind::Name(RenameMode::Both));
+        assert_eq!(
+            classify(&rename, &[PathBuf::from("/p/a.txt"), PathBuf::from("/p/b.txt")], &s),
+            vec![FsEvent::Renamed { from: "/p/a.txt".to_string(), to: "/p/b.txt".to_string() }]
+        );
+
+        // Atomic save by another tool: temp file renamed over the open file
+        assert_eq!(
+            classify(&rename, &[PathBuf::from("/p/.a.txt.tmp"), PathBuf::from("/p/a.txt")], &s),
+            vec![changed("/p/a.txt")]
+        );
+    }
+
+    #[test]
+    fn test_subscribe_refcounts() {
+        let dir = std::env::temp_dir().join(format!("editrion-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("a.txt");
+        std::fs::write(&file, "x").unwrap();
+        let path = file.to_string_lossy().to_string();
+
+        let mut watcher = FsWatcher::new();
+        watcher.subscribe(&path).unwrap();
+        watcher.subscribe(&path).unwrap();
+        assert_eq!(watcher.watched_dirs.len(), 1);
+        watcher.unsubscribe(&path).unwrap();
+        assert_eq!(watcher.watched_dirs.len(), 1);
+        watcher.unsubscribe(&path).unwrap();
+        assert!(watcher.watched_dirs.is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_unsubscribe_with_other_spelling() {
+        let dir = std::env::temp_dir().join(format!("editrion-watch-spelling-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        let file = dir.join("a.txt");
+        std::fs::write(&file, "x").unwrap();
+
+        let mut watcher = FsWatcher::new();
+        watcher.subscribe(&format!("{}/", dir.join("sub").display())).unwrap();
+        watcher.subscribe(&file.to_string_lossy()).unwrap();
+        assert_eq!(watcher.watched_dirs.len(), 2);
+        watcher.unsubscribe(&format!("{}/./sub", dir.display())).unwrap();
+        assert_eq!(watcher.watched_dirs.len(), 1);
+
+        // Deleted files are still found under their canonical key
+        std::fs::remove_file(&file).unwrap();
+        watcher.unsubscribe(&format!("{}/./a.txt", dir.display())).unwrap();
+        assert!(watcher.watched_dirs.is_empty());
+        assert!(watcher.subscriptions.lock().unwrap().is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
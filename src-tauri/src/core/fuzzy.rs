@@ -0,0 +1,259 @@
+/// Points for every matched character
+const SCORE_MATCH: i32 = 16;
+/// Match right after a path separator or at the very start
+const BONUS_SEGMENT_START: i32 = 24;
+/// Match after `_`, `-`, `.` or a space, or on a camelCase hump
+const BONUS_WORD_START: i32 = 16;
+/// First digit after a letter
+const BONUS_NUMBER_START: i32 = 8;
+/// Least bonus for extending a run; runs otherwise keep the bonus of their first character
+const BONUS_CONSECUTIVE: i32 = 16;
+/// Per character matched inside the file name rather than its directories
+const BONUS_BASENAME: i32 = 8;
+const BONUS_EXACT_CASE: i32 = 1;
+const PENALTY_GAP_START: i32 = 3;
+const PENALTY_GAP_EXTEND: i32 = 1;
+
+/// Candidates longer than this are not scored to keep the matrix small
+const MAX_CANDIDATE_CHARS: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    /// Character (not byte) indices of the matched characters, ascending
+    pub positions: Vec<usize>,
+}
+
+/// Scores `/`-separated paths against a query whose characters must appear in order.
+/// Matching ignores case and whitespace in the query. Buffers are reused between
+/// calls, so keep one matcher per query.
+#[derive(Clone)]
+pub struct FuzzyMatcher {
+    query: Vec<char>,
+    folded_query: Vec<char>,
+    chars: Vec<char>,
+    folded: Vec<char>,
+    /// Leftmost and rightmost candidate index each query char can be matched at
+    first: Vec<usize>,
+    last: Vec<usize>,
+    /// `scores[i * n + j]`: best score with query char `i` matched at candidate char `j`
+    scores: Vec<i32>,
+    /// Where query char `i - 1` was matched for the best `scores[i * n + j]`
+    from: Vec<usize>,
+    /// Boundary bonus of the first character of the run ending at `i * n + j`
+    run_bonus: Vec<i32>,
+}
+
+const NONE: i32 = i32::MIN / 2;
+
+impl FuzzyMatcher {
+    pub fn new(query: &str) -> Self {
+        let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+        Self {
+            folded_query: query.iter().map(|&c| fold(c)).collect(),
+            query,
+            chars: Vec::new(),
+            folded: Vec::new(),
+            first: Vec::new(),
+            last: Vec::new(),
+            scores: Vec::new(),
+            from: Vec::new(),
+            run_bonus: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.query.is_empty()
+    }
+
+    /// Cheap check that every query character occurs in order
+    fn is_subsequence(&self, candidate: &str) -> bool {
+        let mut query = self.folded_query.iter().peekable();
+        for c in candidate.chars() {
+            match query.peek() {
+                Some(&&q) if q == fold(c) => {
+                    query.next();
+                }
+                Some(_) => {}
+                None => break,
+            }
+        }
+        query.peek().is_none()
+    }
+
+    pub fn score(&mut self, candidate: &str) -> Option<FuzzyMatch> {
+        if self.query.is_empty() || !self.is_subsequence(candidate) {
+            return None;
+        }
+        self.chars.clear();
+        self.chars.extend(candidate.chars());
+        let (m, n) = (self.query.len(), self.chars.len());
+        if n > MAX_CANDIDATE_CHARS {
+            return None;
+        }
+        self.folded.clear();
+        self.folded.extend(self.chars.iter().map(|&c| fold(c)));
+        self.fill_bounds();
+        let basename_start = self.chars.iter().rposition(|&c| c == '/').map(|p| p + 1).unwrap_or(0);
+
+        self.scores.clear();
+        self.scores.resize(m * n, NONE);
+        self.from.clear();
+        self.from.resize(m * n, 0);
+        self.run_bonus.clear();
+        self.run_bonus.resize(m * n, 0);
+
+        for i in 0..m {
+            // Best `scores[i - 1][k] + k * PENALTY_GAP_EXTEND` over k < j - 1, for gapped matches
+            let mut gapped: (i32, usize) = (NONE, 0);
+            let mut k = if i > 0 { self.first[i - 1] } else { 0 };
+            for j in self.first[i]..=self.last[i] {
+                while i > 0 && k + 2 <= j {
+                    let prev = self.scores[(i - 1) * n + k];
+                    if prev > NONE && prev + k as i32 * PENALTY_GAP_EXTEND > gapped.0 {
+                        gapped = (prev + k as i32 * PENALTY_GAP_EXTEND, k);
+                    }
+                    k += 1;
+                }
+                if self.folded[j] != self.folded_query[i] {
+                    continue;
+                }
+                let boundary = self.boundary_bonus(j);
+                let mut base = SCORE_MATCH;
+                if j >= basename_start {
+                    base += BONUS_BASENAME;
+                }
+                if self.chars[j] == self.query[i] {
+                    base += BONUS_EXACT_CASE;
+                }
+                let cell = i * n + j;
+                if i == 0 {
+                    self.scores[cell] = base + boundary;
+                    self.run_bonus[cell] = boundary;
+                    continue;
+                }
+                let after_gap = if gapped.0 > NONE {
+                    gapped.0 - (j as i32 - 1) * PENALTY_GAP_EXTEND - PENALTY_GAP_START + base + boundary
+                } else {
+                    NONE
+                };
+                let (consecutive, run) = if j >= 1 && self.scores[cell - n - 1] > NONE {
+                    let run = self.run_bonus[cell - n - 1].max(boundary);
+                    (self.scores[cell - n - 1] + base + run.max(BONUS_CONSECUTIVE), run)
+                } else {
+                    (NONE, 0)
+                };
+                if consecutive > NONE && consecutive >= after_gap {
+                    self.scores[cell] = consecutive;
+                    self.from[cell] = j - 1;
+                    self.run_bonus[cell] = run;
+                } else if after_gap > NONE {
+                    self.scores[cell] = after_gap;
+                    self.from[cell] = gapped.1;
+                    self.run_bonus[cell] = boundary;
+                }
+            }
+        }
+
+        let last = (m - 1) * n;
+        let (end, score) = (self.first[m - 1]..=self.last[m - 1])
+            .map(|j| (j, self.scores[last + j]))
+            .filter(|&(_, s)| s > NONE)
+            .max_by_key(|&(j, s)| (s, std::cmp::Reverse(j)))?;
+
+        let mut positions = vec![0; m];
+        let mut j = end;
+        for i in (0..m).rev() {
+            positions[i] = j;
+            j = self.from[i * n + j];
+        }
+        Some(FuzzyMatch { score, positions })
+    }
+
+    /// Greedy leftmost and rightmost placements bound where each query char can go
+    fn fill_bounds(&mut self) {
+        let m = self.folded_query.len();
+        self.first.clear();
+        let mut j = 0;
+        for &q in &self.folded_query {
+            while self.folded[j] != q {
+                j += 1;
+            }
+            self.first.push(j);
+            j += 1;
+        }
+        self.last.clear();
+        self.last.resize(m, 0);
+        let mut j = self.folded.len();
+        for i in (0..m).rev() {
+            j -= 1;
+            while self.folded[j] != self.folded_query[i] {
+                j -= 1;
+            }
+            self.last[i] = j;
+        }
+    }
+
+    fn boundary_bonus(&self, j: usize) -> i32 {
+        let c = self.chars[j];
+        let prev = if j == 0 { None } else { Some(self.chars[j - 1]) };
+        match prev {
+            None | Some('/') => BONUS_SEGMENT_START,
+            Some('_' | '-' | '.' | ' ') => BONUS_WORD_START,
+            Some(p) if p.is_lowercase() && c.is_uppercase() => BONUS_WORD_START,
+            Some(p) if p.is_alphabetic() && c.is_ascii_digit() => BONUS_NUMBER_START,
+            _ => 0,
+        }
+    }
+}
+
+fn fold(c: char) -> char {
+    if c.is_ascii() {
+        c.to_ascii_lowercase()
+    } else {
+        c.to_lowercase().next().unwrap_or(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+        FuzzyMatcher::new(query).score(candidate)
+    }
+
+    fn best<'a>(query: &str, candidates: &[&'a str]) -> &'a str {
+        let mut matcher = FuzzyMatcher::new(query);
+        candidates
+            .iter()
+            .filter_map(|c| matcher.score(c).map(|m| (m.score, *c)))
+            .max_by_key(|&(s, c)| (s, std::cmp::Reverse(c.len())))
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn test_requires_all_chars_in_order() {
+        assert!(score("mr", "src/main.rs").is_some());
+        assert!(score("nm", "src/main.rs").is_none());
+        assert!(score("", "src/main.rs").is_none());
+        assert!(score("MAIN", "src/main.rs").is_some());
+    }
+
+    #[test]
+    fn test_positions() {
+        assert_eq!(score("main", "src/main.rs").unwrap().positions, vec![4, 5, 6, 7]);
+        assert_eq!(score("fe", "src/components/FileExplorer.ts").unwrap().positions, vec![15, 19]);
+        // Non-ASCII paths are indexed by character
+        assert_eq!(score("é", "docs/café.md").unwrap().positions, vec![8]);
+    }
+
+    #[test]
+    fn test_ranking() {
+        assert_eq!(best("main", &["src/domain/index.ts", "src/main.rs"]), "src/main.rs");
+        assert_eq!(best("fe", &["src/fixtures/safe.txt", "src/components/FileExplorer.ts"]), "src/components/FileExplorer.ts");
+        assert_eq!(best("app", &["src/app_state.rs", "src/wrapper/apply.rs"]), "src/app_state.rs");
+        assert_eq!(best("state", &["src/state/mod.rs", "src/app_state.rs"]), "src/app_state.rs");
+    }
+}
@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+
+/// Every N-th line start is remembered; lines in between are found by scanning forward
+const LINES_PER_CHECKPOINT: u64 = 64;
+
+/// Read size used while indexing and searching
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Upper bound on lines returned by a single `read_lines` call
+pub const MAX_LINES_PER_REQUEST: u64 = 10_000;
+
+#[derive(Debug, Default)]
+struct LineIndex {
+    /// Byte offset of line `i * LINES_PER_CHECKPOINT`
+    checkpoints: Vec<u64>,
+    /// Number of `\n` seen so far, i.e. lines known to be complete
+    newlines: u64,
+    indexed_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexStatus {
+    /// Lines that can be served right now; the total once `complete` is set
+    pub lines: u64,
+    pub complete: bool,
+    pub indexed_bytes: u64,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    /// Byte offset of the match; pass `offset + 1` to continue searching
+    pub offset: u64,
+    /// 0-based line, if the index already reaches the match
+    pub line: Option<u64>,
+    /// 0-based column in characters, if `line` is known
+    pub column: Option<u64>,
+}
+
+/// A file too big to hand to the editor in one piece, read on demand by line range
+pub struct LargeFile {
+    pub path: PathBuf,
+    pub size: u64,
+    index: RwLock<LineIndex>,
+    complete: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+impl LargeFile {
+    pub fn open(path: &Path) -> Result<Arc<Self>> {
+        let size = std::fs::metadata(path)?.len();
+        Ok(Arc::new(Self {
+            path: path.to_path_buf(),
+            size,
+            index: RwLock::new(LineIndex { checkpoints: vec![0], ..Default::default() }),
+            complete: AtomicBool::new(false),
+            cancelled: AtomicBool::new(false),
+        }))
+    }
+
+    /// Scan the whole file recording line checkpoints. Meant to run on a background
+    /// thread; `on_progress` is called after every chunk with the current status.
+    pub fn build_index(&self, mut on_progress: impl FnMut(&IndexStatus)) -> Result<()> {
+        let mut file = File::open(&self.path)?;
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        let mut offset = 0u64;
+        let mut newlines = 0u64;
+        let mut since_checkpoint = 0u64;
+
+        loop {
+            if self.cancelled.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            let n = file.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            let mut new_checkpoints = Vec::new();
+            for pos in memchr::memchr_iter(b'\n', &buf[..n]) {
+                newlines += 1;
+                since_checkpoint += 1;
+                if since_checkpoint == LINES_PER_CHECKPOINT {
+                    since_checkpoint = 0;
+                    new_checkpoints.push(offset + pos as u64 + 1);
+                }
+            }
+            offset += n as u64;
+            if let Ok(mut index) = self.index.write() {
+                index.checkpoints.extend(new_checkpoints);
+                index.newlines = newlines;
+                index.indexed_bytes = offset;
+            }
+            on_progress(&self.status());
+        }
+
+        self.complete.store(true, Ordering::Release);
+        on_progress(&self.status());
+        Ok(())
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn status(&self) -> IndexStatus {
+        let complete = self.complete.load(Ordering::Acquire);
+        let (newlines, indexed_bytes) = self
+            .index
+            .read()
+            .map(|i| (i.newlines, i.indexed_bytes))
+            .unwrap_or((0, 0));
+        IndexStatus {
+            // The text after the last `\n` is a line too (possibly empty)
+            lines: if complete { newlines + 1 } else { newlines },
+            complete,
+            indexed_bytes,
+            size: self.size,
+        }
+    }
+
+    /// Lines `start..end` (0-based, end exclusive) without their terminators.
+    /// Ranges past what has been indexed so far are cut short.
+    pub fn read_lines(&self, start: u64, end: u64) -> Result<Vec<String>> {
+        let available = self.status().lines;
+        let end = end.min(available).min(start.saturating_add(MAX_LINES_PER_REQUEST));
+        if start >= end {
+            return Ok(Vec::new());
+        }
+
+        let checkpoint = self.checkpoint_for_line(start);
+        let mut reader = self.reader_at(checkpoint.1)?;
+        let mut line = Vec::new();
+        for _ in checkpoint.0..start {
+            line.clear();
+            reader.read_until(b'\n', &mut line)?;
+        }
+
+        let mut lines = Vec::with_capacity((end - start) as usize);
+        for _ in start..end {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                // Only the empty line after a trailing newline is left
+                lines.push(String::new());
+                break;
+            }
+            lines.push(String::from_utf8_lossy(trim_eol(&line)).to_string());
+        }
+        Ok(lines)
+    }
+
+    /// Find the next occurrence of `needle` at or after byte `from`.
+    /// Case-insensitive matching only folds ASCII letters.
+    pub fn search(&self, needle: &str, from: u64, case_sensitive: bool) -> Result<Option<SearchHit>> {
+        if needle.is_empty() || from >= self.size {
+            return Ok(None);
+        }
+        let needle: Vec<u8> = if case_sensitive {
+            needle.as_bytes().to_vec()
+        } else {
+            needle.as_bytes().to_ascii_lowercase()
+        };
+        let finder = memchr::memmem::Finder::new(&needle);
+
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(from))?;
+        let overlap = needle.len() - 1;
+        let mut window: Vec<u8> = Vec::with_capacity(CHUNK_SIZE + overlap);
+        let mut window_start = from;
+        let mut buf = vec![0u8; CHUNK_SIZE];
+
+        loop {
+            if self.cancelled.load(Ordering::Relaxed) {
+                return Ok(None);
+            }
+            let n = file.read(&mut buf)?;
+            if n == 0 {
+                return Ok(None);
+            }
+            if case_sensitive {
+                window.extend_from_slice(&buf[..n]);
+            } else {
+                window.extend(buf[..n].iter().map(u8::to_ascii_lowercase));
+            }
+            if let Some(pos) = finder.find(&window) {
+                let offset = window_start + pos as u64;
+                let (line, column) = match self.locate(offset)? {
+                    Some((line, column)) => (Some(line), Some(column)),
+                    None => (None, None),
+                };
+                return Ok(Some(SearchHit { offset, line, column }));
+            }
+            // Keep the tail so matches spanning two chunks are still found
+            let keep = overlap.min(window.len());
+            let drop = window.len() - keep;
+            window.drain(..drop);
+            window_start += drop as u64;
+        }
+    }
+
+    /// Line and character column of a byte offset, if the index covers it
+    fn locate(&self, offset: u64) -> Result<Option<(u64, u64)>> {
+        let (checkpoints_len, indexed_bytes) = match self.index.read() {
+            Ok(i) => (i.checkpoints.len(), i.indexed_bytes),
+            Err(_) => return Ok(None),
+        };
+        if offset >= indexed_bytes || checkpoints_len == 0 {
+            return Ok(None);
+        }
+        let (mut line, line_offset) = self.checkpoint_for_offset(offset);
+        let mut reader = self.reader_at(line_offset)?;
+        let mut line_start = line_offset;
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            let n = reader.read_until(b'\n', &mut buf)? as u64;
+            if n == 0 || line_start + n > offset {
+                let prefix = &buf[..((offset - line_start) as usize).min(buf.len())];
+                let column = String::from_utf8_lossy(prefix).chars().count() as u64;
+                return Ok(Some((line, column)));
+            }
+            line_start += n;
+            line += 1;
+        }
+    }
+
+    /// Closest checkpoint at or before `line`: (line number, byte offset)
+    fn checkpoint_for_line(&self, line: u64) -> (u64, u64) {
+        let index = match self.index.read() {
+            Ok(i) => i,
+            Err(_) => return (0, 0),
+        };
+        let slot = ((line / LINES_PER_CHECKPOINT) as usize).min(index.checkpoints.len().saturating_sub(1));
+        (slot as u64 * LINES_PER_CHECKPOINT, index.checkpoints.get(slot).copied().unwrap_or(0))
+    }
+
+    /// Closest checkpoint at or before byte `offset`: (line number, byte offset)
+    fn checkpoint_for_offset(&self, offset: u64) -> (u64, u64) {
+        let index = match self.index.read() {
+            Ok(i) => i,
+            Err(_) => return (0, 0),
+        };
+        let slot = index.checkpoints.partition_point(|&c| c <= offset).saturating_sub(1);
+        (slot as u64 * LINES_PER_CHECKPOINT, index.checkpoints.get(slot).copied().unwrap_or(0))
+    }
+
+    fn reader_at(&self, offset: u64) -> Result<BufReader<File>> {
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(offset))?;
+        Ok(BufReader::with_capacity(64 * 1024, file))
+    }
+}
+
+fn trim_eol(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+/// LargeFileManager keeps the large files currently open in the editor
+pub struct LargeFileManager {
+    pub files: HashMap<String, Arc<LargeFile>>,
+    next_id: u64,
+}
+
+impl Default for LargeFileManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LargeFileManager {
+    pub fn new() -> Self {
+        Self {
+            files: HashMap::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Register a file and return its handle id
+    pub fn add_file(&mut self, file: Arc<LargeFile>) -> String {
+        let id = format!("large-{}", self.next_id);
+        self.next_id += 1;
+        self.files.insert(id.clone(), file);
+        id
+    }
+
+    pub fn get_file(&self, id: &str) -> Result<Arc<LargeFile>> {
+        self.files
+            .get(id)
+            .cloned()
+            .ok_or_else(|| AppError::LargeFileNotFound(id.to_string()))
+    }
+
+    /// Stop background work for the file and forget it
+    pub fn close_file(&mut self, id: &str) -> Result<()> {
+        let file = self
+            .files
+            .remove(id)
+            .ok_or_else(|| AppError::LargeFileNotFound(id.to_string()))?;
+        file.cancel();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str, lines: usize, trailing_newline: bool) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("editrion-large-{}-{}.log", name, std::process::id()));
+        let mut text = (0..lines).map(|i| format!("line {} héllo", i)).collect::<Vec<_>>().join("\r\n");
+        if trailing_newline {
+            text.push_str("\r\n");
+        }
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_index_and_read_lines() {
+        let path = fixture("read", 1000, true);
+        let file = LargeFile::open(&path).unwrap();
+        file.build_index(|_| {}).unwrap();
+
+        let status = file.status();
+        assert!(status.complete);
+        // 1000 lines plus the empty line after the final newline
+        assert_eq!(status.lines, 1001);
+
+        let lines = file.read_lines(130, 133).unwrap();
+        assert_eq!(lines, vec!["line 130 héllo", "line 131 héllo", "line 132 héllo"]);
+        assert_eq!(file.read_lines(999, 2000).unwrap(), vec!["line 999 héllo", ""]);
+        assert!(file.read_lines(5000, 5001).unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_search_forward() {
+        let path = fixture("search", 500, false);
+        let file = LargeFile::open(&path).unwrap();
+        file.build_index(|_| {}).unwrap();
+
+        let hit = file.search("line 321 ", 0, true).unwrap().unwrap();
+        assert_eq!(hit.line, Some(321));
+        assert_eq!(hit.column, Some(0));
+
+        let hit = file.search("HÉLLO", 0, false);
+        // Non-ASCII letters are not case folded
+        assert!(hit.unwrap().is_none());
+
+        let first = file.search("LLO", 0, false).unwrap().unwrap();
+        assert_eq!((first.line, first.column), (Some(0), Some(9)));
+        let second = file.search("llo", first.offset + 1, true).unwrap().unwrap();
+        assert_eq!(second.line, Some(1));
+
+        assert!(file.search("missing", 0, true).unwrap().is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_manager_lifecycle() {
+        let path = fixture("manager", 3, false);
+        let mut manager = LargeFileManager::new();
+        let id = manager.add_file(LargeFile::open(&path).unwrap());
+        assert!(manager.get_file(&id).is_ok());
+        manager.close_file(&id).unwrap();
+        assert!(matches!(manager.get_file(&id), Err(AppError::LargeFileNotFound(_))));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
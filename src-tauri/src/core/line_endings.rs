@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+
+/// Line terminator style of a text buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    Cr,
+    /// More than one style is used in the same file
+    Mixed,
+    /// The text has no line breaks at all
+    None,
+}
+
+impl LineEnding {
+    fn as_str(self) -> Option<&'static str> {
+        match self {
+            LineEnding::Lf => Some("\n"),
+            LineEnding::Crlf => Some("\r\n"),
+            LineEnding::Cr => Some("\r"),
+            LineEnding::Mixed | LineEnding::None => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineEndingInfo {
+    pub line_ending: LineEnding,
+    pub final_newline: bool,
+}
+
+#[derive(Default)]
+struct Counts {
+    lf: usize,
+    crlf: usize,
+    cr: usize,
+}
+
+fn count(text: &str) -> Counts {
+    let mut counts = Counts::default();
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\r' if bytes.get(i + 1) == Some(&b'\n') => {
+                counts.crlf += 1;
+                i += 1;
+            }
+            b'\r' => counts.cr += 1,
+            b'\n' => counts.lf += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    counts
+}
+
+/// Detect the line-ending style and whether the text ends with a line break
+pub fn detect(text: &str) -> LineEndingInfo {
+    let c = count(text);
+    let line_ending = match (c.lf > 0, c.crlf > 0, c.cr > 0) {
+        (false, false, false) => LineEnding::None,
+        (true, false, false) => LineEnding::Lf,
+        (false, true, false) => LineEnding::Crlf,
+        (false, false, true) => LineEnding::Cr,
+        _ => LineEnding::Mixed,
+    };
+    LineEndingInfo {
+        line_ending,
+        final_newline: text.ends_with('\n') || text.ends_with('\r'),
+    }
+}
+
+/// Most frequent terminator in `text`, falling back to LF
+fn dominant(text: &str) -> &'static str {
+    let c = count(text);
+    if c.crlf >= c.lf && c.crlf >= c.cr && c.crlf > 0 {
+        "\r\n"
+    } else if c.cr > c.lf {
+        "\r"
+    } else {
+        "\n"
+    }
+}
+
+/// Prepare text for saving.
+///
+/// `line_ending` set to LF, CRLF or CR converts every line break to that style; `Mixed`,
+/// `None` or no value leaves them untouched. `final_newline` forces a trailing line break
+/// on or off; turning it off removes only the last line break, never blank lines before it.
+/// No value keeps whatever the text has.
+pub fn apply(text: &str, line_ending: Option<LineEnding>, final_newline: Option<bool>) -> String {
+    let target = line_ending.and_then(LineEnding::as_str);
+    let mut out = match target {
+        Some(eol) => normalize(text, eol),
+        None => text.to_string(),
+    };
+
+    match final_newline {
+        Some(true) if !out.is_empty() && !out.ends_with('\n') && !out.ends_with('\r') => {
+            let eol = target.unwrap_or_else(|| dominant(&out));
+            out.push_str(eol);
+        }
+        Some(false) => {
+            let trimmed = out
+                .strip_suffix("\r\n")
+                .or_else(|| out.strip_suffix('\n'))
+                .or_else(|| out.strip_suffix('\r'))
+                .map(str::len);
+            if let Some(len) = trimmed {
+                out.truncate(len);
+            }
+        }
+        _ => {}
+    }
+    out
+}
+
+fn normalize(text: &str, eol: &str) -> String {
+    let mut out = String::with_capacity(text.len() + text.len() / 16);
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\r' => {
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                out.push_str(eol);
+            }
+            '\n' => out.push_str(eol),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_styles() {
+        assert_eq!(detect("a\nb\n").line_ending, LineEnding::Lf);
+        assert_eq!(detect("a\r\nb").line_ending, LineEnding::Crlf);
+        assert_eq!(detect("a\rb\r").line_ending, LineEnding::Cr);
+        assert_eq!(detect("a\r\nb\n").line_ending, LineEnding::Mixed);
+        assert_eq!(detect("abc").line_ending, LineEnding::None);
+    }
+
+    #[test]
+    fn test_detect_final_newline() {
+        assert!(detect("a\r\n").final_newline);
+        assert!(!detect("a\nb").final_newline);
+        assert!(!detect("").final_newline);
+    }
+
+    #[test]
+    fn test_apply_preserves_crlf_round_trip() {
+        // The editor hands back LF text; saving with the detected style restores CRLF
+        let original = "@echo off\r\necho hi\r\n";
+        let info = detect(original);
+        let edited = "@echo off\necho hello\n";
+        let saved = apply(edited, Some(info.line_ending), Some(info.final_newline));
+        assert_eq!(saved, "@echo off\r\necho hello\r\n");
+    }
+
+    #[test]
+    fn test_apply_normalizes_mixed() {
+        assert_eq!(apply("a\r\nb\nc\rd", Some(LineEnding::Lf), None), "a\nb\nc\nd");
+        assert_eq!(apply("a\r\nb\n", Some(LineEnding::Mixed), None), "a\r\nb\n");
+        assert_eq!(apply("a\r\nb\n", None, None), "a\r\nb\n");
+    }
+
+    #[test]
+    fn test_apply_final_newline() {
+        assert_eq!(apply("a\r\nb", None, Some(true)), "a\r\nb\r\n");
+        assert_eq!(apply("a\nb", Some(LineEnding::Crlf), Some(true)), "a\r\nb\r\n");
+        assert_eq!(apply("a\nb\n", None, Some(false)), "a\nb");
+        assert_eq!(apply("a\r\nb\r\n", None, Some(false)), "a\r\nb");
+        assert_eq!(apply("a\rb\r", None, Some(false)), "a\rb");
+        // Blank lines typed at the end are kept
+        assert_eq!(apply("a\nb\n\n", None, Some(false)), "a\nb\n");
+        assert_eq!(apply("a\nb\n\n", Some(LineEnding::Crlf), Some(false)), "a\r\nb\r\n");
+        assert_eq!(apply("", None, Some(true)), "");
+    }
+}
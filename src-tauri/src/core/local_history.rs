@@ -0,0 +1,274 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+
+use crate::core::atomic_write::write_atomic;
+use crate::core::file_version::{self, FileVersion};
+use crate::error::{AppError, Result};
+
+/// Directory under the app data directory
+const HISTORY_DIR: &str = "history";
+const INDEX_NAME: &str = "index.json";
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
+/// How much history is kept per file
+#[derive(Debug, Clone, Copy)]
+pub struct Retention {
+    pub max_entries: usize,
+    pub max_age_days: u64,
+    /// Larger files are not snapshotted
+    pub max_file_size: u64,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self { max_entries: 50, max_age_days: 30, max_file_size: 8 * 1024 * 1024 }
+    }
+}
+
+/// One saved state of a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    /// Unique within the file's timeline
+    pub id: String,
+    /// Milliseconds since the Unix epoch when the snapshot was taken
+    pub timestamp: u64,
+    pub size: u64,
+    /// Hex SHA-256 of the contents; entries with equal hashes share one stored copy
+    pub hash: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileHistory {
+    path: String,
+    /// Oldest first
+    entries: Vec<HistoryEntry>,
+}
+
+/// LocalHistory keeps gzip-compressed snapshots of files as they were before each save,
+/// one directory per file named after the hash of its path:
+///
+/// ```text
+/// <root>/<path hash>/index.json      timeline of the file
+/// <root>/<path hash>/<sha256>.gz     contents, stored once per distinct hash
+/// ```
+pub struct LocalHistory {
+    root: PathBuf,
+    retention: Retention,
+}
+
+impl LocalHistory {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root, retention: Retention::default() }
+    }
+
+    /// The history kept in the app data directory `dir`
+    pub fn in_app_data(dir: &Path) -> Self {
+        Self::new(dir.join(HISTORY_DIR))
+    }
+
+    pub fn with_retention(root: PathBuf, retention: Retention) -> Self {
+        Self { root, retention }
+    }
+
+    fn file_dir(&self, path: &Path) -> PathBuf {
+        self.root.join(file_version::hash_bytes(path.to_string_lossy().as_bytes()))
+    }
+
+    fn read_index(&self, path: &Path) -> Result<FileHistory> {
+        match fs::read(self.file_dir(path).join(INDEX_NAME)) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(FileHistory {
+                path: path.to_string_lossy().to_string(),
+                entries: Vec::new(),
+            }),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Snapshot what is on disk at `path` now, typically right before overwriting it.
+    /// Missing, non-regular and oversized files are skipped.
+    pub fn record_current(&self, path: &Path) -> Result<Option<HistoryEntry>> {
+        match fs::metadata(path) {
+            Ok(meta) if meta.is_file() && meta.len() <= self.retention.max_file_size => {}
+            Ok(_) => return Ok(None),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        let bytes = fs::read(path)?;
+        self.record(path, &bytes)
+    }
+
+    /// Add `bytes` to the timeline of `path`. Nothing is added when they equal the
+    /// latest snapshot.
+    pub fn record(&self, path: &Path, bytes: &[u8]) -> Result<Option<HistoryEntry>> {
+        let mut history = self.read_index(path)?;
+        let hash = file_version::hash_bytes(bytes);
+        if history.entries.last().map(|e| e.hash == hash).unwrap_or(false) {
+            return Ok(None);
+        }
+
+        let dir = self.file_dir(path);
+        fs::create_dir_all(&dir)?;
+        let blob = dir.join(format!("{}.gz", hash));
+        if !blob.exists() {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(bytes)?;
+            write_atomic(&blob, &encoder.finish()?)?;
+        }
+
+        // Two saves within the same millisecond still get distinct ids
+        let mut timestamp = now_millis();
+        if let Some(last) = history.entries.last() {
+            timestamp = timestamp.max(last.timestamp + 1);
+        }
+        let entry = HistoryEntry { id: timestamp.to_string(), timestamp, size: bytes.len() as u64, hash };
+        history.entries.push(entry.clone());
+        self.prune(&dir, &mut history, timestamp)?;
+        write_atomic(&dir.join(INDEX_NAME), &serde_json::to_vec_pretty(&history)?)?;
+        Ok(Some(entry))
+    }
+
+    /// Drop entries beyond the retention limits and the stored copies nothing refers to
+    fn prune(&self, dir: &Path, history: &mut FileHistory, now: u64) -> Result<()> {
+        let cutoff = now.saturating_sub(self.retention.max_age_days * DAY_MILLIS);
+        let excess = history.entries.len().saturating_sub(self.retention.max_entries);
+        let mut removed = Vec::new();
+        let mut index = 0;
+        history.entries.retain(|e| {
+            index += 1;
+            let keep = index > excess && e.timestamp >= cutoff;
+            if !keep {
+                removed.push(e.hash.clone());
+            }
+            keep
+        });
+        for hash in removed {
+            if !history.entries.iter().any(|e| e.hash == hash) {
+                match fs::remove_file(dir.join(format!("{}.gz", hash))) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Snapshots of `path`, newest first
+    pub fn timeline(&self, path: &Path) -> Result<Vec<HistoryEntry>> {
+        let mut entries = self.read_index(path)?.entries;
+        entries.reverse();
+        Ok(entries)
+    }
+
+    /// Contents of one snapshot of `path`
+    pub fn read(&self, path: &Path, id: &str) -> Result<Vec<u8>> {
+        let entry = self
+            .read_index(path)?
+            .entries
+            .into_iter()
+            .find(|e| e.id == id)
+            .ok_or_else(|| AppError::HistoryEntryNotFound(id.to_string()))?;
+        let mut bytes = Vec::with_capacity(entry.size as usize);
+        GzDecoder::new(File::open(self.file_dir(path).join(format!("{}.gz", entry.hash)))?)
+            .read_to_end(&mut bytes)?;
+        if file_version::hash_bytes(&bytes) != entry.hash {
+            return Err(AppError::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("history snapshot {} of {} is corrupt", id, path.display()),
+            )));
+        }
+        Ok(bytes)
+    }
+
+    /// Write a snapshot back to `path`. The current contents are snapshotted first,
+    /// so the restore itself shows up in the timeline and can be reverted.
+    pub fn restore(&self, path: &Path, id: &str, expected_version: Option<&FileVersion>) -> Result<FileVersion> {
+        let bytes = self.read(path, id)?;
+        if let Some(expected) = expected_version {
+            file_version::ensure_unchanged(path, expected)?;
+        }
+        self.record_current(path)?;
+        write_atomic(path, &bytes)?;
+        Ok(FileVersion::from_bytes(&fs::metadata(path)?, &bytes))
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("editrion-history-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_record_dedup_and_restore() {
+        let dir = temp_dir("restore");
+        let history = LocalHistory::new(dir.join("history"));
+        let file = dir.join("notes.txt");
+
+        assert!(history.record_current(&file).unwrap().is_none());
+        for contents in ["one", "two", "two", "one"] {
+            fs::write(&file, contents).unwrap();
+            history.record_current(&file).unwrap();
+        }
+        let timeline = history.timeline(&file).unwrap();
+        let hashes: Vec<_> = timeline.iter().map(|e| e.hash.as_str()).collect();
+        assert_eq!(hashes.len(), 3);
+        assert_eq!(hashes[0], hashes[2]);
+        let blobs = fs::read_dir(history.file_dir(&file)).unwrap().count();
+        assert_eq!(blobs, 3, "two blobs plus the index");
+
+        fs::write(&file, "bad save").unwrap();
+        let oldest = &timeline[2];
+        assert_eq!(history.read(&file, &oldest.id).unwrap(), b"one");
+        history.restore(&file, &timeline[1].id, None).unwrap();
+        assert_eq!(fs::read(&file).unwrap(), b"two");
+        assert_eq!(history.timeline(&file).unwrap()[0].size, 8, "bad save is kept");
+        assert!(matches!(history.read(&file, "0"), Err(AppError::HistoryEntryNotFound(_))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_retention_prunes_entries_and_blobs() {
+        let dir = temp_dir("retention");
+        let retention = Retention { max_entries: 2, ..Default::default() };
+        let history = LocalHistory::with_retention(dir.join("history"), retention);
+        let file = dir.join("a.txt");
+
+        for n in 0..5 {
+            history.record(&file, format!("version {}", n).as_bytes()).unwrap();
+        }
+        let timeline = history.timeline(&file).unwrap();
+        assert_eq!(timeline.len(), 2);
+        assert_eq!(history.read(&file, &timeline[1].id).unwrap(), b"version 3");
+        assert_eq!(fs::read_dir(history.file_dir(&file)).unwrap().count(), 3);
+
+        let large = LocalHistory::with_retention(
+            dir.join("history"),
+            Retention { max_file_size: 4, ..Default::default() },
+        );
+        fs::write(&file, "too large").unwrap();
+        assert!(large.record_current(&file).unwrap().is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1 +1,23 @@
-pub mod process_manager;
\ No newline at end of file
+pub mod ai_events;
+pub mod ai_provider;
+pub mod atomic_write;
+pub mod codex_config;
+pub mod dir_listing;
+pub mod encoding;
+pub mod file_ops;
+pub mod file_probe;
+pub mod file_version;
+pub mod fs_watcher;
+pub mod fuzzy;
+pub mod large_file;
+pub mod line_endings;
+pub mod local_history;
+pub mod process_manager;
+pub mod project_replace;
+pub mod project_search;
+pub mod session;
+pub mod settings;
+pub mod toml_doc;
+pub mod trash;
+pub mod workspace;
+pub mod workspace_index;
\ No newline at end of file
@@ -1,13 +1,53 @@
 use std::collections::HashMap;
+use std::io;
 use std::path::PathBuf;
-use std::process::{Child, Command, Stdio};
-use std::sync::{Arc, Mutex};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(unix)]
+use std::time::Instant;
 
 use crate::error::{AppError, Result};
 
+/// Time a cancelled run gets to exit on its own before it is killed
+pub const CANCEL_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// How often a running or terminating process is checked
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Cancellation handle for a running process. The `Child` stays with the
+/// thread waiting on it, so cancelling never waits for a lock held across `wait()`.
+#[derive(Debug, Clone)]
+pub struct RunHandle {
+    pub pid: u32,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl RunHandle {
+    pub fn new(pid: u32) -> Self {
+        Self { pid, cancelled: Arc::new(AtomicBool::new(false)) }
+    }
+
+    /// Ask the waiting thread to stop the process; returns immediately
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
 /// ProcessManager handles the lifecycle of external CLI processes
 pub struct ProcessManager {
-    pub processes: HashMap<String, Arc<Mutex<Child>>>,
+    pub processes: HashMap<String, RunHandle>,
+}
+
+impl Default for ProcessManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[allow(dead_code)]
@@ -19,27 +59,26 @@ impl ProcessManager {
     }
 
     /// Add a new process to management
-    #[allow(dead_code)]
-    pub fn add_process(&mut self, run_id: String, child: Child) {
-        let child_arc = Arc::new(Mutex::new(child));
-        self.processes.insert(run_id, child_arc);
+    pub fn add_process(&mut self, run_id: String, handle: RunHandle) {
+        self.processes.insert(run_id, handle);
     }
 
     /// Get a process by run_id
     #[allow(dead_code)]
-    pub fn get_process(&self, run_id: &str) -> Option<Arc<Mutex<Child>>> {
+    pub fn get_process(&self, run_id: &str) -> Option<RunHandle> {
         self.processes.get(run_id).cloned()
     }
 
-    /// Cancel a process by run_id
+    /// Cancel a process by run_id. The thread in `wait_or_cancel` then stops it
+    /// together with everything it started.
     pub fn cancel_process(&mut self, run_id: &str) -> Result<()> {
-        if let Some(child_arc) = self.processes.remove(run_id) {
-            if let Ok(mut child) = child_arc.lock() {
-                let _ = child.kill();
-                return Ok(());
+        match self.processes.remove(run_id) {
+            Some(handle) => {
+                handle.cancel();
+                Ok(())
             }
+            None => Err(AppError::ProcessNotFound(run_id.to_string())),
         }
-        Err(AppError::ProcessNotFound(run_id.to_string()))
     }
 
     /// Remove a completed process
@@ -54,6 +93,20 @@ impl ProcessManager {
     }
 }
 
+/// Wait for `child` to exit, polling so a cancel through `handle` is noticed;
+/// a cancelled child is stopped with `terminate_tree`
+pub fn wait_or_cancel(child: &mut Child, handle: &RunHandle, grace: Duration) -> io::Result<ExitStatus> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if handle.is_cancelled() {
+            return terminate_tree(child, grace);
+        }
+        std::thread::sleep(EXIT_POLL_INTERVAL);
+    }
+}
+
 pub fn resolve_binary_path(name: &str) -> Option<PathBuf> {
     // 1) Respect ENV_VAR if set and exists
     if let Ok(p) = std::env::var(format!("{}_BIN", name.to_uppercase())) {
@@ -110,7 +163,79 @@ pub fn resolve_binary_path(name: &str) -> Option<PathBuf> {
     None
 }
 
-#[allow(dead_code)]
+/// Start the process as the leader of a new process group, so `terminate_tree`
+/// reaches the CLI behind the login shell and everything it spawns
+pub fn isolate_process_group(cmd: &mut Command) {
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(cmd, 0);
+    #[cfg(not(unix))]
+    let _ = cmd;
+}
+
+/// Stop `child` and its descendants. On Unix the process group started by
+/// `isolate_process_group` gets SIGTERM, then SIGKILL for whatever is still
+/// running after `grace`. On Windows the tree is killed with `taskkill /T`.
+/// The child is reaped before returning.
+pub fn terminate_tree(child: &mut Child, grace: Duration) -> io::Result<ExitStatus> {
+    #[cfg(unix)]
+    {
+        let group = child.id() as libc::pid_t;
+        signal_tree(group, libc::SIGTERM)?;
+        let deadline = Instant::now() + grace;
+        // Grandchildren can outlive the leader, so wait for the whole group
+        while child.try_wait()?.is_none() || group_alive(group) {
+            if Instant::now() >= deadline {
+                signal_tree(group, libc::SIGKILL)?;
+                break;
+            }
+            std::thread::sleep(EXIT_POLL_INTERVAL);
+        }
+    }
+    #[cfg(windows)]
+    {
+        let _ = grace;
+        let _ = Command::new("taskkill")
+            .args(["/PID", &child.id().to_string(), "/T", "/F"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        let _ = child.kill();
+    }
+    child.wait()
+}
+
+/// Signal the group led by `leader`, and the leader itself in case it was
+/// started without `isolate_process_group`
+#[cfg(unix)]
+fn signal_tree(leader: libc::pid_t, signal: libc::c_int) -> io::Result<()> {
+    for pid in [-leader, leader] {
+        // SAFETY: kill(2) only reads its arguments; a negative pid addresses the group
+        if unsafe { libc::kill(pid, signal) } != 0 {
+            let e = io::Error::last_os_error();
+            // Already gone, or not a group leader
+            if e.raw_os_error() != Some(libc::ESRCH) {
+                return Err(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn group_alive(group: libc::pid_t) -> bool {
+    // SAFETY: signal 0 only checks that the group exists
+    unsafe { libc::kill(-group, 0) == 0 }
+}
+
+/// Exit code, or the signal that terminated the process on Unix
+pub fn exit_details(status: &ExitStatus) -> (Option<i32>, Option<i32>) {
+    #[cfg(unix)]
+    let signal = std::os::unix::process::ExitStatusExt::signal(status);
+    #[cfg(not(unix))]
+    let signal = None;
+    (status.code(), signal)
+}
+
 pub fn shell_quote(s: &str) -> String {
     format!("'{}'", s.replace('\'', "'\\'\''"))
 }
@@ -135,20 +260,21 @@ mod tests {
         let mut manager = ProcessManager::new();
         
         // Create a simple child process (sleep command)
-        let child = Command::new("sleep")
+        let mut child = Command::new("sleep")
             .arg("10")
             .stdout(Stdio::null())
             .stderr(Stdio::null())
             .spawn()
             .expect("Failed to start sleep process");
 
-        manager.add_process("test_id".to_string(), child);
+        manager.add_process("test_id".to_string(), RunHandle::new(child.id()));
         assert_eq!(manager.active_count(), 1);
-        assert!(manager.get_process("test_id").is_some());
+        assert_eq!(manager.get_process("test_id").map(|h| h.pid), Some(child.id()));
 
         manager.remove_process("test_id");
         assert_eq!(manager.active_count(), 0);
         assert!(manager.get_process("test_id").is_none());
+        let _ = terminate_tree(&mut child, Duration::ZERO);
     }
 
     #[test]
@@ -156,20 +282,46 @@ mod tests {
         let mut manager = ProcessManager::new();
         
         // Create a child process
-        let child = Command::new("sleep")
+        let mut child = Command::new("sleep")
             .arg("10")
             .stdout(Stdio::null())
             .stderr(Stdio::null())
             .spawn()
             .expect("Failed to start sleep process");
 
-        manager.add_process("test_cancel".to_string(), child);
+        let handle = RunHandle::new(child.id());
+        manager.add_process("test_cancel".to_string(), handle.clone());
         assert_eq!(manager.active_count(), 1);
 
         // Cancel the process
         let result = manager.cancel_process("test_cancel");
         assert!(result.is_ok());
         assert_eq!(manager.active_count(), 0);
+        assert!(handle.is_cancelled());
+        assert!(wait_or_cancel(&mut child, &handle, CANCEL_GRACE_PERIOD).is_ok());
+    }
+
+    #[test]
+    fn test_cancel_while_waiting() {
+        let mut child = Command::new("sleep")
+            .arg("30")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Failed to start sleep process");
+        let handle = RunHandle::new(child.id());
+        let manager = Arc::new(std::sync::Mutex::new(ProcessManager::new()));
+        manager.lock().unwrap().add_process("waiting".to_string(), handle.clone());
+
+        let waiter = std::thread::spawn(move || wait_or_cancel(&mut child, &handle, CANCEL_GRACE_PERIOD));
+        std::thread::sleep(Duration::from_millis(100));
+        // Returns straight away although the waiter is blocked on the child
+        let started = std::time::Instant::now();
+        manager.lock().unwrap().cancel_process("waiting").unwrap();
+        assert!(started.elapsed() < Duration::from_millis(100));
+
+        let status = waiter.join().unwrap().unwrap();
+        assert!(!status.success());
     }
 
     #[test]
@@ -184,6 +336,78 @@ mod tests {
         }
     }
 
+    /// Start `script` in its own process group; it prints the pid of a grandchild
+    #[cfg(unix)]
+    fn spawn_with_grandchild(script: &str) -> (Child, libc::pid_t) {
+        use std::io::{BufRead, BufReader};
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(script).stdout(Stdio::piped()).stderr(Stdio::null());
+        isolate_process_group(&mut cmd);
+        let mut child = cmd.spawn().expect("Failed to start sh");
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap()).read_line(&mut line).unwrap();
+        (child, line.trim().parse().unwrap())
+    }
+
+    /// Whether `pid` is still running; a killed grandchild stays a zombie until
+    /// init reaps it, which does not count
+    #[cfg(unix)]
+    fn running(pid: libc::pid_t) -> bool {
+        // SAFETY: signal 0 only checks that the process exists
+        if unsafe { libc::kill(pid, 0) } != 0 {
+            return false;
+        }
+        match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+            Ok(stat) => !stat.rsplit(')').next().unwrap_or("").trim_start().starts_with('Z'),
+            Err(_) => !cfg!(target_os = "linux"),
+        }
+    }
+
+    #[cfg(unix)]
+    fn wait_until_gone(pid: libc::pid_t) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while running(pid) {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        true
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cancel_kills_grandchildren() {
+        let (mut child, grandchild) = spawn_with_grandchild("sleep 30 & echo $!; wait");
+        let handle = RunHandle::new(child.id());
+        let mut manager = ProcessManager::new();
+        manager.add_process("tree".to_string(), handle.clone());
+        manager.cancel_process("tree").unwrap();
+        wait_or_cancel(&mut child, &handle, CANCEL_GRACE_PERIOD).unwrap();
+        assert!(wait_until_gone(grandchild), "grandchild {} survived cancel", grandchild);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_terminate_tree_escalates_to_sigkill() {
+        // Ignored signals stay ignored in the grandchild
+        let (mut child, grandchild) = spawn_with_grandchild("trap '' TERM; sleep 30 & echo $!; wait");
+        let started = Instant::now();
+        let status = terminate_tree(&mut child, Duration::from_millis(200)).unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        assert_eq!(exit_details(&status), (None, Some(libc::SIGKILL)));
+        assert!(wait_until_gone(grandchild), "grandchild {} survived SIGKILL", grandchild);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exit_details() {
+        let status = Command::new("sh").arg("-c").arg("exit 3").status().unwrap();
+        assert_eq!(exit_details(&status), (Some(3), None));
+        let status = Command::new("sh").arg("-c").arg("kill -TERM $$").status().unwrap();
+        assert_eq!(exit_details(&status), (None, Some(15)));
+    }
+
     #[test]
     fn test_shell_quote() {
         assert_eq!(shell_quote("hello"), "'hello'");
@@ -0,0 +1,648 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::core::atomic_write::write_atomic;
+use crate::core::encoding;
+use crate::core::file_probe::{self, SNIFF_LEN};
+use crate::core::file_version::{self, FileVersion};
+use crate::core::project_search::{self, SearchQuery};
+use crate::error::{AppError, Result};
+
+/// Unchanged lines shown around every diff hunk
+const HUNK_CONTEXT: usize = 3;
+
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// Replaces that stay undoable; older backups are deleted
+const DEFAULT_MAX_BACKUPS: usize = 20;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceEdit {
+    /// Stable within a file for a given query; pass back to select edits
+    pub id: usize,
+    /// 0-based line
+    pub line: usize,
+    /// 0-based column in characters
+    pub column: usize,
+    /// Length of the replaced text in characters
+    pub length: usize,
+    pub replacement: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffLineKind {
+    Context,
+    Removed,
+    Added,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    /// 0-based first line of the hunk in the current file
+    pub old_start: usize,
+    pub old_lines: usize,
+    /// 0-based first line of the hunk after replacing
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilePreview {
+    pub path: String,
+    /// Pass back with the approved edits; the file must not change in between
+    pub version: FileVersion,
+    pub edits: Vec<ReplaceEdit>,
+    pub hunks: Vec<DiffHunk>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedFile {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplacePreview {
+    pub files: Vec<FilePreview>,
+    /// Files with matches that cannot be rewritten safely
+    pub skipped: Vec<SkippedFile>,
+    /// Search stopped at `max_results`, so the preview is incomplete
+    pub limit_hit: bool,
+}
+
+/// A file the user approved for replacing
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApprovedFile {
+    pub path: String,
+    pub version: FileVersion,
+    /// Ids of the edits to apply; all of them when absent
+    pub edits: Option<Vec<usize>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceOutcome {
+    /// Pass to `ReplaceBackups::undo` to revert the whole replace
+    pub backup_id: String,
+    pub files: Vec<String>,
+    pub replacements: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupEntry {
+    pub path: String,
+    /// Name of the copy of the original bytes inside the backup directory
+    pub backup: String,
+    pub before: FileVersion,
+    /// Hash of the bytes written by the replace; undo refuses files changed since
+    pub after_hash: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceBackup {
+    pub id: String,
+    pub created_at: String,
+    pub pattern: String,
+    pub replacement: String,
+    pub entries: Vec<BackupEntry>,
+}
+
+/// A file's new contents, computed but not yet written
+struct FilePlan {
+    path: PathBuf,
+    version: FileVersion,
+    original: Vec<u8>,
+    replaced: Vec<u8>,
+    edits: Vec<ReplaceEdit>,
+    hunks: Vec<DiffHunk>,
+}
+
+/// Every edit a replace would make under `root`, with diff hunks per file
+pub fn preview(root: &Path, query: &SearchQuery, replacement: &str) -> Result<ReplacePreview> {
+    let regex = project_search::build_regex(query)?;
+    let mut paths = Vec::new();
+    let summary = project_search::search(root, query, &AtomicBool::new(false), |batch| {
+        paths.extend(batch.into_iter().map(|f| PathBuf::from(f.path)));
+    })?;
+    paths.sort();
+
+    let mut files = Vec::new();
+    let mut skipped = Vec::new();
+    for path in paths {
+        match plan_file(&path, &regex, query, replacement, None) {
+            Ok(Some(plan)) => files.push(FilePreview {
+                path: path.to_string_lossy().to_string(),
+                version: plan.version,
+                edits: plan.edits,
+                hunks: plan.hunks,
+            }),
+            Ok(None) => {}
+            Err(e) => skipped.push(SkippedFile {
+                path: path.to_string_lossy().to_string(),
+                reason: e.to_string(),
+            }),
+        }
+    }
+    Ok(ReplacePreview { files, skipped, limit_hit: summary.limit_hit })
+}
+
+/// Work out the replaced contents of one file, keeping only `selected` edits if given.
+/// `Ok(None)` when nothing would change.
+fn plan_file(
+    path: &Path,
+    regex: &Regex,
+    query: &SearchQuery,
+    replacement: &str,
+    selected: Option<&[usize]>,
+) -> Result<Option<FilePlan>> {
+    let original = fs::read(path)?;
+    let version = FileVersion::from_bytes(&fs::metadata(path)?, &original);
+    let cannot_rewrite = |reason: &str| AppError::InvalidTarget {
+        path: path.to_string_lossy().to_string(),
+        reason: reason.to_string(),
+    };
+    if file_probe::sniff(&original[..original.len().min(SNIFF_LEN)]).is_binary {
+        return Err(cannot_rewrite("binary file"));
+    }
+    let decoded = encoding::decode(&original);
+    if decoded.had_errors {
+        return Err(cannot_rewrite(&format!("not valid {} text", decoded.encoding)));
+    }
+
+    let mut edits = Vec::new();
+    let mut old_lines = Vec::new();
+    let mut new_lines: Vec<Option<String>> = Vec::new();
+    let mut text = String::with_capacity(decoded.content.len());
+    for (i, raw) in decoded.content.split_inclusive('\n').enumerate() {
+        let body = raw.strip_suffix('\n').map(|l| l.strip_suffix('\r').unwrap_or(l)).unwrap_or(raw);
+        let ending = &raw[body.len()..];
+        let line = replace_line(body, i, regex, query.is_regex, replacement, selected, &mut edits);
+        text.push_str(line.as_deref().unwrap_or(body));
+        text.push_str(ending);
+        old_lines.push(body);
+        new_lines.push(line);
+    }
+    if let Some(ids) = selected {
+        edits.retain(|e| ids.contains(&e.id));
+    }
+    if edits.is_empty() {
+        return Ok(None);
+    }
+
+    let replaced = encoding::encode(&text, &decoded.encoding, decoded.has_bom)?;
+    Ok(Some(FilePlan {
+        path: path.to_path_buf(),
+        version,
+        original,
+        replaced,
+        hunks: diff_hunks(&old_lines, &new_lines),
+        edits,
+    }))
+}
+
+/// The line with matches replaced, or `None` if it stays as is. Every match gets the
+/// next edit id, selected or not, so ids do not depend on the selection.
+fn replace_line(
+    line: &str,
+    index: usize,
+    regex: &Regex,
+    expand: bool,
+    replacement: &str,
+    selected: Option<&[usize]>,
+    edits: &mut Vec<ReplaceEdit>,
+) -> Option<String> {
+    let mut out = String::new();
+    let mut last = 0;
+    let mut column = 0;
+    let mut changed = false;
+    for caps in regex.captures_iter(line) {
+        let Some(m) = caps.get(0).filter(|m| !m.is_empty()) else { continue };
+        let mut with = String::new();
+        if expand {
+            caps.expand(replacement, &mut with);
+        } else {
+            with.push_str(replacement);
+        }
+        column += line[last..m.start()].chars().count();
+        let length = m.as_str().chars().count();
+        let id = edits.len();
+        edits.push(ReplaceEdit { id, line: index, column, length, replacement: with.clone() });
+        column += length;
+
+        out.push_str(&line[last..m.start()]);
+        if selected.map(|s| s.contains(&id)).unwrap_or(true) {
+            out.push_str(&with);
+            changed = true;
+        } else {
+            out.push_str(m.as_str());
+        }
+        last = m.end();
+    }
+    if !changed {
+        return None;
+    }
+    out.push_str(&line[last..]);
+    Some(out)
+}
+
+/// Group changed lines into hunks with `HUNK_CONTEXT` lines around them
+fn diff_hunks(old_lines: &[&str], new_lines: &[Option<String>]) -> Vec<DiffHunk> {
+    let changed: Vec<usize> = (0..new_lines.len()).filter(|&i| new_lines[i].is_some()).collect();
+    let mut groups: Vec<(usize, usize)> = Vec::new();
+    for &i in &changed {
+        match groups.last_mut() {
+            Some((_, last)) if i - *last <= 2 * HUNK_CONTEXT => *last = i,
+            _ => groups.push((i, i)),
+        }
+    }
+
+    // Replacements may contain line breaks, shifting later lines
+    let added_lines = |i: usize| new_lines[i].as_ref().map(|l| l.split('\n').count()).unwrap_or(1);
+    let mut hunks = Vec::new();
+    let mut shift: isize = 0;
+    let mut counted = 0;
+    for (first, last) in groups {
+        let start = first.saturating_sub(HUNK_CONTEXT);
+        let end = (last + HUNK_CONTEXT + 1).min(old_lines.len());
+        for i in counted..start {
+            shift += added_lines(i) as isize - 1;
+        }
+        let mut lines = Vec::new();
+        let mut new_count = 0;
+        for i in start..end {
+            match &new_lines[i] {
+                Some(new) => {
+                    lines.push(DiffLine { kind: DiffLineKind::Removed, text: old_lines[i].to_string() });
+                    for part in new.split('\n') {
+                        lines.push(DiffLine { kind: DiffLineKind::Added, text: part.to_string() });
+                        new_count += 1;
+                    }
+                }
+                None => {
+                    lines.push(DiffLine { kind: DiffLineKind::Context, text: old_lines[i].to_string() });
+                    new_count += 1;
+                }
+            }
+        }
+        hunks.push(DiffHunk {
+            old_start: start,
+            old_lines: end - start,
+            new_start: (start as isize + shift) as usize,
+            new_lines: new_count,
+            lines,
+        });
+        shift += new_count as isize - (end - start) as isize;
+        counted = end;
+    }
+    hunks
+}
+
+/// ReplaceBackups keeps the original bytes of every file touched by a replace,
+/// one directory per replace with a JSON manifest, so it can be undone as a whole.
+/// Only the most recent `max_backups` replaces are kept.
+pub struct ReplaceBackups {
+    root: PathBuf,
+    max_backups: usize,
+}
+
+impl ReplaceBackups {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root, max_backups: DEFAULT_MAX_BACKUPS }
+    }
+
+    pub fn with_max_backups(root: PathBuf, max_backups: usize) -> Self {
+        Self { root, max_backups }
+    }
+
+    /// Replace in the approved files, all or nothing. Every file is re-planned and
+    /// checked against its previewed version before anything is written; if a write
+    /// fails, files already written are put back.
+    pub fn apply(
+        &self,
+        query: &SearchQuery,
+        replacement: &str,
+        files: &[ApprovedFile],
+    ) -> Result<ReplaceOutcome> {
+        let regex = project_search::build_regex(query)?;
+        let mut plans = Vec::new();
+        for file in files {
+            let path = Path::new(&file.path);
+            let plan = match plan_file(path, &regex, query, replacement, file.edits.as_deref()) {
+                Err(AppError::Io(e)) if e.kind() == io::ErrorKind::NotFound => None,
+                plan => plan?,
+            };
+            // A file deleted or edited so that it no longer matches has no plan but is still a conflict
+            let current = match &plan {
+                Some(plan) => Some(plan.version.clone()),
+                None => FileVersion::of_path(path)?,
+            };
+            if current.as_ref().map(|v| &v.hash) != Some(&file.version.hash) {
+                return Err(AppError::Conflict { path: file.path.clone(), current });
+            }
+            let Some(plan) = plan else { continue };
+            plans.push(plan);
+        }
+
+        let backup = self.save(&query.pattern, replacement, &plans)?;
+        let writes: Vec<(&Path, &[u8], &[u8])> = plans
+            .iter()
+            .map(|p| (p.path.as_path(), p.replaced.as_slice(), p.original.as_slice()))
+            .collect();
+        if let Err(e) = write_all_or_none(&writes) {
+            let _ = fs::remove_dir_all(self.root.join(&backup.id));
+            return Err(e);
+        }
+        self.prune()?;
+        Ok(ReplaceOutcome {
+            backup_id: backup.id,
+            files: plans.iter().map(|p| p.path.to_string_lossy().to_string()).collect(),
+            replacements: plans.iter().map(|p| p.edits.len()).sum(),
+        })
+    }
+
+    /// Write the originals and the manifest before any file is touched
+    fn save(&self, pattern: &str, replacement: &str, plans: &[FilePlan]) -> Result<ReplaceBackup> {
+        fs::create_dir_all(&self.root)?;
+        let created_at = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+        let dir = self.reserve_dir()?;
+        let id = dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+
+        let mut entries = Vec::new();
+        for (n, plan) in plans.iter().enumerate() {
+            let backup = format!("{}.orig", n);
+            write_atomic(&dir.join(&backup), &plan.original)?;
+            entries.push(BackupEntry {
+                path: plan.path.to_string_lossy().to_string(),
+                backup,
+                before: plan.version.clone(),
+                after_hash: file_version::hash_bytes(&plan.replaced),
+            });
+        }
+        let manifest = ReplaceBackup {
+            id,
+            created_at,
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            entries,
+        };
+        write_atomic(&dir.join(MANIFEST_NAME), &serde_json::to_vec_pretty(&manifest)?)?;
+        Ok(manifest)
+    }
+
+    /// Claim a fresh directory named after the current time
+    fn reserve_dir(&self) -> Result<PathBuf> {
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+        let mut n = 1;
+        loop {
+            let id = if n == 1 { stamp.clone() } else { format!("{}-{}", stamp, n) };
+            n += 1;
+            let dir = self.root.join(&id);
+            match fs::create_dir(&dir) {
+                Ok(()) => return Ok(dir),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Delete the oldest backups beyond `max_backups`
+    fn prune(&self) -> Result<()> {
+        for backup in self.list()?.into_iter().skip(self.max_backups) {
+            fs::remove_dir_all(self.root.join(&backup.id))?;
+        }
+        Ok(())
+    }
+
+    /// Replaces that can still be undone, most recent first
+    pub fn list(&self) -> Result<Vec<ReplaceBackup>> {
+        let entries = match fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut backups = Vec::new();
+        for entry in entries {
+            let id = entry?.file_name().to_string_lossy().to_string();
+            // Skip directories of replaces that never got their manifest written
+            if let Ok(backup) = self.read(&id) {
+                backups.push(backup);
+            }
+        }
+        backups.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| b.id.cmp(&a.id)));
+        Ok(backups)
+    }
+
+    fn read(&self, id: &str) -> Result<ReplaceBackup> {
+        let not_found = || AppError::BackupNotFound(id.to_string());
+        if id.contains(['/', '\\']) || id.starts_with('.') {
+            return Err(not_found());
+        }
+        let text = fs::read(self.root.join(id).join(MANIFEST_NAME)).map_err(|_| not_found())?;
+        serde_json::from_slice(&text).map_err(|_| not_found())
+    }
+
+    /// Put back every file of a replace, all or nothing. Fails with `AppError::Conflict`
+    /// if any of them was changed after the replace.
+    pub fn undo(&self, id: &str) -> Result<Vec<String>> {
+        let backup = self.read(id)?;
+        let dir = self.root.join(id);
+        let mut restores = Vec::new();
+        for entry in &backup.entries {
+            let path = Path::new(&entry.path);
+            let current = fs::read(path).ok();
+            if current.as_deref().map(file_version::hash_bytes).as_ref() != Some(&entry.after_hash) {
+                return Err(AppError::Conflict {
+                    path: entry.path.clone(),
+                    current: FileVersion::of_path(path)?,
+                });
+            }
+            let original = fs::read(dir.join(&entry.backup))?;
+            restores.push((path, original, current.unwrap_or_default()));
+        }
+        let writes: Vec<(&Path, &[u8], &[u8])> = restores
+            .iter()
+            .map(|(path, original, current)| (*path, original.as_slice(), current.as_slice()))
+            .collect();
+        write_all_or_none(&writes)?;
+        fs::remove_dir_all(&dir)?;
+        Ok(backup.entries.into_iter().map(|e| e.path).collect())
+    }
+
+    /// Forget a replace without undoing it
+    pub fn discard(&self, id: &str) -> Result<()> {
+        self.read(id)?;
+        fs::remove_dir_all(self.root.join(id))?;
+        Ok(())
+    }
+}
+
+/// Write `(path, new, old)` triples atomically one by one; on the first failure
+/// restore `old` in the files already written and return the error
+fn write_all_or_none(writes: &[(&Path, &[u8], &[u8])]) -> Result<()> {
+    for (done, (path, new, _)) in writes.iter().enumerate() {
+        if let Err(e) = write_atomic(path, new) {
+            for (path, _, old) in writes[..done].iter().rev() {
+                let _ = write_atomic(path, old);
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("editrion-replace-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        dir
+    }
+
+    fn query(pattern: &str, is_regex: bool) -> SearchQuery {
+        SearchQuery { pattern: pattern.to_string(), is_regex, case_sensitive: true, ..Default::default() }
+    }
+
+    fn approve(file: &FilePreview, edits: Option<Vec<usize>>) -> ApprovedFile {
+        ApprovedFile { path: file.path.clone(), version: file.version.clone(), edits }
+    }
+
+    #[test]
+    fn test_preview_with_capture_groups_and_hunks() {
+        let dir = temp_dir("preview");
+        let lines: Vec<String> = (0..12).map(|i| format!("line {}", i)).collect();
+        let mut text = lines.join("\r\n");
+        text = text.replace("line 1\r\n", "let a = get(x);\r\n").replace("line 10", "let b = get(y);");
+        fs::write(dir.join("src/a.rs"), &text).unwrap();
+
+        let previewed = preview(&dir, &query(r"get\((\w+)\)", true), "fetch(&$1)").unwrap();
+        assert_eq!(previewed.files.len(), 1);
+        let file = &previewed.files[0];
+        assert_eq!(file.edits.len(), 2);
+        assert_eq!((file.edits[0].line, file.edits[0].column, file.edits[0].length), (1, 8, 6));
+        assert_eq!(file.edits[1].replacement, "fetch(&y)");
+
+        assert_eq!(file.hunks.len(), 2);
+        let first = &file.hunks[0];
+        assert_eq!((first.old_start, first.old_lines, first.new_start, first.new_lines), (0, 5, 0, 5));
+        assert_eq!(first.lines[1], DiffLine { kind: DiffLineKind::Removed, text: "let a = get(x);".to_string() });
+        assert_eq!(first.lines[2], DiffLine { kind: DiffLineKind::Added, text: "let a = fetch(&x);".to_string() });
+        assert_eq!((file.hunks[1].old_start, file.hunks[1].old_lines), (7, 5));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_apply_subset_and_undo() {
+        let dir = temp_dir("apply");
+        let backups = ReplaceBackups::new(dir.join("backups"));
+        fs::write(dir.join("src/a.txt"), "foo foo\nbar\n").unwrap();
+        fs::write(dir.join("src/b.txt"), "foo\n").unwrap();
+        let q = SearchQuery { exclude: vec!["backups/".to_string()], ..query("foo", false) };
+
+        let previewed = preview(&dir, &q, "$1qux").unwrap();
+        let approved = vec![approve(&previewed.files[0], Some(vec![1])), approve(&previewed.files[1], None)];
+        let outcome = backups.apply(&q, "$1qux", &approved).unwrap();
+        assert_eq!(outcome.replacements, 2);
+        // Literal replacements are not expanded
+        assert_eq!(fs::read_to_string(dir.join("src/a.txt")).unwrap(), "foo $1qux\nbar\n");
+        assert_eq!(fs::read_to_string(dir.join("src/b.txt")).unwrap(), "$1qux\n");
+        assert_eq!(backups.list().unwrap().len(), 1);
+
+        assert_eq!(backups.undo(&outcome.backup_id).unwrap().len(), 2);
+        assert_eq!(fs::read_to_string(dir.join("src/a.txt")).unwrap(), "foo foo\nbar\n");
+        assert_eq!(fs::read_to_string(dir.join("src/b.txt")).unwrap(), "foo\n");
+        assert!(backups.list().unwrap().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_conflict_writes_nothing() {
+        let dir = temp_dir("conflict");
+        let backups = ReplaceBackups::new(dir.join("backups"));
+        fs::write(dir.join("src/a.txt"), "foo\n").unwrap();
+        fs::write(dir.join("src/b.txt"), "foo\n").unwrap();
+        let q = query("foo", false);
+
+        let previewed = preview(&dir, &q, "bar").unwrap();
+        let approved: Vec<_> = previewed.files.iter().map(|f| approve(f, None)).collect();
+        fs::write(dir.join("src/b.txt"), "foo changed\n").unwrap();
+        assert!(matches!(backups.apply(&q, "bar", &approved), Err(AppError::Conflict { .. })));
+        assert_eq!(fs::read_to_string(dir.join("src/a.txt")).unwrap(), "foo\n");
+        assert!(backups.list().unwrap().is_empty());
+
+        // Undo refuses files edited after the replace
+        let previewed = preview(&dir, &q, "bar").unwrap();
+        let approved: Vec<_> = previewed.files.iter().map(|f| approve(f, None)).collect();
+        let outcome = backups.apply(&q, "bar", &approved).unwrap();
+        fs::write(dir.join("src/a.txt"), "edited\n").unwrap();
+        assert!(matches!(backups.undo(&outcome.backup_id), Err(AppError::Conflict { .. })));
+        assert_eq!(fs::read_to_string(dir.join("src/b.txt")).unwrap(), "bar changed\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_no_longer_matching_is_a_conflict() {
+        let dir = temp_dir("nomatch");
+        let backups = ReplaceBackups::new(dir.join("backups"));
+        fs::write(dir.join("src/a.txt"), "foo\n").unwrap();
+        fs::write(dir.join("src/b.txt"), "foo\n").unwrap();
+        let q = query("foo", false);
+
+        let previewed = preview(&dir, &q, "bar").unwrap();
+        let approved: Vec<_> = previewed.files.iter().map(|f| approve(f, None)).collect();
+        fs::write(dir.join("src/b.txt"), "nothing to replace\n").unwrap();
+        assert!(matches!(
+            backups.apply(&q, "bar", &approved),
+            Err(AppError::Conflict { path, current: Some(_) }) if path.ends_with("b.txt")
+        ));
+        assert_eq!(fs::read_to_string(dir.join("src/a.txt")).unwrap(), "foo\n");
+
+        fs::remove_file(dir.join("src/b.txt")).unwrap();
+        assert!(matches!(backups.apply(&q, "bar", &approved), Err(AppError::Conflict { current: None, .. })));
+        assert!(backups.list().unwrap().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_old_backups_are_pruned() {
+        let dir = temp_dir("prune");
+        let backups = ReplaceBackups::with_max_backups(dir.join("backups"), 2);
+        fs::write(dir.join("src/a.txt"), "0\n").unwrap();
+        let q = SearchQuery { exclude: vec!["backups/".to_string()], ..query(r"\d+", true) };
+
+        let mut ids = Vec::new();
+        for n in 1..=3 {
+            let previewed = preview(&dir, &q, &n.to_string()).unwrap();
+            let approved: Vec<_> = previewed.files.iter().map(|f| approve(f, None)).collect();
+            ids.push(backups.apply(&q, &n.to_string(), &approved).unwrap().backup_id);
+        }
+        let kept: Vec<_> = backups.list().unwrap().into_iter().map(|b| b.id).collect();
+        assert_eq!(kept, vec![ids[2].clone(), ids[1].clone()]);
+        assert!(!dir.join("backups").join(&ids[0]).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,421 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkState;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::core::encoding;
+use crate::core::file_probe::{self, SNIFF_LEN};
+use crate::error::{AppError, Result};
+
+/// Files larger than this are skipped unless the query sets another limit
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Search stops once this many matches were found unless the query sets another limit
+pub const DEFAULT_MAX_RESULTS: usize = 20_000;
+
+/// Longest line text sent to the frontend; longer lines are cut and flagged
+const MAX_LINE_CHARS: usize = 2000;
+
+/// A batch is delivered when it holds this many files or has waited this long
+const BATCH_FILES: usize = 64;
+const BATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchQuery {
+    pub pattern: String,
+    pub is_regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    /// Gitignore-style globs; when present only matching files are searched
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    /// Honour `.gitignore`, `.ignore` and git's exclude files
+    pub use_ignore_files: bool,
+    /// Lines of context before and after every matching line
+    pub context_lines: usize,
+    pub max_results: usize,
+    pub max_file_size: u64,
+}
+
+impl Default for SearchQuery {
+    fn default() -> Self {
+        Self {
+            pattern: String::new(),
+            is_regex: false,
+            case_sensitive: false,
+            whole_word: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            use_ignore_files: true,
+            context_lines: 2,
+            max_results: DEFAULT_MAX_RESULTS,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchRange {
+    /// 0-based column in characters
+    pub column: usize,
+    /// Length in characters
+    pub length: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineMatch {
+    /// 0-based line
+    pub line: usize,
+    pub text: String,
+    /// `text` was cut to `MAX_LINE_CHARS`; ranges still refer to the full line
+    pub truncated: bool,
+    pub ranges: Vec<MatchRange>,
+    pub context_before: Vec<String>,
+    pub context_after: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileResult {
+    pub path: String,
+    pub matches: Vec<LineMatch>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchSummary {
+    pub files_searched: usize,
+    pub files_matched: usize,
+    pub matches: usize,
+    /// Stopped early because `max_results` was reached
+    pub limit_hit: bool,
+    pub cancelled: bool,
+}
+
+/// Compile the pattern as the line matcher used for every file
+pub fn build_regex(query: &SearchQuery) -> Result<Regex> {
+    if query.pattern.is_empty() {
+        return Err(AppError::InvalidPattern("pattern is empty".to_string()));
+    }
+    let mut pattern = if query.is_regex { query.pattern.clone() } else { regex::escape(&query.pattern) };
+    if query.whole_word {
+        pattern = format!(r"\b(?:{})\b", pattern);
+    }
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!query.case_sensitive)
+        .multi_line(true)
+        .crlf(true)
+        .build()
+        .map_err(|e| AppError::InvalidPattern(e.to_string()))
+}
+
+/// Search every file under `root`, delivering matches to `on_batch` in batches
+/// as they are found. Stops early when `cancelled` is set.
+pub fn search(
+    root: &Path,
+    query: &SearchQuery,
+    cancelled: &AtomicBool,
+    mut on_batch: impl FnMut(Vec<FileResult>),
+) -> Result<SearchSummary> {
+    if !root.is_dir() {
+        return Err(AppError::NotFound(root.to_string_lossy().to_string()));
+    }
+    let regex = build_regex(query)?;
+    let walker = walker(root, query)?;
+
+    let searched = AtomicUsize::new(0);
+    let found = AtomicUsize::new(0);
+    let stop = || cancelled.load(Ordering::Relaxed) || found.load(Ordering::Relaxed) >= query.max_results;
+    let mut summary = SearchSummary::default();
+    let (tx, rx) = mpsc::channel::<FileResult>();
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            walker.build_parallel().run(|| {
+                let tx = tx.clone();
+                let (regex, searched, found) = (&regex, &searched, &found);
+                Box::new(move |entry| {
+                    if stop() {
+                        return WalkState::Quit;
+                    }
+                    let Ok(entry) = entry else { return WalkState::Continue };
+                    if !entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
+                        return WalkState::Continue;
+                    }
+                    searched.fetch_add(1, Ordering::Relaxed);
+                    let Some(matches) = search_file(entry.path(), regex, query) else { return WalkState::Continue };
+                    found.fetch_add(matches.iter().map(|m| m.ranges.len()).sum(), Ordering::Relaxed);
+                    let result = FileResult { path: entry.path().to_string_lossy().to_string(), matches };
+                    if tx.send(result).is_err() {
+                        return WalkState::Quit;
+                    }
+                    WalkState::Continue
+                })
+            });
+            drop(tx);
+        });
+
+        let mut batch = Vec::new();
+        let mut last_flush = Instant::now();
+        loop {
+            let next = rx.recv_timeout(BATCH_INTERVAL.saturating_sub(last_flush.elapsed()));
+            let done = matches!(next, Err(mpsc::RecvTimeoutError::Disconnected));
+            if let Ok(result) = next {
+                summary.files_matched += 1;
+                summary.matches += result.matches.iter().map(|m| m.ranges.len()).sum::<usize>();
+                batch.push(result);
+            }
+            if done || batch.len() >= BATCH_FILES || last_flush.elapsed() >= BATCH_INTERVAL {
+                if !batch.is_empty() {
+                    on_batch(std::mem::take(&mut batch));
+                }
+                last_flush = Instant::now();
+            }
+            if done {
+                break;
+            }
+        }
+    });
+
+    summary.files_searched = searched.load(Ordering::Relaxed);
+    summary.cancelled = cancelled.load(Ordering::Relaxed);
+    summary.limit_hit = !summary.cancelled && summary.matches >= query.max_results;
+    Ok(summary)
+}
+
+fn walker(root: &Path, query: &SearchQuery) -> Result<ignore::WalkBuilder> {
+    let mut overrides = OverrideBuilder::new(root);
+    let globs = query
+        .include
+        .iter()
+        .cloned()
+        .chain(query.exclude.iter().map(|g| format!("!{}", g)));
+    for glob in globs.filter(|g| !g.trim().is_empty()) {
+        overrides.add(&glob).map_err(|e| AppError::InvalidPattern(e.to_string()))?;
+    }
+    let overrides = overrides.build().map_err(|e| AppError::InvalidPattern(e.to_string()))?;
+
+    let mut builder = ignore::WalkBuilder::new(root);
+    builder
+        .hidden(false)
+        .parents(query.use_ignore_files)
+        .ignore(query.use_ignore_files)
+        .git_ignore(query.use_ignore_files)
+        .git_global(query.use_ignore_files)
+        .git_exclude(query.use_ignore_files)
+        .overrides(overrides)
+        .filter_entry(|e| e.file_name() != ".git");
+    Ok(builder)
+}
+
+/// Matching lines of one file, or `None` when it has none or is not searchable text
+pub fn search_file(path: &Path, regex: &Regex, query: &SearchQuery) -> Option<Vec<LineMatch>> {
+    let file = File::open(path).ok()?;
+    if file.metadata().ok()?.len() > query.max_file_size {
+        return None;
+    }
+    let mut bytes = Vec::new();
+    file.take(query.max_file_size).read_to_end(&mut bytes).ok()?;
+    if file_probe::sniff(&bytes[..bytes.len().min(SNIFF_LEN)]).is_binary {
+        return None;
+    }
+    let text = encoding::decode(&bytes).content;
+    if !regex.is_match(&text) {
+        return None;
+    }
+    let matches = search_text(&text, regex, query.context_lines);
+    if matches.is_empty() {
+        None
+    } else {
+        Some(matches)
+    }
+}
+
+/// Line-by-line matches in `text`; matches never span lines
+pub fn search_text(text: &str, regex: &Regex, context_lines: usize) -> Vec<LineMatch> {
+    let lines: Vec<&str> = text.split('\n').map(|l| l.strip_suffix('\r').unwrap_or(l)).collect();
+    let mut out = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let mut ranges = Vec::new();
+        // Byte offsets to character columns, walking the line once
+        let mut chars_before = 0;
+        let mut last_byte = 0;
+        for m in regex.find_iter(line) {
+            if m.start() == m.end() {
+                continue;
+            }
+            chars_before += line[last_byte..m.start()].chars().count();
+            let length = m.as_str().chars().count();
+            ranges.push(MatchRange { column: chars_before, length });
+            chars_before += length;
+            last_byte = m.end();
+        }
+        if ranges.is_empty() {
+            continue;
+        }
+        let (text, truncated) = preview(line);
+        out.push(LineMatch {
+            line: i,
+            text,
+            truncated,
+            ranges,
+            context_before: lines[i.saturating_sub(context_lines)..i].iter().map(|l| preview(l).0).collect(),
+            context_after: lines[i + 1..(i + 1 + context_lines).min(lines.len())].iter().map(|l| preview(l).0).collect(),
+        });
+    }
+    out
+}
+
+fn preview(line: &str) -> (String, bool) {
+    match line.char_indices().nth(MAX_LINE_CHARS) {
+        Some((cut, _)) => (line[..cut].to_string(), true),
+        None => (line.to_string(), false),
+    }
+}
+
+/// SearchManager keeps the cancellation flags of running searches by run id
+pub struct SearchManager {
+    pub searches: HashMap<String, Arc<AtomicBool>>,
+}
+
+impl Default for SearchManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SearchManager {
+    pub fn new() -> Self {
+        Self {
+            searches: HashMap::new(),
+        }
+    }
+
+    /// Register a search and get the flag it should poll
+    pub fn add_search(&mut self, run_id: String) -> Arc<AtomicBool> {
+        let flag = Arc::new(AtomicBool::new(false));
+        self.searches.insert(run_id, flag.clone());
+        flag
+    }
+
+    pub fn cancel_search(&mut self, run_id: &str) -> Result<()> {
+        match self.searches.remove(run_id) {
+            Some(flag) => {
+                flag.store(true, Ordering::Relaxed);
+                Ok(())
+            }
+            None => Err(AppError::SearchNotFound(run_id.to_string())),
+        }
+    }
+
+    /// Forget a finished search
+    pub fn remove_search(&mut self, run_id: &str) {
+        self.searches.remove(run_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("editrion-search-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn query(pattern: &str) -> SearchQuery {
+        SearchQuery { pattern: pattern.to_string(), ..Default::default() }
+    }
+
+    fn run(root: &Path, query: &SearchQuery) -> (Vec<FileResult>, SearchSummary) {
+        let mut results = Vec::new();
+        let summary = search(root, query, &AtomicBool::new(false), |batch| results.extend(batch)).unwrap();
+        results.sort_by(|a, b| a.path.cmp(&b.path));
+        (results, summary)
+    }
+
+    #[test]
+    fn test_search_text_columns_and_context() {
+        let regex = build_regex(&query("héllo")).unwrap();
+        let matches = search_text("one\r\nsay Héllo, héllo\r\nthree\nfour", &regex, 1);
+        assert_eq!(matches.len(), 1);
+        let m = &matches[0];
+        assert_eq!(m.line, 1);
+        assert_eq!(m.text, "say Héllo, héllo");
+        assert_eq!(m.ranges, vec![MatchRange { column: 4, length: 5 }, MatchRange { column: 11, length: 5 }]);
+        assert_eq!(m.context_before, vec!["one"]);
+        assert_eq!(m.context_after, vec!["three"]);
+    }
+
+    #[test]
+    fn test_pattern_options() {
+        let text = "foo food Foo\n";
+        let count = |q: SearchQuery| search_text(text, &build_regex(&q).unwrap(), 0)[0].ranges.len();
+        assert_eq!(count(query("foo")), 3);
+        assert_eq!(count(SearchQuery { case_sensitive: true, ..query("foo") }), 2);
+        assert_eq!(count(SearchQuery { whole_word: true, ..query("foo") }), 2);
+        assert_eq!(count(SearchQuery { is_regex: true, ..query("fo+d?$") }), 1);
+        assert!(search_text(text, &build_regex(&query("fo+")).unwrap(), 0).is_empty());
+        assert!(matches!(build_regex(&SearchQuery { is_regex: true, ..query("(") }), Err(AppError::InvalidPattern(_))));
+    }
+
+    #[test]
+    fn test_search_tree_with_globs_and_ignores() {
+        let dir = temp_dir("tree");
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::write(dir.join(".gitignore"), "dist/\n").unwrap();
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("dist")).unwrap();
+        fs::write(dir.join("src/a.rs"), "let needle = 1;\n").unwrap();
+        fs::write(dir.join("src/b.ts"), "needle();\nneedle();\n").unwrap();
+        fs::write(dir.join("dist/out.js"), "needle").unwrap();
+        fs::write(dir.join("src/blob.bin"), b"needle\0\0\0\x01\x02").unwrap();
+
+        let (results, summary) = run(&dir, &query("needle"));
+        assert_eq!(results.len(), 2);
+        assert_eq!(summary.files_matched, 2);
+        assert_eq!(summary.matches, 3);
+
+        let (results, _) = run(&dir, &SearchQuery { use_ignore_files: false, ..query("needle") });
+        assert_eq!(results.len(), 3);
+
+        let (results, _) = run(&dir, &SearchQuery { include: vec!["*.ts".to_string()], ..query("needle") });
+        assert_eq!(results.len(), 1);
+        assert!(results[0].path.ends_with("b.ts"));
+
+        let (results, _) = run(&dir, &SearchQuery { exclude: vec!["src/b.*".to_string()], ..query("needle") });
+        assert_eq!(results.len(), 1);
+        assert!(results[0].path.ends_with("a.rs"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cancelled_search_reports_it() {
+        let dir = temp_dir("cancel");
+        fs::write(dir.join("a.txt"), "needle").unwrap();
+        let summary = search(&dir, &query("needle"), &AtomicBool::new(true), |_| {}).unwrap();
+        assert!(summary.cancelled);
+        assert_eq!(summary.matches, 0);
+
+        let mut manager = SearchManager::new();
+        let flag = manager.add_search("run-1".to_string());
+        manager.cancel_search("run-1").unwrap();
+        assert!(flag.load(Ordering::Relaxed));
+        assert!(matches!(manager.cancel_search("run-1"), Err(AppError::SearchNotFound(_))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,304 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::atomic_write::write_atomic;
+use crate::core::file_version::FileVersion;
+use crate::core::line_endings::LineEnding;
+use crate::error::{AppError, Result};
+
+/// Bump when the file layout changes and teach `upgrade` the previous one
+pub const SESSION_VERSION: u32 = 1;
+
+const SESSION_NAME: &str = "session.json";
+
+/// Everything needed to bring the editor back after a restart or crash
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Session {
+    pub version: u32,
+    /// Milliseconds since the Unix epoch
+    pub saved_at: u64,
+    /// Set by the last save before quitting; a session without it was left by a crash
+    pub clean_exit: bool,
+    pub roots: Vec<String>,
+    /// `.editrion-workspace` file that was open, if any
+    pub workspace: Option<String>,
+    /// In tab bar order
+    pub tabs: Vec<SessionTab>,
+    pub active_tab: Option<String>,
+    pub window: Option<WindowGeometry>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SessionTab {
+    pub id: String,
+    pub name: String,
+    /// Empty for buffers that were never saved
+    pub path: String,
+    /// Buffer text, only kept for tabs with unsaved changes
+    pub content: Option<String>,
+    /// Disk version the unsaved buffer was based on, to detect changes made meanwhile
+    pub version: Option<FileVersion>,
+    /// How the file of an unsaved buffer is stored, so it is saved back the same way
+    pub format: Option<TextFormat>,
+    pub view: Option<ViewState>,
+}
+
+/// Encoding and line breaks a buffer was read with
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TextFormat {
+    /// WHATWG encoding name, as reported by `read_file_with_encoding`
+    pub encoding: String,
+    pub bom: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_ending: Option<LineEnding>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_newline: Option<bool>,
+}
+
+/// Cursor and scroll position of a tab
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ViewState {
+    /// 1-based, as reported by the editor
+    pub line: u32,
+    pub column: u32,
+    pub scroll_top: f64,
+    pub scroll_left: f64,
+}
+
+/// Outer position and inner size in physical pixels
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub maximized: bool,
+    pub fullscreen: bool,
+}
+
+/// A draft written by earlier versions as `<drafts dir>/<tab id>.json`, one per dirty tab
+#[derive(Debug, Deserialize)]
+struct LegacyDraft {
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    path: String,
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    ts: u64,
+}
+
+/// SessionStore keeps the session as a single JSON file, replaced atomically on
+/// every save so a crash mid-write leaves the previous session intact.
+pub struct SessionStore {
+    dir: PathBuf,
+}
+
+impl SessionStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn session_path(&self) -> PathBuf {
+        self.dir.join(SESSION_NAME)
+    }
+
+    pub fn save(&self, session: &Session) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let session = Session { version: SESSION_VERSION, saved_at: now_millis(), ..session.clone() };
+        write_atomic(&self.session_path(), &serde_json::to_vec_pretty(&session)?)
+    }
+
+    /// The stored session, or the drafts left in `legacy_drafts` by an older version
+    /// converted into one. A file that cannot be parsed is set aside as
+    /// `session.json.corrupt` rather than failing startup forever.
+    pub fn load(&self, legacy_drafts: Option<&Path>) -> Result<Option<Session>> {
+        let bytes = match fs::read(self.session_path()) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return match legacy_drafts {
+                    Some(dir) => self.migrate_drafts(dir),
+                    None => Ok(None),
+                };
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let value: serde_json::Value = match serde_json::from_slice(&bytes) {
+            Ok(value) => value,
+            Err(_) => {
+                fs::rename(self.session_path(), self.dir.join(format!("{}.corrupt", SESSION_NAME)))?;
+                return Ok(None);
+            }
+        };
+        Ok(Some(upgrade(value)?))
+    }
+
+    /// Forget the session, e.g. after the user declined to restore it
+    pub fn discard(&self) -> Result<()> {
+        match fs::remove_file(self.session_path()) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Turn the old per-tab draft files into a session, save it and remove the drafts.
+    /// Drafts only existed for dirty tabs and were cleared on a clean exit, so the
+    /// result is treated as a crashed session.
+    fn migrate_drafts(&self, drafts_dir: &Path) -> Result<Option<Session>> {
+        let entries = match fs::read_dir(drafts_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut drafts = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().map(|e| e != "json").unwrap_or(true) {
+                continue;
+            }
+            // Unreadable drafts are left in place rather than lost
+            if let Some(draft) = fs::read(&path).ok().and_then(|b| serde_json::from_slice::<LegacyDraft>(&b).ok()) {
+                drafts.push((path, draft));
+            }
+        }
+        if drafts.is_empty() {
+            return Ok(None);
+        }
+        drafts.sort_by_key(|(_, d)| d.ts);
+
+        let tabs = drafts
+            .iter()
+            .map(|(file, d)| SessionTab {
+                id: if d.id.is_empty() {
+                    file.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default()
+                } else {
+                    d.id.clone()
+                },
+                name: d.name.clone(),
+                path: d.path.clone(),
+                content: Some(d.content.clone()),
+                ..Default::default()
+            })
+            .collect();
+        let session = Session { tabs, ..Default::default() };
+        self.save(&session)?;
+        for (path, _) in &drafts {
+            let _ = fs::remove_file(path);
+        }
+        Ok(Some(Session { version: SESSION_VERSION, ..session }))
+    }
+}
+
+/// Bring a stored session up to `SESSION_VERSION`
+fn upgrade(value: serde_json::Value) -> Result<Session> {
+    let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+    match version {
+        // Version 0 only differs by the missing field
+        0 | 1 => Ok(Session { version: SESSION_VERSION, ..serde_json::from_value(value)? }),
+        v => Err(AppError::Config(format!(
+            "session file version {} is newer than supported version {}",
+            v, SESSION_VERSION
+        ))),
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("editrion-session-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_save_load_discard() {
+        let dir = temp_dir("roundtrip");
+        let store = SessionStore::new(dir.join("session"));
+        assert_eq!(store.load(None).unwrap(), None);
+
+        let session = Session {
+            roots: vec!["/work".to_string()],
+            tabs: vec![SessionTab {
+                id: "tab-1".to_string(),
+                name: "a.txt".to_string(),
+                path: "/work/a.txt".to_string(),
+                content: Some("unsaved".to_string()),
+                format: Some(TextFormat {
+                    encoding: "windows-1251".to_string(),
+                    line_ending: Some(LineEnding::Crlf),
+                    final_newline: Some(true),
+                    ..Default::default()
+                }),
+                view: Some(ViewState { line: 3, column: 2, scroll_top: 40.0, scroll_left: 0.0 }),
+                ..Default::default()
+            }],
+            active_tab: Some("tab-1".to_string()),
+            ..Default::default()
+        };
+        store.save(&session).unwrap();
+        let loaded = store.load(None).unwrap().unwrap();
+        assert_eq!(loaded.version, SESSION_VERSION);
+        assert_eq!(loaded.tabs, session.tabs);
+        assert_eq!(loaded.roots, session.roots);
+
+        store.discard().unwrap();
+        assert_eq!(store.load(None).unwrap(), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_migrates_legacy_drafts() {
+        let dir = temp_dir("migrate");
+        let drafts = dir.join("drafts");
+        fs::create_dir_all(&drafts).unwrap();
+        fs::write(drafts.join("tab-2.json"), r#"{"id":"tab-2","name":"b","path":"","content":"two","ts":20}"#).unwrap();
+        fs::write(drafts.join("tab-1.json"), r#"{"id":"tab-1","name":"a","path":"/a","content":"one","ts":10}"#).unwrap();
+        fs::write(drafts.join("broken.json"), "{").unwrap();
+
+        let store = SessionStore::new(dir.join("session"));
+        let session = store.load(Some(&drafts)).unwrap().unwrap();
+        assert!(!session.clean_exit);
+        let contents: Vec<_> = session.tabs.iter().map(|t| t.content.as_deref().unwrap()).collect();
+        assert_eq!(contents, ["one", "two"]);
+        assert!(drafts.join("broken.json").exists());
+        assert!(!drafts.join("tab-1.json").exists());
+        // The migrated session is what gets loaded from now on
+        assert_eq!(store.load(Some(&drafts)).unwrap().unwrap().tabs, session.tabs);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_and_newer_sessions() {
+        let dir = temp_dir("corrupt");
+        let store = SessionStore::new(dir.clone());
+        fs::write(dir.join(SESSION_NAME), "{ not json").unwrap();
+        assert_eq!(store.load(None).unwrap(), None);
+        assert!(dir.join("session.json.corrupt").exists());
+
+        fs::write(dir.join(SESSION_NAME), r#"{"version": 99}"#).unwrap();
+        assert!(matches!(store.load(None), Err(AppError::Config(_))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
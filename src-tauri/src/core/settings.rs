@@ -0,0 +1,534 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::core::atomic_write::write_atomic;
+use crate::error::{AppError, Result};
+
+/// Bump when keys are renamed or change meaning
+pub const SETTINGS_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy)]
+pub enum SettingKind {
+    String,
+    /// One of the listed strings
+    Enum(&'static [&'static str]),
+    StringList,
+    /// Object whose values are strings or arrays of strings
+    StringMap,
+    /// Any JSON array; items are checked by whoever uses them
+    Array,
+    /// Any JSON object; entries are checked by whoever uses them
+    Object,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SettingSpec {
+    /// Dotted path; `ai.model` is stored as `{ "ai": { "model": ... } }`
+    pub key: &'static str,
+    pub kind: SettingKind,
+    /// JSON literal
+    pub default: &'static str,
+    /// Whether `null` is allowed, meaning "not set"
+    pub nullable: bool,
+}
+
+const LOCALES: &[&str] = &["en", "uk", "es", "fr", "ja", "de"];
+const PROVIDERS: &[&str] = &["codex", "claude", "custom"];
+const EFFORTS: &[&str] = &["minimal", "low", "medium", "high"];
+const SUMMARIES: &[&str] = &["auto", "concise", "detailed", "none"];
+const VERBOSITIES: &[&str] = &["low", "medium", "high"];
+
+/// Every known setting. Keys missing here are reported as unknown.
+pub const SCHEMA: &[SettingSpec] = &[
+    SettingSpec { key: "theme", kind: SettingKind::String, default: "\"dark\"", nullable: false },
+    SettingSpec { key: "themeMode", kind: SettingKind::Enum(&["dark", "light"]), default: "\"dark\"", nullable: false },
+    SettingSpec { key: "customThemes", kind: SettingKind::Object, default: "{}", nullable: false },
+    // Null follows the system language
+    SettingSpec { key: "locale", kind: SettingKind::Enum(LOCALES), default: "null", nullable: true },
+    SettingSpec { key: "projectRoots", kind: SettingKind::StringList, default: "[]", nullable: false },
+    SettingSpec { key: "shortcuts", kind: SettingKind::StringMap, default: "{}", nullable: false },
+    SettingSpec { key: "ai.provider", kind: SettingKind::Enum(PROVIDERS), default: "\"codex\"", nullable: false },
+    // Executable and arguments of the "custom" provider
+    SettingSpec { key: "ai.customCommand", kind: SettingKind::StringList, default: "[]", nullable: false },
+    SettingSpec { key: "ai.model", kind: SettingKind::String, default: "null", nullable: true },
+    SettingSpec { key: "ai.effort", kind: SettingKind::Enum(EFFORTS), default: "null", nullable: true },
+    SettingSpec { key: "ai.summary", kind: SettingKind::Enum(SUMMARIES), default: "null", nullable: true },
+    SettingSpec { key: "ai.verbosity", kind: SettingKind::Enum(VERBOSITIES), default: "null", nullable: true },
+    SettingSpec { key: "ai.templates", kind: SettingKind::Array, default: "[]", nullable: false },
+];
+
+/// A setting that was ignored because it is invalid
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsProblem {
+    pub file: String,
+    /// Dotted key, absent when the whole file could not be parsed
+    pub key: Option<String>,
+    /// 1-based position in the file, when it could be found
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+/// Effective settings after layering defaults, user file and workspace
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsSnapshot {
+    pub settings: Value,
+    /// Parsed Codex `config.toml`; the last valid version while the file has errors
+    pub codex: Value,
+    /// Invalid entries that were skipped, so the rest of the file still applies
+    pub problems: Vec<SettingsProblem>,
+    /// Dotted keys that differ from the previous broadcast, `codex.` prefixed for
+    /// the Codex config. Empty when the snapshot was requested rather than pushed.
+    pub changed: Vec<String>,
+}
+
+pub fn spec(key: &str) -> Option<&'static SettingSpec> {
+    SCHEMA.iter().find(|s| s.key == key)
+}
+
+/// Check one value against the schema; the error explains what was expected
+pub fn check(spec: &SettingSpec, value: &Value) -> std::result::Result<(), String> {
+    if value.is_null() {
+        return if spec.nullable { Ok(()) } else { Err("must not be null".to_string()) };
+    }
+    let ok = match spec.kind {
+        SettingKind::String => value.is_string(),
+        SettingKind::Enum(allowed) => {
+            return match value.as_str() {
+                Some(s) if allowed.contains(&s) => Ok(()),
+                _ => Err(format!("expected one of {}, got {}", allowed.join(", "), value)),
+            };
+        }
+        SettingKind::StringList => is_string_list(value),
+        SettingKind::StringMap => value
+            .as_object()
+            .map(|o| o.values().all(|v| v.is_string() || is_string_list(v)))
+            .unwrap_or(false),
+        SettingKind::Array => value.is_array(),
+        SettingKind::Object => value.is_object(),
+    };
+    if ok {
+        Ok(())
+    } else {
+        Err(format!("expected {}, got {}", kind_name(spec.kind), type_name(value)))
+    }
+}
+
+fn is_string_list(value: &Value) -> bool {
+    value.as_array().map(|a| a.iter().all(Value::is_string)).unwrap_or(false)
+}
+
+fn kind_name(kind: SettingKind) -> &'static str {
+    match kind {
+        SettingKind::String | SettingKind::Enum(_) => "a string",
+        SettingKind::StringList => "an array of strings",
+        SettingKind::StringMap => "an object with string or string array values",
+        SettingKind::Array => "an array",
+        SettingKind::Object => "an object",
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+pub fn get_path<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    key.split('.').try_fold(value, |node, segment| node.get(segment))
+}
+
+fn set_path(root: &mut Map<String, Value>, key: &str, value: Value) {
+    let mut segments: Vec<&str> = key.split('.').collect();
+    let last = segments.pop().unwrap_or(key);
+    let mut node = root;
+    for segment in segments {
+        let entry = node.entry(segment.to_string()).or_insert_with(|| Value::Object(Map::new()));
+        if !entry.is_object() {
+            *entry = Value::Object(Map::new());
+        }
+        node = entry.as_object_mut().expect("replaced by an object above");
+    }
+    node.insert(last.to_string(), value);
+}
+
+/// Remove `key`, then any parent objects it left empty
+fn remove_path(root: &mut Map<String, Value>, key: &str) {
+    match key.split_once('.') {
+        None => {
+            root.remove(key);
+        }
+        Some((head, rest)) => {
+            if let Some(Value::Object(child)) = root.get_mut(head) {
+                remove_path(child, rest);
+                if child.is_empty() {
+                    root.remove(head);
+                }
+            }
+        }
+    }
+}
+
+/// Every setting at its default value
+pub fn defaults() -> Map<String, Value> {
+    let mut root = Map::new();
+    for spec in SCHEMA {
+        set_path(&mut root, spec.key, serde_json::from_str(spec.default).unwrap_or(Value::Null));
+    }
+    root
+}
+
+/// Keep the valid settings of one layer, reporting the rest. Keys may be nested
+/// objects or dotted (`"ai.model": ...`); the result is always nested.
+pub fn validate_layer(layer: &Map<String, Value>, file: &str, text: Option<&str>) -> (Map<String, Value>, Vec<SettingsProblem>) {
+    let mut valid = Map::new();
+    let mut problems = Vec::new();
+    let mut leaves = Vec::new();
+    flatten("", layer, &mut leaves);
+    for (key, value) in leaves {
+        if key == "version" {
+            continue;
+        }
+        let problem = match spec(&key) {
+            Some(spec) => match check(spec, value) {
+                Ok(()) => {
+                    set_path(&mut valid, &key, value.clone());
+                    continue;
+                }
+                Err(message) => message,
+            },
+            None => "unknown setting".to_string(),
+        };
+        let position = text.and_then(|t| locate(t, &key));
+        problems.push(SettingsProblem {
+            file: file.to_string(),
+            line: position.map(|p| p.0),
+            column: position.map(|p| p.1),
+            key: Some(key),
+            message: problem,
+        });
+    }
+    (valid, problems)
+}
+
+/// Dotted paths of the values in `layer`, descending into objects until a known key
+fn flatten<'a>(prefix: &str, layer: &'a Map<String, Value>, out: &mut Vec<(String, &'a Value)>) {
+    for (name, value) in layer {
+        let key = if prefix.is_empty() { name.clone() } else { format!("{}.{}", prefix, name) };
+        match value {
+            Value::Object(child) if spec(&key).is_none() && is_group(&key) => flatten(&key, child, out),
+            _ => out.push((key, value)),
+        }
+    }
+}
+
+/// Whether some setting lives below `key`
+fn is_group(key: &str) -> bool {
+    SCHEMA.iter().any(|s| s.key.len() > key.len() && s.key.starts_with(key) && s.key.as_bytes()[key.len()] == b'.')
+}
+
+/// 1-based line and column of the name of `key` in a JSON text, following its
+/// segments in order. Good enough for hand-written files; `None` if not found.
+fn locate(text: &str, key: &str) -> Option<(usize, usize)> {
+    // A dotted key may also be written as one quoted name
+    let at = find_names(text, &[key]).or_else(|| find_names(text, &key.split('.').collect::<Vec<_>>()))?;
+    Some(line_column(text, at))
+}
+
+/// 1-based line and column of a byte offset in `text`
+pub fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+/// Byte offset of the last of `names`, each searched as an object key after the previous one
+fn find_names(text: &str, names: &[&str]) -> Option<usize> {
+    let mut from = 0;
+    let mut found = None;
+    for name in names {
+        let quoted = format!("\"{}\"", name);
+        let mut start = from;
+        let at = loop {
+            let at = text[start..].find(&quoted)? + start;
+            let after = at + quoted.len();
+            if text[after..].trim_start().starts_with(':') {
+                break at;
+            }
+            start = after;
+        };
+        from = at + quoted.len();
+        found = Some(at);
+    }
+    found
+}
+
+/// Deep-merge a validated layer into `base`. Groups like `ai` merge key by key;
+/// settings holding objects, like `shortcuts`, are replaced whole.
+pub fn merge(base: &mut Map<String, Value>, over: &Map<String, Value>) {
+    merge_at("", base, over);
+}
+
+fn merge_at(prefix: &str, base: &mut Map<String, Value>, over: &Map<String, Value>) {
+    for (name, value) in over {
+        let key = if prefix.is_empty() { name.clone() } else { format!("{}.{}", prefix, name) };
+        match (base.get_mut(name), value) {
+            (Some(Value::Object(existing)), Value::Object(child)) if is_group(&key) => merge_at(&key, existing, child),
+            _ => {
+                base.insert(name.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// SettingsStore is the user layer: a hand-editable JSON file holding only the
+/// settings that differ from the defaults.
+pub struct SettingsStore {
+    file: PathBuf,
+}
+
+impl SettingsStore {
+    pub fn new(file: PathBuf) -> Self {
+        Self { file }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn exists(&self) -> bool {
+        self.file.is_file()
+    }
+
+    fn read_raw(&self) -> Result<Map<String, Value>> {
+        match fs::read(&self.file) {
+            Ok(bytes) => match serde_json::from_slice(&bytes)? {
+                Value::Object(map) => Ok(map),
+                _ => Ok(Map::new()),
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Map::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn write_raw(&self, mut map: Map<String, Value>) -> Result<()> {
+        map.insert("version".to_string(), Value::from(SETTINGS_VERSION));
+        if let Some(dir) = self.file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut bytes = serde_json::to_vec_pretty(&Value::Object(map))?;
+        bytes.push(b'\n');
+        write_atomic(&self.file, &bytes)
+    }
+
+    /// The valid part of the user file. A file that is not valid JSON is ignored
+    /// as a whole and reported with the position of the syntax error.
+    pub fn load(&self) -> Result<(Map<String, Value>, Vec<SettingsProblem>)> {
+        let file = self.file.to_string_lossy().to_string();
+        let text = match fs::read_to_string(&self.file) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok((Map::new(), Vec::new())),
+            Err(e) => return Err(e.into()),
+        };
+        let layer = match serde_json::from_str::<Value>(&text) {
+            Ok(Value::Object(map)) => map,
+            Ok(_) => {
+                let problem = SettingsProblem {
+                    file,
+                    key: None,
+                    line: Some(1),
+                    column: Some(1),
+                    message: "expected a JSON object".to_string(),
+                };
+                return Ok((Map::new(), vec![problem]));
+            }
+            Err(e) => {
+                let problem = SettingsProblem {
+                    file,
+                    key: None,
+                    line: Some(e.line()),
+                    column: Some(e.column()),
+                    message: e.to_string(),
+                };
+                return Ok((Map::new(), vec![problem]));
+            }
+        };
+        Ok(validate_layer(&layer, &file, Some(&text)))
+    }
+
+    /// Store one setting; `AppError::InvalidSetting` if the schema rejects it.
+    /// The rest of the file, including entries this version does not know, is kept.
+    pub fn set(&self, key: &str, value: Value) -> Result<()> {
+        let spec = spec(key).ok_or_else(|| AppError::InvalidSetting {
+            key: key.to_string(),
+            reason: "unknown setting".to_string(),
+        })?;
+        check(spec, &value).map_err(|reason| AppError::InvalidSetting { key: key.to_string(), reason })?;
+        let mut map = self.read_raw()?;
+        // A dotted spelling written by hand would shadow the nested one
+        map.remove(key);
+        set_path(&mut map, key, value);
+        self.write_raw(map)
+    }
+
+    /// Back to the default for `key`, or for everything when `None`
+    pub fn reset(&self, key: Option<&str>) -> Result<()> {
+        match key {
+            Some(key) => {
+                let mut map = self.read_raw()?;
+                map.remove(key);
+                remove_path(&mut map, key);
+                self.write_raw(map)
+            }
+            None => self.write_raw(Map::new()),
+        }
+    }
+
+    /// Seed a missing user file from settings kept elsewhere before; invalid entries
+    /// are skipped and returned. Does nothing once the file exists.
+    pub fn import(&self, values: &Map<String, Value>) -> Result<Vec<SettingsProblem>> {
+        if self.exists() {
+            return Ok(Vec::new());
+        }
+        let (valid, problems) = validate_layer(values, "import", None);
+        self.write_raw(valid)?;
+        Ok(problems)
+    }
+}
+
+/// Layer defaults, the user file and the workspace overrides
+pub fn snapshot(store: &SettingsStore, workspace: Option<(&str, &Map<String, Value>)>) -> Result<SettingsSnapshot> {
+    let mut settings = defaults();
+    let (user, mut problems) = store.load()?;
+    merge(&mut settings, &user);
+    if let Some((file, overrides)) = workspace {
+        let (valid, workspace_problems) = validate_layer(overrides, file, None);
+        merge(&mut settings, &valid);
+        problems.extend(workspace_problems);
+    }
+    Ok(SettingsSnapshot {
+        settings: Value::Object(settings),
+        codex: Value::Object(Map::new()),
+        problems,
+        changed: Vec::new(),
+    })
+}
+
+/// Dotted paths of the values that differ between `old` and `new`. Objects are
+/// compared key by key; anything else, arrays included, is reported as a whole.
+pub fn changed_keys(old: &Value, new: &Value) -> Vec<String> {
+    let mut out = Vec::new();
+    diff_at("", old, new, &mut out);
+    out
+}
+
+fn diff_at(prefix: &str, old: &Value, new: &Value, out: &mut Vec<String>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let removed = old.keys().filter(|k| !new.contains_key(*k));
+            for name in new.keys().chain(removed) {
+                let key = if prefix.is_empty() { name.clone() } else { format!("{}.{}", prefix, name) };
+                diff_at(&key, old.get(name).unwrap_or(&Value::Null), new.get(name).unwrap_or(&Value::Null), out);
+            }
+        }
+        (old, new) if old != new => out.push(prefix.to_string()),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_store(name: &str) -> (PathBuf, SettingsStore) {
+        let dir = std::env::temp_dir().join(format!("editrion-settings-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let store = SettingsStore::new(dir.join("settings.json"));
+        (dir, store)
+    }
+
+    fn overrides(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_layers_and_set_reset() {
+        let (dir, store) = temp_store("layers");
+        let defaults = snapshot(&store, None).unwrap().settings;
+        assert_eq!(defaults["theme"], "dark");
+        assert_eq!(defaults["ai"]["model"], Value::Null);
+
+        store.set("ai.model", json!("o3")).unwrap();
+        store.set("shortcuts", json!({ "save": "Mod+S" })).unwrap();
+        let workspace = overrides(json!({ "ai.effort": "high", "shortcuts": { "find": "Mod+F" } }));
+        let layered = snapshot(&store, Some(("ws", &workspace))).unwrap().settings;
+        assert_eq!(layered["ai"]["model"], "o3");
+        assert_eq!(layered["ai"]["effort"], "high");
+        assert_eq!(layered["shortcuts"], json!({ "find": "Mod+F" }));
+
+        assert!(matches!(store.set("locale", json!("xx")), Err(AppError::InvalidSetting { .. })));
+        assert!(matches!(store.set("nope", json!(1)), Err(AppError::InvalidSetting { .. })));
+
+        store.reset(Some("ai.model")).unwrap();
+        let raw: Value = serde_json::from_slice(&fs::read(store.path()).unwrap()).unwrap();
+        assert_eq!(raw, json!({ "version": SETTINGS_VERSION, "shortcuts": { "save": "Mod+S" } }));
+        store.reset(None).unwrap();
+        assert_eq!(snapshot(&store, None).unwrap().settings, defaults);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_problems_point_at_the_file() {
+        let (dir, store) = temp_store("problems");
+        fs::write(store.path(), "{\n  \"theme\": \"light\",\n  \"ai\": {\n    \"effort\": \"max\"\n  },\n  \"colour\": 1\n}\n").unwrap();
+        let snapshot = snapshot(&store, None).unwrap();
+        assert_eq!(snapshot.settings["theme"], "light");
+        let found: Vec<_> = snapshot
+            .problems
+            .iter()
+            .map(|p| (p.key.as_deref().unwrap(), p.line.unwrap(), p.column.unwrap()))
+            .collect();
+        assert_eq!(found, [("ai.effort", 4, 5), ("colour", 6, 3)]);
+
+        fs::write(store.path(), "{\n  \"theme\": \"light\",\n}").unwrap();
+        let problems = super::snapshot(&store, None).unwrap().problems;
+        assert_eq!((problems[0].key.as_ref(), problems[0].line), (None, Some(3)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_changed_keys() {
+        let old = json!({ "theme": "dark", "ai": { "effort": "low", "templates": [1] }, "gone": 1 });
+        let new = json!({ "theme": "dark", "ai": { "effort": "high", "templates": [1, 2] }, "added": true });
+        let mut changed = changed_keys(&old, &new);
+        changed.sort();
+        assert_eq!(changed, ["added", "ai.effort", "ai.templates", "gone"]);
+        assert!(changed_keys(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_import_only_seeds_missing_file() {
+        let (dir, store) = temp_store("import");
+        let legacy = overrides(json!({ "locale": "uk", "ai": { "effort": "low", "model": 5 } }));
+        let skipped = store.import(&legacy).unwrap();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(snapshot(&store, None).unwrap().settings["locale"], "uk");
+        assert!(store.import(&overrides(json!({ "locale": "de" }))).unwrap().is_empty());
+        assert_eq!(snapshot(&store, None).unwrap().settings["locale"], "uk");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,250 @@
+use serde_json::Value as Json;
+use toml_edit::{DocumentMut, InlineTable, Item, Key, Table, TableLike, Value};
+
+use crate::core::codex_config::item_to_json;
+use crate::core::settings::line_column;
+use crate::error::{AppError, Result};
+
+/// A TOML file edited in place. Keys are paths such as `model` or
+/// `profiles.work.model` (quote segments containing dots: `profiles."a.b".model`).
+/// Comments, blank lines, key order and untouched entries survive every edit.
+pub struct TomlDocument {
+    doc: DocumentMut,
+}
+
+impl TomlDocument {
+    /// `path` only labels the error when `text` is not valid TOML
+    pub fn parse(text: &str, path: &str) -> Result<Self> {
+        let doc = text.parse::<DocumentMut>().map_err(|e| {
+            let (line, column) = e.span().map(|span| line_column(text, span.start)).unwrap_or((1, 1));
+            AppError::InvalidToml { path: path.to_string(), line, column, message: e.message().to_string() }
+        })?;
+        Ok(Self { doc })
+    }
+
+    /// The whole document as a JSON object
+    pub fn to_json(&self) -> Json {
+        item_to_json(self.doc.as_item())
+    }
+
+    /// Value at `key` as JSON; tables come back as objects
+    pub fn get(&self, key: &str) -> Result<Option<Json>> {
+        let path = parse_key(key)?;
+        let (last, parents) = path.split_last().expect("parse_key never returns an empty path");
+        let mut table: &dyn TableLike = self.doc.as_table();
+        for segment in parents {
+            match table.get(segment.get()).and_then(as_table) {
+                Some(child) => table = child,
+                None => return Ok(None),
+            }
+        }
+        Ok(table.get(last.get()).map(item_to_json))
+    }
+
+    /// Set `key` to `value`, creating the tables on the way. Missing tables get a
+    /// `[section]` header after the existing ones, or stay inline / dotted when the
+    /// parent is. A replaced value keeps its trailing comment.
+    pub fn set(&mut self, key: &str, mut value: Value) -> Result<()> {
+        let path = parse_key(key)?;
+        let (last, parents) = path.split_last().expect("parse_key never returns an empty path");
+        let mut table: &mut dyn TableLike = self.doc.as_table_mut();
+        let mut inline = false;
+        for (depth, segment) in parents.iter().enumerate() {
+            if !table.contains_key(segment.get()) {
+                let child = new_table(inline, table.is_dotted());
+                append(table, inline, segment.get(), child);
+            }
+            let item = table.get_mut(segment.get()).expect("inserted above");
+            inline = matches!(item, Item::Value(Value::InlineTable(_)));
+            table = as_table_mut(item).ok_or_else(|| not_a_table(&path[..=depth]))?;
+        }
+
+        value.decor_mut().clear();
+        match table.get_mut(last.get()) {
+            Some(Item::Value(old)) => {
+                *value.decor_mut() = old.decor().clone();
+                *old = value;
+            }
+            Some(item) => *item = Item::Value(value),
+            None => append(table, inline, last.get(), Item::Value(value)),
+        }
+        Ok(())
+    }
+
+    /// Remove `key`; returns whether it was there
+    pub fn remove(&mut self, key: &str) -> Result<bool> {
+        let path = parse_key(key)?;
+        let (last, parents) = path.split_last().expect("parse_key never returns an empty path");
+        let mut table: &mut dyn TableLike = self.doc.as_table_mut();
+        for segment in parents {
+            match table.get_mut(segment.get()).and_then(as_table_mut) {
+                Some(child) => table = child,
+                None => return Ok(false),
+            }
+        }
+        Ok(table.remove(last.get()).is_some())
+    }
+}
+
+impl std::fmt::Display for TomlDocument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.doc.fmt(f)
+    }
+}
+
+fn parse_key(key: &str) -> Result<Vec<Key>> {
+    match Key::parse(key) {
+        Ok(path) if !path.is_empty() => Ok(path),
+        Ok(_) => Err(AppError::Config("Empty config key".to_string())),
+        Err(e) => Err(AppError::Config(format!("Invalid config key {:?}: {}", key, e.message()))),
+    }
+}
+
+fn as_table(item: &Item) -> Option<&dyn TableLike> {
+    match item {
+        Item::Table(table) => Some(table),
+        Item::Value(Value::InlineTable(table)) => Some(table),
+        _ => None,
+    }
+}
+
+fn as_table_mut(item: &mut Item) -> Option<&mut dyn TableLike> {
+    match item {
+        Item::Table(table) => Some(table),
+        Item::Value(Value::InlineTable(table)) => Some(table),
+        _ => None,
+    }
+}
+
+/// An empty table that fits its parent. Implicit tables only get a header once
+/// they hold values, so `a.b.c = 1` writes `[a.b]` and no `[a]`.
+fn new_table(inline: bool, dotted: bool) -> Item {
+    if inline {
+        return Item::Value(Value::InlineTable(InlineTable::new()));
+    }
+    let mut table = Table::new();
+    if dotted {
+        table.set_dotted(true);
+    } else {
+        table.set_implicit(true);
+    }
+    Item::Table(table)
+}
+
+/// Add a new entry at the end of `table`. In `{ a = 1 }` the space before `}`
+/// belongs to the last value, so it moves to the appended one.
+fn append(table: &mut dyn TableLike, inline: bool, key: &str, mut item: Item) {
+    if let (true, Item::Value(value)) = (inline, &mut item) {
+        if let Some((_, Item::Value(last))) = table.iter_mut().last() {
+            if let Some(suffix) = last.decor().suffix().cloned() {
+                last.decor_mut().set_suffix("");
+                value.decor_mut().set_suffix(suffix);
+            }
+        }
+    }
+    table.insert(key, item);
+}
+
+fn not_a_table(path: &[Key]) -> AppError {
+    let key: Vec<&str> = path.iter().map(Key::get).collect();
+    AppError::Config(format!("{} is not a table", key.join(".")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn edit(text: &str, key: &str, value: Value) -> String {
+        let mut doc = TomlDocument::parse(text, "config.toml").unwrap();
+        doc.set(key, value).unwrap();
+        doc.to_string()
+    }
+
+    #[test]
+    fn test_set_matches_whole_keys_only() {
+        let text = "model_provider = \"openai\"\nmodel = \"o3\"\n";
+        assert_eq!(edit(text, "model", "gpt-5".into()), "model_provider = \"openai\"\nmodel = \"gpt-5\"\n");
+        assert_eq!(edit(text, "model_provider", "oss".into()), "model_provider = \"oss\"\nmodel = \"o3\"\n");
+    }
+
+    #[test]
+    fn test_set_respects_sections() {
+        let text = "model = \"o3\"\n\n[profiles.work]\nmodel = \"o4-mini\"\n";
+        assert_eq!(
+            edit(text, "profiles.work.model", "gpt-5".into()),
+            "model = \"o3\"\n\n[profiles.work]\nmodel = \"gpt-5\"\n"
+        );
+        // A new root key goes with the other root keys, not under the last header
+        let text = "[profiles.work]\nmodel = \"o4-mini\"\n";
+        assert_eq!(edit(text, "model", "o3".into()), "model = \"o3\"\n[profiles.work]\nmodel = \"o4-mini\"\n");
+    }
+
+    #[test]
+    fn test_set_creates_tables() {
+        let text = "model = \"o3\"\n\n[tui]\nnotifications = true\n";
+        assert_eq!(
+            edit(text, "profiles.work.model", "gpt-5".into()),
+            "model = \"o3\"\n\n[tui]\nnotifications = true\n\n[profiles.work]\nmodel = \"gpt-5\"\n"
+        );
+        let text = "[profiles]\nwork.model = \"o3\"\n";
+        assert_eq!(
+            edit(text, "profiles.work.effort", "high".into()),
+            "[profiles]\nwork.model = \"o3\"\nwork.effort = \"high\"\n"
+        );
+        let mut doc = TomlDocument::parse("model = \"o3\"\n", "config.toml").unwrap();
+        let err = doc.set("model.name", "x".into()).unwrap_err();
+        assert_eq!(err.kind(), "config");
+    }
+
+    #[test]
+    fn test_set_keeps_comments_and_layout() {
+        let text = "# Codex\nmodel      = \"o3\"   # default model\n\n[tui]  # ui\nnotifications = true\n";
+        assert_eq!(
+            edit(text, "model", "gpt-5".into()),
+            "# Codex\nmodel      = \"gpt-5\"   # default model\n\n[tui]  # ui\nnotifications = true\n"
+        );
+    }
+
+    #[test]
+    fn test_arrays_and_inline_tables() {
+        let text = "shell_environment_policy = { inherit = \"core\" }\n";
+        let args: Value = "[\"-y\", \"server\"]".parse().unwrap();
+        assert_eq!(
+            edit(text, "mcp_servers.docs.args", args),
+            "shell_environment_policy = { inherit = \"core\" }\n\n[mcp_servers.docs]\nargs = [\"-y\", \"server\"]\n"
+        );
+        assert_eq!(
+            edit(text, "shell_environment_policy.set.PATH", "/bin".into()),
+            "shell_environment_policy = { inherit = \"core\", set = { PATH = \"/bin\" } }\n"
+        );
+        let doc = TomlDocument::parse(text, "config.toml").unwrap();
+        assert_eq!(doc.get("shell_environment_policy.inherit").unwrap(), Some(json!("core")));
+        assert_eq!(doc.get("shell_environment_policy").unwrap(), Some(json!({ "inherit": "core" })));
+    }
+
+    #[test]
+    fn test_get_and_remove() {
+        let text = "model = \"o3\"\nmodel_provider = \"openai\"\n\n[profiles.\"a.b\"]\nmodel = \"gpt-5\" # fast\n";
+        let mut doc = TomlDocument::parse(text, "config.toml").unwrap();
+        assert_eq!(doc.get("profiles.\"a.b\".model").unwrap(), Some(json!("gpt-5")));
+        assert_eq!(doc.get("profiles.a.model").unwrap(), None);
+        assert_eq!(doc.get("model.name").unwrap(), None);
+        assert_eq!(doc.to_json()["profiles"], json!({ "a.b": { "model": "gpt-5" } }));
+
+        assert!(doc.remove("model").unwrap());
+        assert!(!doc.remove("model").unwrap());
+        assert!(!doc.remove("profiles.missing.model").unwrap());
+        assert_eq!(doc.to_string(), "model_provider = \"openai\"\n\n[profiles.\"a.b\"]\nmodel = \"gpt-5\" # fast\n");
+        assert!(TomlDocument::parse(text, "config.toml").unwrap().get("profiles..model").is_err());
+    }
+
+    #[test]
+    fn test_parse_error_position() {
+        let err = TomlDocument::parse("model = \"o3\"\n[tui\n", "config.toml").err().unwrap();
+        match err {
+            AppError::InvalidToml { line, .. } => assert_eq!(line, 2),
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+}
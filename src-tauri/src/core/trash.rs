@@ -0,0 +1,459 @@
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use percent_encoding::{percent_decode, percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::Serialize;
+
+use crate::core::file_ops::{self, path_error, OverwritePolicy};
+use crate::error::{AppError, Result};
+
+/// Bytes left unescaped in the `Path=` key, as in a URL path
+const PATH_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'/').remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+
+const INFO_EXT: &str = "trashinfo";
+
+/// A trashed file or directory that can be restored
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashItem {
+    /// Name of the entry under `Trash/files`, unique within the trash
+    pub id: String,
+    pub original_path: String,
+    /// Local time as written by the deleting program, `YYYY-MM-DDThh:mm:ss`
+    pub deleted_at: String,
+    pub is_dir: bool,
+}
+
+/// What happened to a deleted path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Removal {
+    /// Entry to restore the path from; `None` when it was not trashed
+    pub trash_item: Option<TrashItem>,
+    /// The path is gone for good: that was asked for, or there is no trash to use
+    pub permanent: bool,
+}
+
+/// The user's home trash as described by the freedesktop.org Trash specification.
+///
+/// Files on other file systems are copied into the home trash and then deleted rather
+/// than going to a per-volume `.Trash-$uid` directory.
+pub struct Trash {
+    root: PathBuf,
+}
+
+impl Trash {
+    /// Whether `for_user` can find a trash on this platform
+    pub const SUPPORTED: bool = cfg!(all(unix, not(target_os = "macos")));
+
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// The trash deletions go to, or `None` when they are permanent because that was
+    /// asked for or because this platform has no trash support
+    pub fn for_deletion(permanent: bool) -> Result<Option<Self>> {
+        if permanent || !Self::SUPPORTED {
+            return Ok(None);
+        }
+        Self::for_user().map(Some)
+    }
+
+    /// `$XDG_DATA_HOME/Trash`, falling back to `~/.local/share/Trash`
+    #[cfg(all(unix, not(target_os = "macos")))]
+    pub fn for_user() -> Result<Self> {
+        home_trash_dir(std::env::var_os("XDG_DATA_HOME"), std::env::var_os("HOME"))
+            .map(Self::new)
+            .ok_or_else(|| AppError::TrashUnavailable("cannot determine the home directory".to_string()))
+    }
+
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    pub fn for_user() -> Result<Self> {
+        Err(AppError::TrashUnavailable("not supported on this platform".to_string()))
+    }
+
+    fn files_dir(&self) -> PathBuf {
+        self.root.join("files")
+    }
+
+    fn info_dir(&self) -> PathBuf {
+        self.root.join("info")
+    }
+
+    fn info_path(&self, id: &str) -> PathBuf {
+        self.info_dir().join(format!("{}.{}", id, INFO_EXT))
+    }
+
+    /// Move `path` into the trash
+    pub fn trash(&self, path: &Path) -> Result<TrashItem> {
+        let meta = fs::symlink_metadata(path).map_err(|e| path_error(e, path))?;
+        let original = std::path::absolute(path)?;
+        let (files, info) = (self.files_dir(), self.info_dir());
+        create_private_dir(&files).map_err(|e| path_error(e, &files))?;
+        create_private_dir(&info).map_err(|e| path_error(e, &info))?;
+
+        let deleted_at = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+        let contents = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            percent_encode(&path_bytes(&original), PATH_SET),
+            deleted_at
+        );
+        let id = self.reserve_id(&original, &contents)?;
+        if let Err(e) = file_ops::move_path(path, &files.join(&id), OverwritePolicy::Fail) {
+            let _ = fs::remove_file(self.info_path(&id));
+            return Err(e);
+        }
+        Ok(TrashItem {
+            id,
+            original_path: original.to_string_lossy().to_string(),
+            deleted_at,
+            is_dir: meta.is_dir(),
+        })
+    }
+
+    /// Claim a free name by creating its info file, which the spec makes the lock
+    fn reserve_id(&self, original: &Path, contents: &str) -> Result<String> {
+        let base = original
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "unnamed".to_string());
+        let mut n = 1;
+        loop {
+            let id = if n == 1 { base.clone() } else { format!("{}.{}", base, n) };
+            n += 1;
+            let info_path = self.info_path(&id);
+            let mut file = match OpenOptions::new().write(true).create_new(true).open(&info_path) {
+                Ok(f) => f,
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(path_error(e, &info_path)),
+            };
+            // An orphaned entry in `files` would be overwritten by this name
+            if fs::symlink_metadata(self.files_dir().join(&id)).is_ok() {
+                drop(file);
+                let _ = fs::remove_file(&info_path);
+                continue;
+            }
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()?;
+            return Ok(id);
+        }
+    }
+
+    /// Trashed items, most recently deleted first
+    pub fn list(&self) -> Result<Vec<TrashItem>> {
+        let entries = match fs::read_dir(self.info_dir()) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut items = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(INFO_EXT) {
+                continue;
+            }
+            let Some(id) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else { continue };
+            // Skip half-written entries and info files whose data is gone
+            if let Ok((_, item)) = self.read_item(&id) {
+                items.push(item);
+            }
+        }
+        items.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at).then_with(|| a.id.cmp(&b.id)));
+        Ok(items)
+    }
+
+    /// Original path with its exact bytes, plus the item as shown to the user
+    fn read_item(&self, id: &str) -> Result<(PathBuf, TrashItem)> {
+        let not_found = || AppError::TrashItemNotFound(id.to_string());
+        // Ids come from the frontend and must name an entry inside the trash.
+        // Trashed dotfiles keep their leading dot, so only `.` and `..` are refused.
+        if id.is_empty() || id.contains(['/', '\\']) || id == "." || id == ".." {
+            return Err(not_found());
+        }
+        let text = fs::read_to_string(self.info_path(id)).map_err(|_| not_found())?;
+        let meta = fs::symlink_metadata(self.files_dir().join(id)).map_err(|_| not_found())?;
+        let (original, deleted_at) = parse_info(&text).ok_or_else(not_found)?;
+        let item = TrashItem {
+            id: id.to_string(),
+            original_path: original.to_string_lossy().to_string(),
+            deleted_at,
+            is_dir: meta.is_dir(),
+        };
+        Ok((original, item))
+    }
+
+    /// Put an item back where it was deleted from and return that path
+    pub fn restore(&self, id: &str, policy: OverwritePolicy) -> Result<PathBuf> {
+        let (original, _) = self.read_item(id)?;
+        if let Some(parent) = original.parent() {
+            file_ops::create_dir(parent)?;
+        }
+        file_ops::move_path(&self.files_dir().join(id), &original, policy)?;
+        fs::remove_file(self.info_path(id))?;
+        Ok(original)
+    }
+
+    /// Permanently delete one item from the trash
+    pub fn purge(&self, id: &str) -> Result<()> {
+        self.read_item(id)?;
+        let data = self.files_dir().join(id);
+        if fs::symlink_metadata(&data)?.is_dir() {
+            fs::remove_dir_all(&data)?;
+        } else {
+            fs::remove_file(&data)?;
+        }
+        fs::remove_file(self.info_path(id))?;
+        Ok(())
+    }
+}
+
+/// Move `path` to `trash`, or delete it for good without one. A missing path is not an error.
+pub fn remove(path: &Path, trash: Option<&Trash>) -> Result<Removal> {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return Ok(Removal { trash_item: None, permanent: false });
+    };
+    if let Some(trash) = trash {
+        return Ok(Removal { trash_item: Some(trash.trash(path)?), permanent: false });
+    }
+    let result = if meta.is_dir() { fs::remove_dir_all(path) } else { fs::remove_file(path) };
+    match result {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(path_error(e, path)),
+    }
+    Ok(Removal { trash_item: None, permanent: true })
+}
+
+#[cfg(any(test, all(unix, not(target_os = "macos"))))]
+fn home_trash_dir(xdg_data_home: Option<OsString>, home: Option<OsString>) -> Option<PathBuf> {
+    let data_home = xdg_data_home
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| home.map(|h| PathBuf::from(h).join(".local/share")))?;
+    Some(data_home.join("Trash"))
+}
+
+fn parse_info(text: &str) -> Option<(PathBuf, String)> {
+    let mut in_section = false;
+    let (mut path, mut date) = (None, None);
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_section = line == "[Trash Info]";
+        } else if in_section {
+            if let Some(v) = line.strip_prefix("Path=") {
+                path = Some(path_from_bytes(percent_decode(v.as_bytes()).collect()));
+            } else if let Some(v) = line.strip_prefix("DeletionDate=") {
+                date = Some(v.to_string());
+            }
+        }
+    }
+    Some((path?, date.unwrap_or_default()))
+}
+
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)
+}
+
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().as_bytes().to_vec()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    PathBuf::from(OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).to_string())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    /// A fake home directory with its own trash
+    fn temp_home(name: &str) -> (PathBuf, Trash) {
+        let home = std::env::temp_dir().join(format!("editrion-trash-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        fs::create_dir_all(&home).unwrap();
+        let root = home_trash_dir(None, Some(home.clone().into_os_string())).unwrap();
+        (home, Trash::new(root))
+    }
+
+    #[test]
+    fn test_home_trash_dir() {
+        assert_eq!(
+            home_trash_dir(None, Some("/home/u".into())),
+            Some(PathBuf::from("/home/u/.local/share/Trash"))
+        );
+        assert_eq!(
+            home_trash_dir(Some("/data".into()), Some("/home/u".into())),
+            Some(PathBuf::from("/data/Trash"))
+        );
+        // Relative XDG paths are invalid and ignored
+        assert_eq!(
+            home_trash_dir(Some("data".into()), Some("/home/u".into())),
+            Some(PathBuf::from("/home/u/.local/share/Trash"))
+        );
+    }
+
+    #[test]
+    fn test_trash_writes_info_file() {
+        let (home, trash) = temp_home("info");
+        let file = home.join("my notes.txt");
+        fs::write(&file, "keep me").unwrap();
+
+        let item = trash.trash(&file).unwrap();
+        assert!(!file.exists());
+        assert_eq!(item.id, "my notes.txt");
+        let root = home.join(".local/share/Trash");
+        assert_eq!(fs::read_to_string(root.join("files/my notes.txt")).unwrap(), "keep me");
+
+        let info = fs::read_to_string(root.join("info/my notes.txt.trashinfo")).unwrap();
+        let mut lines = info.lines();
+        assert_eq!(lines.next(), Some("[Trash Info]"));
+        assert_eq!(lines.next().unwrap(), format!("Path={}/my%20notes.txt", home.display()));
+        assert!(lines.next().unwrap().starts_with("DeletionDate="));
+        fs::remove_dir_all(&home).unwrap();
+    }
+
+    #[test]
+    fn test_same_name_gets_unique_id() {
+        let (home, trash) = temp_home("unique");
+        let a = home.join("a");
+        fs::create_dir_all(a.join("x")).unwrap();
+        fs::write(a.join("x/f.txt"), "1").unwrap();
+        fs::write(home.join("f.txt"), "2").unwrap();
+
+        let first = trash.trash(&a.join("x/f.txt")).unwrap();
+        let second = trash.trash(&home.join("f.txt")).unwrap();
+        let folder = trash.trash(&a).unwrap();
+        assert_eq!((first.id.as_str(), second.id.as_str()), ("f.txt", "f.txt.2"));
+        assert!(folder.is_dir);
+        assert_eq!(trash.list().unwrap().len(), 3);
+        fs::remove_dir_all(&home).unwrap();
+    }
+
+    #[test]
+    fn test_restore_round_trip() {
+        let (home, trash) = temp_home("restore");
+        let dir = home.join("project/src");
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("main.rs");
+        fs::write(&file, "fn main() {}").unwrap();
+
+        let item = trash.trash(&file).unwrap();
+        fs::remove_dir_all(home.join("project")).unwrap();
+
+        let restored = trash.restore(&item.id, OverwritePolicy::Fail).unwrap();
+        assert_eq!(restored, file);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "fn main() {}");
+        assert!(trash.list().unwrap().is_empty());
+        assert!(matches!(
+            trash.restore(&item.id, OverwritePolicy::Fail),
+            Err(AppError::TrashItemNotFound(_))
+        ));
+        fs::remove_dir_all(&home).unwrap();
+    }
+
+    #[test]
+    fn test_restore_refuses_to_clobber() {
+        let (home, trash) = temp_home("clobber");
+        let file = home.join("a.txt");
+        fs::write(&file, "old").unwrap();
+        let item = trash.trash(&file).unwrap();
+        fs::write(&file, "new").unwrap();
+
+        assert!(matches!(
+            trash.restore(&item.id, OverwritePolicy::Fail),
+            Err(AppError::TargetExists(_))
+        ));
+        assert_eq!(trash.list().unwrap().len(), 1);
+        trash.restore(&item.id, OverwritePolicy::Overwrite).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "old");
+        fs::remove_dir_all(&home).unwrap();
+    }
+
+    #[test]
+    fn test_remove_with_and_without_trash() {
+        let (home, trash) = temp_home("remove");
+        let (file, dir) = (home.join("a.txt"), home.join("folder"));
+        fs::write(&file, "x").unwrap();
+        fs::create_dir_all(dir.join("sub")).unwrap();
+
+        let trashed = remove(&file, Some(&trash)).unwrap();
+        assert!(!trashed.permanent);
+        assert_eq!(trashed.trash_item.unwrap().original_path, file.to_string_lossy());
+        let deleted = remove(&dir, None).unwrap();
+        assert_eq!(deleted, Removal { trash_item: None, permanent: true });
+        assert!(!dir.exists());
+        assert_eq!(remove(&dir, None).unwrap(), Removal { trash_item: None, permanent: false });
+        fs::remove_dir_all(&home).unwrap();
+    }
+
+    #[test]
+    fn test_list_skips_orphans_and_purge() {
+        let (home, trash) = temp_home("purge");
+        let file = home.join("gone.txt");
+        fs::write(&file, "x").unwrap();
+        let item = trash.trash(&file).unwrap();
+        fs::write(trash.info_path("orphan"), "[Trash Info]\nPath=/nowhere\n").unwrap();
+
+        let listed = trash.list().unwrap();
+        assert_eq!(listed, vec![item.clone()]);
+        trash.purge(&item.id).unwrap();
+        assert!(trash.list().unwrap().is_empty());
+        assert!(!trash.files_dir().join(&item.id).exists());
+        fs::remove_dir_all(&home).unwrap();
+    }
+
+    #[test]
+    fn test_ids_stay_inside_the_trash() {
+        let (home, trash) = temp_home("escape");
+        let dotfile = home.join(".env");
+        fs::write(&dotfile, "SECRET=1").unwrap();
+        let item = trash.trash(&dotfile).unwrap();
+        // A look-alike entry outside `files/` and `info/`
+        fs::write(home.join(".local/share/victim.trashinfo"), "[Trash Info]\nPath=/tmp/x\n").unwrap();
+        fs::write(home.join(".local/share/victim"), "x").unwrap();
+
+        for id in ["../../victim", "..", ".", "", "a\\b"] {
+            assert!(matches!(trash.purge(id), Err(AppError::TrashItemNotFound(_))), "{:?}", id);
+            assert!(matches!(
+                trash.restore(id, OverwritePolicy::Overwrite),
+                Err(AppError::TrashItemNotFound(_))
+            ));
+        }
+        assert!(home.join(".local/share/victim").exists());
+        assert_eq!(trash.restore(&item.id, OverwritePolicy::Fail).unwrap(), dotfile);
+        fs::remove_dir_all(&home).unwrap();
+    }
+
+    #[test]
+    fn test_parse_info_decodes_path() {
+        let (path, date) =
+            parse_info("[Trash Info]\nPath=/tmp/%D0%B6%20x\nDeletionDate=2024-05-01T10:20:30\n").unwrap();
+        assert_eq!(path, PathBuf::from("/tmp/ж x"));
+        assert_eq!(date, "2024-05-01T10:20:30");
+        assert!(parse_info("[Other]\nPath=/x\n").is_none());
+    }
+}
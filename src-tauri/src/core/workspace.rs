@@ -0,0 +1,309 @@
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::atomic_write::write_atomic;
+use crate::core::project_search::SearchQuery;
+use crate::core::session::Session;
+use crate::error::{AppError, Result};
+
+pub const WORKSPACE_EXTENSION: &str = "editrion-workspace";
+
+/// Bump when the file layout changes
+pub const WORKSPACE_VERSION: u32 = 1;
+
+/// How many workspaces the recent list remembers
+const MAX_RECENT: usize = 20;
+
+const RECENT_NAME: &str = "recent-workspaces.json";
+
+/// Contents of a `.editrion-workspace` file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Workspace {
+    pub version: u32,
+    /// Absolute once loaded; saved relative to the workspace file when inside its folder
+    pub folders: Vec<WorkspaceFolder>,
+    /// Overrides of the user settings while this workspace is open
+    pub settings: serde_json::Map<String, serde_json::Value>,
+    /// Globs left out of project-wide search and replace (`SearchQuery::exclude`)
+    pub exclude: Vec<String>,
+    pub ai: AiDefaults,
+    /// Tabs and buffers as they were when the workspace was last left
+    pub session: Option<Session>,
+}
+
+impl Workspace {
+    /// Leave this workspace's excluded globs out of `query` as well
+    pub fn apply_exclude(&self, query: &mut SearchQuery) {
+        for glob in &self.exclude {
+            if !query.exclude.contains(glob) {
+                query.exclude.push(glob.clone());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WorkspaceFolder {
+    pub path: String,
+    /// Label shown instead of the folder name
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AiDefaults {
+    pub model: Option<String>,
+    pub effort: Option<String>,
+    /// Working directory for AI runs; relative paths resolve like folders
+    pub cwd: Option<String>,
+}
+
+/// A workspace together with the file it was read from
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenedWorkspace {
+    pub path: String,
+    pub name: String,
+    pub workspace: Workspace,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentWorkspace {
+    pub path: String,
+    pub name: String,
+    /// Milliseconds since the Unix epoch
+    pub opened_at: u64,
+}
+
+/// Display name of a workspace file: its name without the extension
+pub fn workspace_name(path: &Path) -> String {
+    path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// `path` with the workspace extension added unless it already has it
+pub fn with_extension(path: &Path) -> PathBuf {
+    if path.extension().map(|e| e == WORKSPACE_EXTENSION).unwrap_or(false) {
+        path.to_path_buf()
+    } else {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".");
+        name.push(WORKSPACE_EXTENSION);
+        PathBuf::from(name)
+    }
+}
+
+/// Read a workspace file, resolving its relative paths against the file's folder
+pub fn load(path: &Path) -> Result<OpenedWorkspace> {
+    let path = std::path::absolute(path)?;
+    let bytes = fs::read(&path).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => AppError::NotFound(path.to_string_lossy().to_string()),
+        _ => e.into(),
+    })?;
+    let mut workspace: Workspace = serde_json::from_slice(&bytes)
+        .map_err(|e| AppError::Config(format!("invalid workspace file {}: {}", path.display(), e)))?;
+    if workspace.version > WORKSPACE_VERSION {
+        return Err(AppError::Config(format!(
+            "workspace file version {} is newer than supported version {}",
+            workspace.version, WORKSPACE_VERSION
+        )));
+    }
+    workspace.version = WORKSPACE_VERSION;
+
+    let base = path.parent().unwrap_or(Path::new("/"));
+    for folder in &mut workspace.folders {
+        folder.path = resolve(base, &folder.path);
+    }
+    if let Some(cwd) = workspace.ai.cwd.as_mut() {
+        *cwd = resolve(base, cwd);
+    }
+    Ok(OpenedWorkspace { name: workspace_name(&path), path: path.to_string_lossy().to_string(), workspace })
+}
+
+/// Write `workspace` to `path` atomically. Folders inside the workspace file's folder
+/// are stored relative to it so the file can be committed and shared.
+pub fn save(path: &Path, workspace: &Workspace) -> Result<OpenedWorkspace> {
+    let path = std::path::absolute(with_extension(path))?;
+    let base = path.parent().unwrap_or(Path::new("/"));
+    let mut stored = Workspace { version: WORKSPACE_VERSION, ..workspace.clone() };
+    for folder in &mut stored.folders {
+        folder.path = relativize(base, &folder.path);
+    }
+    if let Some(cwd) = stored.ai.cwd.as_mut() {
+        *cwd = relativize(base, cwd);
+    }
+    write_atomic(&path, &serde_json::to_vec_pretty(&stored)?)?;
+    Ok(OpenedWorkspace {
+        name: workspace_name(&path),
+        path: path.to_string_lossy().to_string(),
+        workspace: Workspace { version: WORKSPACE_VERSION, ..workspace.clone() },
+    })
+}
+
+fn resolve(base: &Path, path: &str) -> String {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return path.to_string_lossy().to_string();
+    }
+    // Normalize `.` and `..` so the roots compare equal to paths picked in a dialog
+    let mut out = base.to_path_buf();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => {}
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out.to_string_lossy().to_string()
+}
+
+fn relativize(base: &Path, path: &str) -> String {
+    match Path::new(path).strip_prefix(base) {
+        Ok(rel) if rel.as_os_str().is_empty() => ".".to_string(),
+        Ok(rel) => rel.to_string_lossy().replace('\\', "/"),
+        Err(_) => path.to_string(),
+    }
+}
+
+/// RecentWorkspaces is the most-recently-opened list, kept as a JSON file in app data
+pub struct RecentWorkspaces {
+    file: PathBuf,
+}
+
+impl RecentWorkspaces {
+    pub fn new(dir: &Path) -> Self {
+        Self { file: dir.join(RECENT_NAME) }
+    }
+
+    fn read(&self) -> Vec<RecentWorkspace> {
+        fs::read(&self.file)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    fn write(&self, recent: &[RecentWorkspace]) -> Result<()> {
+        if let Some(dir) = self.file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        write_atomic(&self.file, &serde_json::to_vec_pretty(recent)?)
+    }
+
+    /// Most recent first, skipping workspace files that no longer exist
+    pub fn list(&self) -> Vec<RecentWorkspace> {
+        self.read().into_iter().filter(|r| Path::new(&r.path).is_file()).collect()
+    }
+
+    /// Move `path` to the top of the list
+    pub fn touch(&self, path: &str, opened_at: u64) -> Result<Vec<RecentWorkspace>> {
+        let mut recent = self.list();
+        recent.retain(|r| r.path != path);
+        recent.insert(0, RecentWorkspace {
+            path: path.to_string(),
+            name: workspace_name(Path::new(path)),
+            opened_at,
+        });
+        recent.truncate(MAX_RECENT);
+        self.write(&recent)?;
+        Ok(recent)
+    }
+
+    pub fn remove(&self, path: &str) -> Result<Vec<RecentWorkspace>> {
+        let mut recent = self.list();
+        recent.retain(|r| r.path != path);
+        self.write(&recent)?;
+        Ok(recent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("editrion-workspace-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_save_and_load_relative_folders() {
+        let dir = temp_dir("roundtrip");
+        let inside = dir.join("app").to_string_lossy().to_string();
+        let workspace = Workspace {
+            folders: vec![
+                WorkspaceFolder { path: inside.clone(), name: None },
+                WorkspaceFolder { path: "/opt/shared".to_string(), name: Some("Shared".to_string()) },
+            ],
+            exclude: vec!["**/dist".to_string()],
+            ai: AiDefaults { model: Some("gpt-5".to_string()), cwd: Some(inside.clone()), ..Default::default() },
+            ..Default::default()
+        };
+
+        let saved = save(&dir.join("project"), &workspace).unwrap();
+        assert!(saved.path.ends_with("project.editrion-workspace"));
+        assert_eq!(saved.name, "project");
+        let raw: serde_json::Value = serde_json::from_slice(&fs::read(&saved.path).unwrap()).unwrap();
+        assert_eq!(raw["folders"][0]["path"], "app");
+        assert_eq!(raw["folders"][1]["path"], "/opt/shared");
+
+        let loaded = load(Path::new(&saved.path)).unwrap();
+        assert_eq!(loaded.workspace.folders, workspace.folders);
+        assert_eq!(loaded.workspace.ai.cwd.as_deref(), Some(inside.as_str()));
+        assert_eq!(loaded.workspace.version, WORKSPACE_VERSION);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_rejects_invalid_files() {
+        let dir = temp_dir("invalid");
+        let file = dir.join("broken.editrion-workspace");
+        fs::write(&file, "{").unwrap();
+        assert!(matches!(load(&file), Err(AppError::Config(_))));
+        fs::write(&file, r#"{"version": 99}"#).unwrap();
+        assert!(matches!(load(&file), Err(AppError::Config(_))));
+        assert!(matches!(load(&dir.join("missing.editrion-workspace")), Err(AppError::NotFound(_))));
+        // Paths in hand-edited files may climb out of the workspace folder
+        fs::write(&file, r#"{"folders": [{"path": "../sibling/./src"}]}"#).unwrap();
+        let expected = dir.parent().unwrap().join("sibling/src").to_string_lossy().to_string();
+        assert_eq!(load(&file).unwrap().workspace.folders[0].path, expected);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_apply_exclude() {
+        let workspace = Workspace { exclude: vec!["**/dist".to_string(), "*.lock".to_string()], ..Default::default() };
+        let mut query = SearchQuery { exclude: vec!["*.lock".to_string()], ..Default::default() };
+        workspace.apply_exclude(&mut query);
+        assert_eq!(query.exclude, ["*.lock", "**/dist"]);
+    }
+
+    #[test]
+    fn test_recent_workspaces() {
+        let dir = temp_dir("recent");
+        let recent = RecentWorkspaces::new(&dir);
+        let (a, b) = (dir.join("a.editrion-workspace"), dir.join("b.editrion-workspace"));
+        fs::write(&a, "{}").unwrap();
+        fs::write(&b, "{}").unwrap();
+        let (a, b) = (a.to_string_lossy().to_string(), b.to_string_lossy().to_string());
+
+        recent.touch(&a, 1).unwrap();
+        recent.touch(&b, 2).unwrap();
+        recent.touch(&a, 3).unwrap();
+        let names: Vec<_> = recent.list().into_iter().map(|r| r.name).collect();
+        assert_eq!(names, ["a", "b"]);
+
+        fs::remove_file(&b).unwrap();
+        assert_eq!(recent.list().len(), 1);
+        assert!(recent.remove(&a).unwrap().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,600 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use ignore::WalkState;
+use notify_debouncer_full::notify::event::{ModifyKind, RenameMode};
+use notify_debouncer_full::notify::{self, EventKind, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{new_debouncer_opt, DebounceEventResult, Debouncer, NoCache};
+use serde::Serialize;
+
+use crate::core::dir_listing;
+use crate::core::fuzzy::{FuzzyMatch, FuzzyMatcher};
+use crate::error::{AppError, Result};
+
+/// Files handed to queries at a time while a root is scanned for the first time
+const SCAN_BATCH: usize = 4096;
+
+/// Quiet period before a burst of file system events is applied to the index
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(300);
+
+pub const DEFAULT_QUERY_LIMIT: usize = 100;
+
+/// Changes to these files can hide or reveal whole subtrees
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceIndexStatus {
+    pub root: String,
+    pub files: usize,
+    /// The first full scan has finished
+    pub complete: bool,
+    /// Changes on disk are being picked up; false when the watch could not be set up
+    pub watching: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileMatch {
+    pub root: String,
+    pub path: String,
+    /// Path below `root` with `/` separators
+    pub relative_path: String,
+    pub score: i32,
+    /// Character indices into `relative_path` to highlight
+    pub positions: Vec<usize>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    /// Relative paths of indexed files (and links)
+    files: BTreeSet<String>,
+    /// Relative paths of indexed directories, `""` being the root itself
+    dirs: BTreeSet<String>,
+}
+
+/// What an event batch did to a root
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Change {
+    None,
+    Updated,
+    Rescan,
+}
+
+/// One opened folder and the files found under it
+pub struct IndexedRoot {
+    /// Path as given by the frontend, used to build result paths
+    original: String,
+    /// Canonical form, matched against watcher events
+    root: PathBuf,
+    entries: RwLock<Entries>,
+    complete: AtomicBool,
+    watching: AtomicBool,
+    removed: AtomicBool,
+    /// Bumped to supersede a running scan
+    generation: AtomicU64,
+}
+
+impl IndexedRoot {
+    fn new(original: &str) -> Result<Arc<Self>> {
+        let root = std::fs::canonicalize(original).map_err(|_| AppError::NotFound(original.to_string()))?;
+        if !root.is_dir() {
+            return Err(AppError::InvalidTarget {
+                path: original.to_string(),
+                reason: "not a directory".to_string(),
+            });
+        }
+        Ok(Arc::new(Self {
+            original: original.to_string(),
+            root,
+            entries: RwLock::new(Entries::default()),
+            complete: AtomicBool::new(false),
+            watching: AtomicBool::new(false),
+            removed: AtomicBool::new(false),
+            generation: AtomicU64::new(0),
+        }))
+    }
+
+    pub fn status(&self) -> WorkspaceIndexStatus {
+        WorkspaceIndexStatus {
+            root: self.original.clone(),
+            files: self.entries.read().map(|e| e.files.len()).unwrap_or(0),
+            complete: self.complete.load(Ordering::Acquire),
+            watching: self.watching.load(Ordering::Acquire),
+        }
+    }
+
+    /// Walk the whole root, replacing the index once done. Until the first scan
+    /// completes, files are published in batches so queries work right away.
+    /// Returns false when superseded by a newer scan.
+    fn scan(&self, on_progress: &(dyn Fn(WorkspaceIndexStatus) + Send + Sync)) -> bool {
+        let generation = self.generation.fetch_add(1, Ordering::AcqRel) + 1;
+        let first = !self.complete.load(Ordering::Acquire);
+        let current = || self.generation.load(Ordering::Acquire) == generation && !self.removed.load(Ordering::Acquire);
+
+        let mut fresh = Entries::default();
+        let mut batch = Entries::default();
+        let (tx, rx) = mpsc::channel::<(String, bool)>();
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                walker(&self.root).build_parallel().run(|| {
+                    let tx = tx.clone();
+                    Box::new(move |entry| {
+                        if !current() {
+                            return WalkState::Quit;
+                        }
+                        let Ok(entry) = entry else { return WalkState::Continue };
+                        let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+                        let Some(rel) = relative(&self.root, entry.path()) else { return WalkState::Continue };
+                        if tx.send((rel, is_dir)).is_err() {
+                            WalkState::Quit
+                        } else {
+                            WalkState::Continue
+                        }
+                    })
+                });
+            });
+
+            for (rel, is_dir) in rx {
+                if first {
+                    if is_dir {
+                        batch.dirs.insert(rel.clone());
+                    } else {
+                        batch.files.insert(rel.clone());
+                    }
+                    if batch.files.len() >= SCAN_BATCH {
+                        self.merge(std::mem::take(&mut batch));
+                        on_progress(self.status());
+                    }
+                }
+                if is_dir {
+                    fresh.dirs.insert(rel);
+                } else {
+                    fresh.files.insert(rel);
+                }
+            }
+        });
+
+        if !current() {
+            return false;
+        }
+        if let Ok(mut entries) = self.entries.write() {
+            *entries = fresh;
+        }
+        self.complete.store(true, Ordering::Release);
+        on_progress(self.status());
+        true
+    }
+
+    fn merge(&self, found: Entries) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.files.extend(found.files);
+            entries.dirs.extend(found.dirs);
+        }
+    }
+
+    /// Bring the index up to date with one debounced event
+    fn apply(
+        &self,
+        kind: &EventKind,
+        paths: &[PathBuf],
+        listings: &mut HashMap<PathBuf, HashSet<OsString>>,
+    ) -> Change {
+        let ours: Vec<&PathBuf> = paths.iter().filter(|p| p.starts_with(&self.root)).collect();
+        if ours.is_empty() {
+            return Change::None;
+        }
+        let is_ignore_file = |p: &Path| p.file_name().map(|n| IGNORE_FILES.iter().any(|f| n == *f)).unwrap_or(false);
+        if ours.iter().any(|p| is_ignore_file(p)) {
+            return Change::Rescan;
+        }
+
+        let mut changed = false;
+        match kind {
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                for p in &ours {
+                    changed |= self.add(p, listings);
+                }
+            }
+            EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                for p in &ours {
+                    changed |= self.remove(p);
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if paths.len() == 2 => {
+                // Either side may lie outside this root
+                if paths[0].starts_with(&self.root) {
+                    changed |= self.remove(&paths[0]);
+                }
+                if paths[1].starts_with(&self.root) {
+                    changed |= self.add(&paths[1], listings);
+                }
+            }
+            // Renames the backend could not pair up: look at what is on disk now
+            EventKind::Modify(ModifyKind::Name(_)) | EventKind::Any => {
+                for p in &ours {
+                    changed |= if p.symlink_metadata().is_ok() { self.add(p, listings) } else { self.remove(p) };
+                }
+            }
+            EventKind::Modify(_) | EventKind::Access(_) | EventKind::Other => {}
+        }
+        if changed {
+            Change::Updated
+        } else {
+            Change::None
+        }
+    }
+
+    /// Index a new file, or a new directory with everything below it, unless ignored
+    fn add(&self, path: &Path, listings: &mut HashMap<PathBuf, HashSet<OsString>>) -> bool {
+        let Some(rel) = relative(&self.root, path) else { return false };
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else { return false };
+        let parent_rel = rel.rsplit_once('/').map(|(p, _)| p).unwrap_or("");
+        // Paths below ignored or not yet scanned directories stay out
+        let parent_indexed = self.entries.read().map(|e| e.dirs.contains(parent_rel)).unwrap_or(false);
+        if rel.is_empty() || !parent_indexed {
+            return false;
+        }
+        let visible = listings
+            .entry(parent.to_path_buf())
+            .or_insert_with(|| dir_listing::not_ignored(parent));
+        if !visible.contains(name) || is_git_dir(name) {
+            return false;
+        }
+        let Ok(meta) = path.symlink_metadata() else { return false };
+
+        let mut found = Entries::default();
+        if meta.is_dir() {
+            for entry in walker(path).build().filter_map(|e| e.ok()) {
+                let Some(rel) = relative(&self.root, entry.path()) else { continue };
+                if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+                    found.dirs.insert(rel);
+                } else {
+                    found.files.insert(rel);
+                }
+            }
+        } else {
+            found.files.insert(rel);
+        }
+        self.merge(found);
+        true
+    }
+
+    /// Drop a path and, if it was a directory, everything below it
+    fn remove(&self, path: &Path) -> bool {
+        let Some(rel) = relative(&self.root, path) else { return false };
+        if rel.is_empty() {
+            return false;
+        }
+        let Ok(mut entries) = self.entries.write() else { return false };
+        let files = remove_subtree(&mut entries.files, &rel);
+        let dirs = remove_subtree(&mut entries.dirs, &rel);
+        files || dirs
+    }
+}
+
+/// Recursive index of the opened folders, kept current by a file system watch.
+///
+/// Scans and watch setup run on background threads; queries read whatever has
+/// been indexed so far.
+pub struct WorkspaceIndex {
+    roots: Arc<RwLock<Vec<Arc<IndexedRoot>>>>,
+    debouncer: Arc<Mutex<Option<Debouncer<RecommendedWatcher, NoCache>>>>,
+    on_progress: Option<Arc<dyn Fn(WorkspaceIndexStatus) + Send + Sync>>,
+}
+
+impl Default for WorkspaceIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WorkspaceIndex {
+    pub fn new() -> Self {
+        Self {
+            roots: Arc::new(RwLock::new(Vec::new())),
+            debouncer: Arc::new(Mutex::new(None)),
+            on_progress: None,
+        }
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.on_progress.is_some()
+    }
+
+    /// Start reporting scan progress and index updates to `on_progress`; later calls are no-ops
+    pub fn start(&mut self, on_progress: impl Fn(WorkspaceIndexStatus) + Send + Sync + 'static) -> Result<()> {
+        if self.on_progress.is_some() {
+            return Ok(());
+        }
+        let on_progress: Arc<dyn Fn(WorkspaceIndexStatus) + Send + Sync> = Arc::new(on_progress);
+        let roots = self.roots.clone();
+        let progress = on_progress.clone();
+        // Without a file id cache watching a large tree does not walk it a second time
+        let debouncer = new_debouncer_opt(
+            DEBOUNCE_TIMEOUT,
+            None,
+            move |result: DebounceEventResult| {
+                let Ok(events) = result else { return };
+                let Ok(roots) = roots.read().map(|r| r.clone()) else { return };
+                let mut listings = HashMap::new();
+                let mut changes = vec![Change::None; roots.len()];
+                for event in &events {
+                    for (root, change) in roots.iter().zip(changes.iter_mut()) {
+                        if *change == Change::Rescan {
+                            continue;
+                        }
+                        let applied = if event.need_rescan() {
+                            Change::Rescan
+                        } else {
+                            root.apply(&event.kind, &event.paths, &mut listings)
+                        };
+                        if applied != Change::None {
+                            *change = applied;
+                        }
+                    }
+                }
+                for (root, change) in roots.into_iter().zip(changes) {
+                    match change {
+                        Change::None => {}
+                        Change::Updated => progress(root.status()),
+                        Change::Rescan => {
+                            let progress = progress.clone();
+                            std::thread::spawn(move || root.scan(&*progress));
+                        }
+                    }
+                }
+            },
+            NoCache,
+            notify::Config::default(),
+        )
+        .map_err(|e| AppError::Command(format!("File watcher error: {}", e)))?;
+        *self.debouncer.lock().map_err(|e| AppError::Command(e.to_string()))? = Some(debouncer);
+        self.on_progress = Some(on_progress);
+        Ok(())
+    }
+
+    /// Index exactly `roots`: new folders are scanned in the background, folders no
+    /// longer listed are dropped. Folders that cannot be opened are left out.
+    pub fn set_roots(&mut self, roots: &[String]) -> Result<Vec<WorkspaceIndexStatus>> {
+        let (added, dropped) = {
+            let mut current = self.roots.write().map_err(|e| AppError::Command(e.to_string()))?;
+            let (kept, dropped): (Vec<_>, Vec<_>) = current.drain(..).partition(|r| roots.contains(&r.original));
+            *current = kept;
+            let mut added = Vec::new();
+            for path in roots {
+                if current.iter().any(|r| &r.original == path) {
+                    continue;
+                }
+                if let Ok(root) = IndexedRoot::new(path) {
+                    current.push(root.clone());
+                    added.push(root);
+                }
+            }
+            (added, dropped)
+        };
+
+        for root in dropped {
+            root.removed.store(true, Ordering::Release);
+            // Another root may be busy setting up its watch while holding the debouncer
+            let debouncer = self.debouncer.clone();
+            std::thread::spawn(move || {
+                if let Ok(Some(debouncer)) = debouncer.lock().as_deref_mut() {
+                    if root.watching.swap(false, Ordering::AcqRel) {
+                        let _ = debouncer.unwatch(&root.root);
+                    }
+                }
+            });
+        }
+        for root in added {
+            let debouncer = self.debouncer.clone();
+            let on_progress = self.on_progress.clone();
+            std::thread::spawn(move || {
+                // Watch first so nothing created during the scan is missed
+                if let Ok(Some(debouncer)) = debouncer.lock().as_deref_mut() {
+                    if !root.removed.load(Ordering::Acquire)
+                        && debouncer.watch(&root.root, RecursiveMode::Recursive).is_ok()
+                    {
+                        root.watching.store(true, Ordering::Release);
+                    }
+                }
+                match on_progress {
+                    Some(on_progress) => root.scan(&*on_progress),
+                    None => root.scan(&|_| {}),
+                }
+            });
+        }
+        Ok(self.status())
+    }
+
+    pub fn status(&self) -> Vec<WorkspaceIndexStatus> {
+        self.roots().iter().map(|r| r.status()).collect()
+    }
+
+    /// The indexed roots, for querying without holding on to the index itself
+    pub fn roots(&self) -> Vec<Arc<IndexedRoot>> {
+        self.roots.read().map(|r| r.clone()).unwrap_or_default()
+    }
+}
+
+type Hit<'a> = (FuzzyMatch, &'a IndexedRoot, &'a String);
+
+/// Fuzzy-match `query` against every indexed file, best matches first.
+/// Candidates are split across threads, each keeping only its best `limit`.
+pub fn query(roots: &[Arc<IndexedRoot>], query: &str, limit: usize) -> Vec<FileMatch> {
+    let matcher = FuzzyMatcher::new(query);
+    if matcher.is_empty() || limit == 0 {
+        return Vec::new();
+    }
+    let guards: Vec<_> = roots.iter().filter_map(|r| r.entries.read().ok().map(|e| (r, e))).collect();
+    let candidates: Vec<(&IndexedRoot, &String)> = guards
+        .iter()
+        .flat_map(|(root, entries)| entries.files.iter().map(move |rel| (&***root, rel)))
+        .collect();
+    let workers = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let chunk = candidates.len().div_ceil(workers).max(1);
+
+    let mut hits: Vec<Hit> = std::thread::scope(|scope| {
+        let handles: Vec<_> = candidates
+            .chunks(chunk)
+            .map(|part| {
+                let mut matcher = matcher.clone();
+                scope.spawn(move || {
+                    let mut hits: Vec<Hit> = part
+                        .iter()
+                        .filter_map(|&(root, rel)| matcher.score(rel).map(|m| (m, root, rel)))
+                        .collect();
+                    keep_best(&mut hits, limit);
+                    hits
+                })
+            })
+            .collect();
+        handles.into_iter().flat_map(|h| h.join().unwrap_or_default()).collect()
+    });
+    keep_best(&mut hits, limit);
+    hits.sort_by(rank);
+
+    hits.into_iter()
+        .map(|(m, root, rel)| FileMatch {
+            path: Path::new(&root.original)
+                .join(rel.replace('/', std::path::MAIN_SEPARATOR_STR))
+                .to_string_lossy()
+                .to_string(),
+            root: root.original.clone(),
+            relative_path: rel.clone(),
+            score: m.score,
+            positions: m.positions,
+        })
+        .collect()
+}
+
+/// Higher score first, then shorter paths
+fn rank(a: &Hit, b: &Hit) -> std::cmp::Ordering {
+    b.0.score
+        .cmp(&a.0.score)
+        .then_with(|| a.2.len().cmp(&b.2.len()))
+        .then_with(|| dir_listing::natural_cmp(a.2, b.2))
+}
+
+fn keep_best(hits: &mut Vec<Hit>, limit: usize) {
+    if hits.len() > limit {
+        hits.select_nth_unstable_by(limit - 1, rank);
+        hits.truncate(limit);
+    }
+}
+
+/// Same ignore rules as the explorer's `read_dir`, minus the contents of `.git`
+fn walker(path: &Path) -> ignore::WalkBuilder {
+    let mut builder = ignore::WalkBuilder::new(path);
+    builder
+        .hidden(false)
+        .parents(true)
+        .filter_entry(|e| !is_git_dir(e.file_name()));
+    builder
+}
+
+fn is_git_dir(name: &std::ffi::OsStr) -> bool {
+    name == ".git"
+}
+
+/// `path` below `root` joined with `/`, `""` for the root itself
+fn relative(root: &Path, path: &Path) -> Option<String> {
+    let rel = path.strip_prefix(root).ok()?;
+    let parts: Vec<_> = rel.components().map(|c| c.as_os_str().to_string_lossy()).collect();
+    Some(parts.join("/"))
+}
+
+fn remove_subtree(set: &mut BTreeSet<String>, rel: &str) -> bool {
+    let prefix = format!("{}/", rel);
+    let below: Vec<String> = set
+        .range(prefix.clone()..)
+        .take_while(|p| p.starts_with(&prefix))
+        .cloned()
+        .collect();
+    let mut removed = set.remove(rel);
+    for p in below {
+        removed |= set.remove(&p);
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify_debouncer_full::notify::event::{CreateKind, RemoveKind};
+    use std::fs;
+
+    fn temp_root(name: &str) -> (PathBuf, Arc<IndexedRoot>) {
+        let dir = std::env::temp_dir().join(format!("editrion-index-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::write(dir.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        fs::write(dir.join(".gitignore"), "target/\n").unwrap();
+        fs::create_dir_all(dir.join("src/core")).unwrap();
+        fs::create_dir_all(dir.join("target/debug")).unwrap();
+        fs::write(dir.join("src/main.rs"), "").unwrap();
+        fs::write(dir.join("src/core/mod.rs"), "").unwrap();
+        fs::write(dir.join("target/debug/app"), "").unwrap();
+        let root = IndexedRoot::new(&dir.to_string_lossy()).unwrap();
+        assert!(root.scan(&|_| {}));
+        (dir, root)
+    }
+
+    fn files(root: &IndexedRoot) -> Vec<String> {
+        root.entries.read().unwrap().files.iter().cloned().collect()
+    }
+
+    #[test]
+    fn test_scan_respects_ignore_files() {
+        let (dir, root) = temp_root("scan");
+        assert_eq!(files(&root), vec![".gitignore", "src/core/mod.rs", "src/main.rs"]);
+        assert!(root.status().complete);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_apply_incremental_changes() {
+        let (_, root) = temp_root("apply");
+        let dir = root.root.clone();
+
+        fs::create_dir_all(dir.join("src/ui")).unwrap();
+        fs::write(dir.join("src/ui/view.ts"), "").unwrap();
+        fs::write(dir.join("target/debug/other"), "").unwrap();
+        let create = EventKind::Create(CreateKind::Any);
+        assert_eq!(root.apply(&create, &[dir.join("src/ui")], &mut HashMap::new()), Change::Updated);
+        assert_eq!(root.apply(&create, &[dir.join("target/debug/other")], &mut HashMap::new()), Change::None);
+        assert!(files(&root).contains(&"src/ui/view.ts".to_string()));
+
+        fs::rename(dir.join("src/main.rs"), dir.join("src/lib.rs")).unwrap();
+        let rename = EventKind::Modify(ModifyKind::Name(RenameMode::Both));
+        root.apply(&rename, &[dir.join("src/main.rs"), dir.join("src/lib.rs")], &mut HashMap::new());
+
+        fs::remove_dir_all(dir.join("src/core")).unwrap();
+        let remove = EventKind::Remove(RemoveKind::Folder);
+        root.apply(&remove, &[dir.join("src/core")], &mut HashMap::new());
+        assert_eq!(files(&root), vec![".gitignore", "src/lib.rs", "src/ui/view.ts"]);
+
+        let modify = EventKind::Modify(ModifyKind::Any);
+        assert_eq!(root.apply(&modify, &[dir.join(".gitignore")], &mut HashMap::new()), Change::Rescan);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_query_across_roots() {
+        let (dir, root) = temp_root("query");
+        let matches = query(std::slice::from_ref(&root), "mod", 10);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].relative_path, "src/core/mod.rs");
+        assert_eq!(matches[0].positions, vec![9, 10, 11]);
+        assert_eq!(Path::new(&matches[0].path), dir.join("src").join("core").join("mod.rs"));
+
+        let matches = query(&[root], "sr", 1);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].relative_path, "src/main.rs");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
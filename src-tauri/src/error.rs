@@ -1,4 +1,6 @@
-use serde::{Serialize, ser::Serializer};
+use serde::{Serialize, ser::{SerializeMap, Serializer}};
+
+use crate::core::file_version::FileVersion;
 
 #[derive(Debug, thiserror::Error)]
 #[allow(dead_code)]
@@ -18,20 +20,169 @@ pub enum AppError {
     #[error("Process not found for run_id: {0}")]
     ProcessNotFound(String),
 
+    #[error("Unknown AI provider: {0}")]
+    ProviderNotFound(String),
+
     #[error("Could not resolve path for binary: {0}")]
     BinaryPath(String),
 
     #[error("Configuration error: {0}")]
     Config(String),
+
+    #[error("Cannot atomically replace {path}: {reason}")]
+    AtomicWrite { path: String, reason: String },
+
+    #[error("Unsupported encoding: {0}")]
+    UnsupportedEncoding(String),
+
+    #[error("Character {character:?} at line {line}, column {column} cannot be encoded as {encoding}")]
+    Unencodable { encoding: String, character: String, line: usize, column: usize },
+
+    #[error("File is too large to open ({size} bytes, limit {limit} bytes): {path}")]
+    FileTooLarge { path: String, size: u64, limit: u64 },
+
+    #[error("Large file not open for id: {0}")]
+    LargeFileNotFound(String),
+
+    #[error("File was changed on disk since it was opened: {path}")]
+    Conflict { path: String, current: Option<FileVersion> },
+
+    #[error("Only part of the file was loaded, so it cannot be saved: {0}")]
+    PartialContent(String),
+
+    #[error("Already exists: {0}")]
+    TargetExists(String),
+
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
+    #[error("No such file or directory: {0}")]
+    NotFound(String),
+
+    #[error("Invalid destination {path}: {reason}")]
+    InvalidTarget { path: String, reason: String },
+
+    #[error("Trash is unavailable: {0}")]
+    TrashUnavailable(String),
+
+    #[error("Not found in trash: {0}")]
+    TrashItemNotFound(String),
+
+    #[error("Invalid search pattern: {0}")]
+    InvalidPattern(String),
+
+    #[error("Search not found for run_id: {0}")]
+    SearchNotFound(String),
+
+    #[error("Replace backup not found: {0}")]
+    BackupNotFound(String),
+
+    #[error("No such local history entry: {0}")]
+    HistoryEntryNotFound(String),
+
+    #[error("Invalid setting {key}: {reason}")]
+    InvalidSetting { key: String, reason: String },
+
+    #[error("Invalid TOML in {path} at line {line}, column {column}: {message}")]
+    InvalidToml { path: String, line: usize, column: usize, message: String },
+}
+
+impl AppError {
+    /// Stable identifier the frontend can branch on
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::Io(_) => "io",
+            AppError::Json(_) => "json",
+            AppError::Tauri(_) => "tauri",
+            AppError::Command(_) => "command",
+            AppError::ProcessNotFound(_) => "processNotFound",
+            AppError::ProviderNotFound(_) => "providerNotFound",
+            AppError::BinaryPath(_) => "binaryPath",
+            AppError::Config(_) => "config",
+            AppError::AtomicWrite { .. } => "atomicWrite",
+            AppError::UnsupportedEncoding(_) => "unsupportedEncoding",
+            AppError::Unencodable { .. } => "unencodable",
+            AppError::FileTooLarge { .. } => "fileTooLarge",
+            AppError::LargeFileNotFound(_) => "largeFileNotFound",
+            AppError::Conflict { .. } => "conflict",
+            AppError::PartialContent(_) => "partialContent",
+            AppError::TargetExists(_) => "targetExists",
+            AppError::PermissionDenied(_) => "permissionDenied",
+            AppError::NotFound(_) => "notFound",
+            AppError::InvalidTarget { .. } => "invalidTarget",
+            AppError::TrashUnavailable(_) => "trashUnavailable",
+            AppError::TrashItemNotFound(_) => "trashItemNotFound",
+            AppError::InvalidPattern(_) => "invalidPattern",
+            AppError::SearchNotFound(_) => "searchNotFound",
+            AppError::BackupNotFound(_) => "backupNotFound",
+            AppError::HistoryEntryNotFound(_) => "historyEntryNotFound",
+            AppError::InvalidSetting { .. } => "invalidSetting",
+            AppError::InvalidToml { .. } => "invalidToml",
+        }
+    }
+
+    /// Structured fields for variants the frontend reacts to
+    fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            AppError::AtomicWrite { path, reason } => Some(serde_json::json!({
+                "path": path,
+                "reason": reason,
+            })),
+            AppError::Unencodable { encoding, character, line, column } => Some(serde_json::json!({
+                "encoding": encoding,
+                "character": character,
+                "line": line,
+                "column": column,
+            })),
+            AppError::FileTooLarge { path, size, limit } => Some(serde_json::json!({
+                "path": path,
+                "size": size,
+                "limit": limit,
+            })),
+            AppError::Conflict { path, current } => Some(serde_json::json!({
+                "path": path,
+                "current": current,
+            })),
+            AppError::TargetExists(path)
+            | AppError::PermissionDenied(path)
+            | AppError::NotFound(path)
+            | AppError::PartialContent(path) => {
+                Some(serde_json::json!({ "path": path }))
+            }
+            AppError::InvalidTarget { path, reason } => Some(serde_json::json!({
+                "path": path,
+                "reason": reason,
+            })),
+            AppError::InvalidSetting { key, reason } => Some(serde_json::json!({
+                "key": key,
+                "reason": reason,
+            })),
+            AppError::InvalidToml { path, line, column, message } => Some(serde_json::json!({
+                "path": path,
+                "line": line,
+                "column": column,
+                "message": message,
+            })),
+            _ => None,
+        }
+    }
 }
 
-// We need to implement Serialize manually for AppError
+// We need to implement Serialize manually for AppError:
+// the frontend receives `{ kind, message, details? }`
 impl Serialize for AppError {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        let details = self.details();
+        let mut map = serializer.serialize_map(Some(if details.is_some() { 3 } else { 2 }))?;
+        map.serialize_entry("kind", self.kind())?;
+        map.serialize_entry("message", &self.to_string())?;
+        if let Some(details) = details {
+            map.serialize_entry("details", &details)?;
+        }
+        map.end()
     }
 }
 
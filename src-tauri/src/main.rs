@@ -57,6 +57,7 @@ fn main() {
         .setup(|app| {
             let menu = menu::build_initial_menu(app.handle())?;
             app.handle().set_menu(menu)?;
+            commands::settings::watch_codex_config(app.handle());
             Ok(())
         })
         .on_menu_event(|app, event| {
@@ -68,21 +69,79 @@ fn main() {
             commands::app::drafts_dir,
             // commands::file_system
             commands::file_system::read_file,
+            commands::file_system::stat_file,
             commands::file_system::write_file,
+            commands::file_system::read_file_with_encoding,
+            commands::file_system::write_file_with_encoding,
             commands::file_system::read_dir,
             commands::file_system::create_new_file,
             commands::file_system::remove_file,
             commands::file_system::clear_dir,
+            commands::file_system::create_dir,
+            commands::file_system::rename_path,
+            commands::file_system::copy_path,
+            commands::file_system::duplicate_path,
+            // commands::history
+            commands::history::list_file_history,
+            commands::history::read_history_snapshot,
+            commands::history::restore_history_snapshot,
+            // commands::large_file
+            commands::large_file::large_file_open,
+            commands::large_file::large_file_lines,
+            commands::large_file::large_file_status,
+            commands::large_file::large_file_search,
+            commands::large_file::large_file_close,
+            // commands::replace
+            commands::replace::replace_preview,
+            commands::replace::replace_apply,
+            commands::replace::replace_undo,
+            commands::replace::list_replace_backups,
+            commands::replace::discard_replace_backup,
+            // commands::search
+            commands::search::search_in_files,
+            commands::search::search_cancel,
+            // commands::session
+            commands::session::save_session,
+            commands::session::restore_session,
+            commands::session::discard_session,
+            // commands::settings
+            commands::settings::settings_get,
+            commands::settings::settings_set,
+            commands::settings::settings_reset,
+            commands::settings::settings_import,
+            commands::settings::settings_path,
+            // commands::trash
+            commands::trash::trash_path,
+            commands::trash::list_trash,
+            commands::trash::restore_from_trash,
+            commands::trash::purge_from_trash,
+            // commands::watcher
+            commands::watcher::watch_paths,
+            commands::watcher::unwatch_paths,
+            // commands::workspace
+            commands::workspace::open_workspace,
+            commands::workspace::save_workspace,
+            commands::workspace::switch_workspace,
+            commands::workspace::close_workspace,
+            commands::workspace::current_workspace,
+            commands::workspace::list_recent_workspaces,
+            commands::workspace::remove_recent_workspace,
+            // commands::workspace_index
+            commands::workspace_index::index_workspace,
+            commands::workspace_index::workspace_index_status,
+            commands::workspace_index::find_files,
             // commands::external_cli
-            commands::external_cli::codex_exec_stream,
-            commands::external_cli::claude_exec_stream,
-            commands::external_cli::codex_login_stream,
-            commands::external_cli::claude_login_stream,
-            commands::external_cli::codex_cancel,
-            commands::external_cli::claude_cancel,
+            commands::external_cli::ai_exec_stream,
+            commands::external_cli::ai_login_stream,
+            commands::external_cli::ai_cancel,
+            commands::external_cli::list_providers,
             // config
             config::codex_config_path,
+            config::codex_config_get,
+            config::codex_config_read,
+            config::codex_config_profiles,
             config::codex_config_set,
+            config::codex_config_unset,
             // menu
             menu::rebuild_menu,
             // startup
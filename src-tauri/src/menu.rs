@@ -37,6 +37,10 @@ const FILE_MENU_ITEMS: &[MenuItemConfig] = &[
     MenuItemConfig { id: "new_file", label_key: "menu.item.newFile", default_label: "New File", shortcut: Some("CmdOrCtrl+N") },
     MenuItemConfig { id: "open_file", label_key: "menu.item.openFile", default_label: "Open File...", shortcut: Some("CmdOrCtrl+O") },
     MenuItemConfig { id: "open_folder", label_key: "menu.item.openFolder", default_label: "Open Folder...", shortcut: None },
+    MenuItemConfig { id: "open_workspace", label_key: "menu.item.openWorkspace", default_label: "Open Workspace...", shortcut: None },
+    MenuItemConfig { id: "open_recent_workspace", label_key: "menu.item.openRecentWorkspace", default_label: "Open Recent Workspace...", shortcut: None },
+    MenuItemConfig { id: "save_workspace_as", label_key: "menu.item.saveWorkspaceAs", default_label: "Save Workspace As...", shortcut: None },
+    MenuItemConfig { id: "close_workspace", label_key: "menu.item.closeWorkspace", default_label: "Close Workspace", shortcut: None },
     MenuItemConfig { id: "save", label_key: "menu.item.save", default_label: "Save", shortcut: Some("CmdOrCtrl+S") },
     MenuItemConfig { id: "save_as", label_key: "menu.item.saveAs", default_label: "Save As…", shortcut: Some("CmdOrCtrl+Shift+S") },
     MenuItemConfig { id: "close_tab", label_key: "menu.item.closeTab", default_label: "Close Tab", shortcut: Some("CmdOrCtrl+W") },
@@ -172,11 +176,14 @@ fn build_file_menu<R: Runtime>(
     Submenu::with_items(app, &file_label, true, &[
         &items[0], &items[1], &items[2],  // new, open file, open folder
         &PredefinedMenuItem::separator(app)?,
-        &items[3], &items[4],             // save, save as
+        &items[3], &items[4],             // open workspace, open recent workspace
+        &items[5], &items[6],             // save workspace as, close workspace
         &PredefinedMenuItem::separator(app)?,
-        &items[5],                        // close tab
+        &items[7], &items[8],             // save, save as
         &PredefinedMenuItem::separator(app)?,
-        &items[6],                        // quit
+        &items[9],                        // close tab
+        &PredefinedMenuItem::separator(app)?,
+        &items[10],                       // quit
     ]).map_err(Into::into)
 }
 
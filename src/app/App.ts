@@ -1,6 +1,6 @@
 import { listen } from '@tauri-apps/api/event';
 import { convertFileSrc } from '@tauri-apps/api/core';
-import { initI18n, setLocale, t, registerDictionaries, applyTranslations } from '../services/i18n';
+import { initI18n, setLocale, getLocale, t, registerDictionaries, applyTranslations } from '../services/i18n';
 import { themeManager } from '../services/themeManager';
 import { tauriApi } from '../services/tauriApi';
 // import { appStore } from '../store/appStore';
@@ -10,7 +10,9 @@ import { Tab } from '../components/Tab';
 import { SearchPanel } from '../components/SearchPanel';
 import { FileExplorer } from '../components/FileExplorer';
 import { getShortcuts, matchesDomEvent } from '../services/shortcuts';
-import type { Tab as TabData } from '../types';
+import { readTextFile, writeTextFile, writeUnlessChanged } from '../services/fileSave';
+import { getSettings, getSettingsProblems, onSettingsChanged, reloadSettings, resetSettings, setSetting } from '../services/settings';
+import type { FsEventPayload, OpenedWorkspace, RecentWorkspace, Session, SessionTab, Settings, Tab as TabData, Workspace } from '../types';
 
 import en from '../locales/en.json';
 import uk from '../locales/uk.json';
@@ -31,9 +33,20 @@ export class App {
   private tabComponents: Map<string, Tab> = new Map();
   // Track a compact signature of tabs to avoid unnecessary re-renders
   private prevTabsSignature: string = '';
-  private draftsDir: string | null = null;
-  private draftTimers: Record<string, number> = {};
+  private sessionTimer: number | null = null;
+  // Path of the open `.editrion-workspace` file
+  private workspacePath: string | null = null;
+  // Session saves start once the previous session has been restored
+  private sessionReady = false;
+  /** settings.json, reloaded when saved from a tab */
+  private settingsPath = '';
+  private appliedThemeMode: 'dark' | 'light' = 'dark';
+  private appliedLocale = '';
+  /** Settings problems already shown, so each is reported once */
+  private reportedProblems = new Set<string>();
   private aiOverrides: { model?: string; effort?: 'minimal'|'low'|'medium'|'high'; summary?: 'auto'|'concise'|'detailed'|'none'; verbosity?: 'low'|'medium'|'high' } = {};
+  /** Paths of open tabs watched for changes on disk */
+  private watchedTabPaths = new Set<string>();
   private confirmOverlay?: HTMLElement;
   private confirmMessageEl?: HTMLElement;
   private confirmResolve?: (v: 'save' | 'discard' | 'cancel') => void;
@@ -53,23 +66,31 @@ export class App {
     registerDictionaries('ja', ja as any);
     registerDictionaries('de', de as any);
     initI18n();
+    this.appliedLocale = getLocale();
     applyTranslations();
     // Ensure native menu uses current locale labels
     this.updateNativeMenuLabels();
 
-    // Do not overwrite clean-exit flag on startup; we check it in prepareDrafts()
-
     // Theme
-    const savedMode = localStorage.getItem('editrion.themeMode');
-    const savedTheme = localStorage.getItem('editrion.theme') || 'dark';
-    const isLight = savedMode ? savedMode === 'light' : savedTheme.includes('light');
-    themeManager.setTheme(isLight ? 'light' : 'dark');
+    const settings = getSettings();
+    this.applyThemeMode(settings);
+    this.aiOverrides = {
+      model: settings.ai.model ?? undefined,
+      effort: settings.ai.effort ?? undefined,
+      summary: settings.ai.summary ?? undefined,
+      verbosity: settings.ai.verbosity ?? undefined,
+    };
+    // Changes from other windows, a workspace, a reset or a hand-edited settings.json
+    onSettingsChanged(settings => this.onSettingsChanged(settings));
+    tauriApi.getSettingsPath().then(path => { this.settingsPath = path; }).catch(() => {});
 
     // Components
     this.editor = new Editor(this.editorContainer);
-    this.editor.onContentChanged = (tab, content) => this.onContentChanged(tab, content);
+    this.editor.onContentChanged = () => this.scheduleSessionSave();
     this.searchPanel = new SearchPanel();
     this.fileExplorer = new FileExplorer((path, name) => this.openFileByPath(path, name));
+    this.fileExplorer.onRootsChanged = () => this.scheduleSessionSave();
+    window.addEventListener('resize', () => this.scheduleSessionSave());
 
     // Welcome actions
     const welcomeOpenFileBtn = document.getElementById('welcome-open-file');
@@ -81,6 +102,7 @@ export class App {
     this.setupShortcuts();
     this.setupMenuListeners();
     this.setupOpenWithListener();
+    this.setupFileWatching();
     this.openStartupPaths();
     this.setupTabsOverflowMenu();
     // Horizontal wheel scroll over tabs
@@ -101,78 +123,110 @@ export class App {
     this.subscribeToTabs();
     this.updateWelcomeState();
 
-    // Drafts
-    this.prepareDrafts();
+    // Hot exit
+    this.restoreSession();
     // Init unsaved changes modal
     this.initConfirmModal();
   }
 
-  private async prepareDrafts() {
+  /** Bring back the previous session. Unsaved buffers left by a crash are only restored after asking. */
+  private async restoreSession() {
     try {
-      this.draftsDir = await tauriApi.getDraftsDir();
-      // Skip restoring drafts if last session exited cleanly
-      let cleanExit = false;
-      try { cleanExit = localStorage.getItem('editrion.cleanExit') === 'true'; } catch {}
-      if (cleanExit) {
-        try { await tauriApi.clearDir(this.draftsDir); } catch {}
-      } else {
-        // Ask whether to restore if there are any draft files
-        try {
-          const entries = await tauriApi.readDir(this.draftsDir);
-          const hasDrafts = entries.some(e => !e.is_dir && e.name.endsWith('.json'));
-          if (hasDrafts) {
-            const agree = confirm(t('info.restoredDrafts') || 'Restored unsaved changes from previous session. Restore now?');
-            if (!agree) { await tauriApi.clearDir(this.draftsDir); }
-            else { await this.restoreDrafts(); }
-          }
-        } catch (e) { console.warn('Failed to inspect drafts dir', e); }
-      }
-    } catch (e) { console.warn('Drafts unavailable:', e); }
-  }
-
-  private async restoreDrafts() {
-    if (!this.draftsDir) return;
-    try {
-      const entries: { name: string; path: string; is_dir: boolean }[] = await tauriApi.readDir(this.draftsDir);
-      const files = entries.filter(e => !e.is_dir && e.name.endsWith('.json'));
-      for (const f of files) {
-        try {
-          const raw = await tauriApi.readFile(f.path);
-          const draft = JSON.parse(raw) as { id: string; name: string; path: string; content: string };
-          const name = draft.name || this.basename(draft.path || '') || t('common.untitled');
-          // Create tab; editor will be lazily created on activation
-          // If the draft has no real path, keep it empty so Save prompts for location
-          tabsStore.createTab(name, draft.path || '', draft.content || '');
-        } catch (e) { console.warn('Failed to restore draft', f.path, e); }
+      // Superseded by `Session.cleanExit`
+      try { localStorage.removeItem('editrion.cleanExit'); } catch {}
+      const session = await tauriApi.restoreSession();
+      if (session) {
+        const hasUnsaved = session.tabs.some(tab => tab.content != null);
+        const keepUnsaved = !hasUnsaved || session.cleanExit
+          || confirm(t('info.restoredDrafts') || 'Restored unsaved changes from previous session. Restore now?');
+        if (session.workspace) {
+          // Reattach without applying its stored session; this one is newer
+          try { this.applyWorkspaceDefaults(await tauriApi.openWorkspace(session.workspace)); } catch (e) { console.warn('Workspace unavailable:', e); }
+        }
+        if (session.roots.length > 0) this.fileExplorer.setRoots(session.roots);
+        await this.restoreTabs(session, keepUnsaved);
       }
-      this.updateWelcomeState();
-    } catch (e) { console.warn('Failed to scan drafts dir', e); }
+    } catch (e) { console.warn('Session unavailable:', e); }
+    this.sessionReady = true;
+    this.scheduleSessionSave();
   }
 
-  private async onContentChanged(tab: TabData, content: string) {
-    if (!this.draftsDir) return;
-    if (this.draftTimers[tab.id]) window.clearTimeout(this.draftTimers[tab.id]);
-    this.draftTimers[tab.id] = window.setTimeout(async () => {
+  private async restoreTabs(session: Session, keepUnsaved: boolean) {
+    const restoredIds: Record<string, string> = {};
+    for (const saved of session.tabs) {
       try {
-        const current = tabsStore.getTab(tab.id);
-        const draftPath = `${this.draftsDir}/${tab.id}.json`;
-        // Only persist a draft if the tab is dirty; otherwise remove any leftover draft
-        if (!current || !current.isDirty) {
-          try { await tauriApi.removeFile(draftPath); } catch {}
-          return;
+        let tab: TabData | undefined;
+        if (saved.content != null && keepUnsaved) {
+          const name = saved.name || this.basename(saved.path) || t('common.untitled');
+          // Editor is created lazily on activation; an empty path makes Save prompt for a location
+          tab = tabsStore.createTab(name, saved.path, saved.content);
+          tabsStore.updateTab(tab.id, { isDirty: true, version: saved.version ?? undefined, format: saved.format ?? undefined });
+        } else if (saved.path) {
+          // Files deleted since the last session are dropped silently
+          try { await tauriApi.statFile(saved.path); } catch { continue; }
+          await this.openFileByPath(saved.path, saved.name || this.basename(saved.path));
+          tab = tabsStore.getTabByPath(saved.path);
         }
-        const payload = { id: tab.id, name: current.name, path: current.path, content, ts: Date.now() };
-        await tauriApi.writeFile(draftPath, JSON.stringify(payload));
-      } catch (e) { console.warn('Failed to save draft', e); }
+        if (!tab) continue;
+        restoredIds[saved.id] = tab.id;
+        if (saved.view) tabsStore.updateTab(tab.id, { pendingView: saved.view });
+      } catch (e) { console.warn('Failed to restore tab', saved.path, e); }
+    }
+    const active = session.activeTab ? restoredIds[session.activeTab] : undefined;
+    if (active) tabsStore.setActiveTab(active);
+    this.updateWelcomeState();
+  }
+
+  /** Snapshot of the open tabs; `discardUnsaved` drops buffer contents and never-saved tabs */
+  private collectSession(cleanExit: boolean, discardUnsaved = false): Session {
+    const state = tabsStore.getState();
+    const tabs: SessionTab[] = [];
+    for (const tab of state.tabs) {
+      if (discardUnsaved && tab.isDirty && !tab.path) continue;
+      const unsaved = tab.isDirty && !discardUnsaved;
+      const pos = tab.editor?.getPosition();
+      tabs.push({
+        id: tab.id,
+        name: tab.name,
+        path: tab.path,
+        content: unsaved ? (tab.editor ? tab.editor.getValue() : tab.originalContent ?? '') : null,
+        version: unsaved ? tab.version ?? null : null,
+        format: unsaved ? tab.format ?? null : null,
+        view: tab.editor && pos
+          ? { line: pos.lineNumber, column: pos.column, scrollTop: tab.editor.getScrollTop(), scrollLeft: tab.editor.getScrollLeft() }
+          : tab.pendingView ?? null,
+      });
+    }
+    return { cleanExit, roots: this.fileExplorer.getRoots(), workspace: this.workspacePath, tabs, activeTab: state.activeTabId };
+  }
+
+  private scheduleSessionSave() {
+    if (!this.sessionReady) return;
+    if (this.sessionTimer) window.clearTimeout(this.sessionTimer);
+    this.sessionTimer = window.setTimeout(() => {
+      this.sessionTimer = null;
+      this.saveSession(false);
     }, 400);
   }
 
+  private async saveSession(cleanExit: boolean, discardUnsaved = false) {
+    if (cleanExit) {
+      // Nothing may overwrite the final save
+      this.sessionReady = false;
+      if (this.sessionTimer) { window.clearTimeout(this.sessionTimer); this.sessionTimer = null; }
+    }
+    try { await tauriApi.saveSession(this.collectSession(cleanExit, discardUnsaved)); }
+    catch (e) { console.warn('Failed to save session', e); }
+  }
+
   private subscribeToTabs() {
     tabsStore.subscribe((state) => {
+      this.scheduleSessionSave();
       const sig = this.computeTabsRenderSignature(state);
       if (sig !== this.prevTabsSignature) {
         this.prevTabsSignature = sig;
         this.renderTabs();
+        this.syncWatchedTabs();
       }
     });
   }
@@ -318,6 +372,10 @@ export class App {
         case 'new_file': await this.createNewFile(); break;
         case 'open_file': await this.openFile(); break;
         case 'open_folder': await this.fileExplorer.openFolder(); break;
+        case 'open_workspace': await this.openWorkspace(); break;
+        case 'open_recent_workspace': await this.openRecentWorkspaces(); break;
+        case 'save_workspace_as': await this.saveWorkspaceAs(); break;
+        case 'close_workspace': await this.closeWorkspace(); break;
         case 'save': await this.saveActiveFile(); break;
         case 'save_as': await this.saveActiveFileAs(); break;
         case 'close_tab': { const active = tabsStore.getActiveTab(); if (active) await this.closeTab(active.id); } break;
@@ -328,8 +386,8 @@ export class App {
           break;
         }
         case 'quit_app': await this.handleQuitRequest(); break;
-        case 'theme_dark': themeManager.setTheme('dark'); localStorage.setItem('editrion.theme', 'dark'); localStorage.setItem('editrion.themeMode', 'dark'); break;
-        case 'theme_light': themeManager.setTheme('light'); localStorage.setItem('editrion.theme', 'light'); localStorage.setItem('editrion.themeMode', 'light'); break;
+        case 'theme_dark': this.appliedThemeMode = 'dark'; themeManager.setTheme('dark'); await setSetting('theme', 'dark'); await setSetting('themeMode', 'dark'); break;
+        case 'theme_light': this.appliedThemeMode = 'light'; themeManager.setTheme('light'); await setSetting('theme', 'light'); await setSetting('themeMode', 'light'); break;
         case 'theme_load_custom':
           try {
             const picked = await tauriApi.openFileDialog([{ name: 'JSON', extensions: ['json'] }]);
@@ -340,12 +398,11 @@ export class App {
             const id = await themeManager.loadCustomTheme(def);
             themeManager.setTheme(id);
             if (def && def.name) {
-              const raw = localStorage.getItem('editrion.customThemes');
-              const map = raw ? JSON.parse(raw) as Record<string, any> : {};
-              map[String(def.name)] = def;
-              localStorage.setItem('editrion.customThemes', JSON.stringify(map));
-              localStorage.setItem('editrion.theme', `custom:${String(def.name)}`);
-              localStorage.setItem('editrion.themeMode', def.isDark ? 'dark' : 'light');
+              const map = { ...getSettings().customThemes, [String(def.name)]: def };
+              this.appliedThemeMode = def.isDark ? 'dark' : 'light';
+              await setSetting('customThemes', map);
+              await setSetting('theme', `custom:${String(def.name)}`);
+              await setSetting('themeMode', this.appliedThemeMode);
             }
           } catch (e) {
             console.error('Failed to load custom theme:', e);
@@ -401,6 +458,57 @@ export class App {
     });
   }
 
+  // ---------- Changes on disk ----------
+  private async setupFileWatching() {
+    for (const name of ['fs-changed', 'fs-removed', 'fs-renamed']) {
+      await listen<FsEventPayload>(name, (event) => { this.onFsEvent(event.payload); });
+    }
+  }
+
+  /** Watch the file of every open tab, and stop watching closed ones */
+  private syncWatchedTabs() {
+    const paths = new Set(tabsStore.getState().tabs.map(tab => tab.path).filter(Boolean));
+    const added = [...paths].filter(p => !this.watchedTabPaths.has(p));
+    const removed = [...this.watchedTabPaths].filter(p => !paths.has(p));
+    this.watchedTabPaths = paths;
+    if (added.length) tauriApi.watchPaths(added).catch(e => console.warn('Failed to watch files', e));
+    if (removed.length) tauriApi.unwatchPaths(removed).catch(e => console.warn('Failed to unwatch files', e));
+  }
+
+  private async onFsEvent(event: FsEventPayload) {
+    this.fileExplorer.onFsEvent(event);
+    const path = event.kind === 'renamed' ? event.from : event.path;
+    const tab = tabsStore.getTabByPath(path);
+    if (!tab || this.isImagePath(tab.path)) return;
+    if (event.kind === 'changed') { await this.reloadTabFromDisk(tab.id); return; }
+    // Tools that save by moving the old file away put a new one in its place
+    const exists = await tauriApi.statFile(path).then(() => true, () => false);
+    if (exists) { await this.reloadTabFromDisk(tab.id); return; }
+    if (event.kind === 'renamed') {
+      tabsStore.updateTab(tab.id, { path: event.to, name: this.basename(event.to) });
+    } else {
+      // Keep the buffer; closing the tab now asks before it is lost
+      tabsStore.updateTab(tab.id, { isDirty: true, version: undefined });
+    }
+  }
+
+  /** Show what another program wrote. Tabs with unsaved edits keep them; saving reports the conflict. */
+  private async reloadTabFromDisk(tabId: string) {
+    const tab = tabsStore.getTab(tabId); if (!tab || tab.isDirty) return;
+    try {
+      const { content, version, format } = await readTextFile(tab.path);
+      // Our own saves are reported too
+      if (tab.version && tab.version.hash === version.hash) return;
+      tabsStore.saveTab(tab.id, content);
+      tabsStore.updateTab(tab.id, { version, format });
+      const model = tab.editor?.getModel();
+      if (model && model.getValue() !== content) {
+        // An edit rather than setValue, so undo can go back to the previous text
+        model.pushEditOperations([], [{ range: model.getFullModelRange(), text: content }], () => null);
+      }
+    } catch (e) { console.warn('Failed to reload', tab.path, e); }
+  }
+
   private async openStartupPaths() {
     try {
       const paths = await tauriApi.getStartupPaths();
@@ -416,7 +524,9 @@ export class App {
   private async updateNativeMenuLabels() {
     const labels = {
       'menu.file': t('menu.file'), 'menu.edit': t('menu.edit'), 'menu.view': t('menu.view'), 'menu.window': t('menu.window'), 'menu.settings': t('menu.settings'), 'menu.language': t('menu.language'), 'menu.theme': t('menu.theme'),
-      'menu.item.newFile': t('menu.item.newFile'), 'menu.item.openFile': t('menu.item.openFile'), 'menu.item.openFolder': t('menu.item.openFolder'), 'menu.item.save': t('menu.item.save'), 'menu.item.saveAs': t('menu.item.saveAs'), 'menu.item.closeTab': t('menu.item.closeTab'), 'menu.item.quit': t('menu.item.quit'),
+      'menu.item.newFile': t('menu.item.newFile'), 'menu.item.openFile': t('menu.item.openFile'), 'menu.item.openFolder': t('menu.item.openFolder'),
+      'menu.item.openWorkspace': t('menu.item.openWorkspace'), 'menu.item.openRecentWorkspace': t('menu.item.openRecentWorkspace'), 'menu.item.saveWorkspaceAs': t('menu.item.saveWorkspaceAs'), 'menu.item.closeWorkspace': t('menu.item.closeWorkspace'),
+      'menu.item.save': t('menu.item.save'), 'menu.item.saveAs': t('menu.item.saveAs'), 'menu.item.closeTab': t('menu.item.closeTab'), 'menu.item.quit': t('menu.item.quit'),
       'menu.item.undo': t('menu.item.undo'), 'menu.item.redo': t('menu.item.redo'), 'menu.item.cut': t('menu.item.cut'), 'menu.item.copy': t('menu.item.copy'), 'menu.item.paste': t('menu.item.paste'),
       'menu.item.find': t('menu.item.find'), 'menu.item.replace': t('menu.item.replace'), 'menu.item.selectAllOccurrences': t('menu.item.selectAllOccurrences'),
       'menu.item.theme.dark': t('menu.item.theme.dark'), 'menu.item.theme.light': t('menu.item.theme.light'), 'menu.item.theme.loadCustom': t('menu.item.theme.loadCustom'),
@@ -454,10 +564,138 @@ export class App {
         tabsStore.createTab(name, path, '');
         // viewer will render on active switch
       } else {
-        const content = await tauriApi.readFile(path);
-        tabsStore.createTab(name, path, content);
+        const { content, version, format } = await readTextFile(path);
+        const tab = tabsStore.createTab(name, path, content);
+        tabsStore.updateTab(tab.id, { version, format });
+      }
+    } catch (e) { console.error('Failed to open file:', e); alert(t('alert.failedToOpenFile', { error: tauriApi.errorMessage(e) })); }
+  }
+
+  // ---------- Workspaces ----------
+  async openWorkspace() {
+    const path = await tauriApi.openFileDialog([{ name: 'Editrion Workspace', extensions: ['editrion-workspace'] }]);
+    if (path) await this.openWorkspaceAt(path);
+  }
+
+  private async openWorkspaceAt(path: string) {
+    try {
+      // Workspace files are meant to be shared, so unsaved buffers never go into them
+      if (!await this.settleUnsavedTabs()) return;
+      const opened = await tauriApi.switchWorkspace(path, this.collectSession(false, true));
+      this.dropAllTabs();
+      this.applyWorkspaceDefaults(opened);
+      this.fileExplorer.setRoots(opened.workspace.folders.map(f => f.path));
+      if (opened.workspace.session) await this.restoreTabs(opened.workspace.session, true);
+      this.scheduleSessionSave();
+    } catch (e) { console.error('Failed to open workspace:', e); alert(t('alert.failedToOpenWorkspace', { error: tauriApi.errorMessage(e) })); }
+  }
+
+  async saveWorkspaceAs() {
+    try {
+      const path = await tauriApi.saveFileDialog('workspace.editrion-workspace'); if (!path) return;
+      // Keep settings and excludes of the workspace being saved under a new name
+      const current = await tauriApi.currentWorkspace().catch(() => null);
+      const workspace: Workspace = {
+        folders: this.fileExplorer.getRoots().map(p => ({ path: p })),
+        settings: current?.workspace.settings ?? {},
+        exclude: current?.workspace.exclude ?? [],
+        ai: { ...current?.workspace.ai, model: this.aiOverrides.model ?? null, effort: this.aiOverrides.effort ?? null },
+        session: this.collectSession(false, true),
+      };
+      const saved = await tauriApi.saveWorkspace(workspace, path);
+      this.workspacePath = saved.path;
+      this.scheduleSessionSave();
+    } catch (e) { console.error('Failed to save workspace:', e); alert(t('alert.failedToSaveWorkspace', { error: tauriApi.errorMessage(e) })); }
+  }
+
+  /** Close the workspace with its tabs and folders, remembering its session in its file */
+  async closeWorkspace() {
+    if (!this.workspacePath) return;
+    try {
+      if (!await this.settleUnsavedTabs()) return;
+      await tauriApi.closeWorkspace(this.collectSession(false, true));
+      this.workspacePath = null;
+      this.dropAllTabs();
+      this.fileExplorer.setRoots([]);
+      this.scheduleSessionSave();
+    } catch (e) { console.error('Failed to close workspace:', e); alert(t('alert.failedToSaveWorkspace', { error: tauriApi.errorMessage(e) })); }
+  }
+
+  // Recent workspaces modal: open one, or take it off the list
+  private async openRecentWorkspaces() {
+    let recent: RecentWorkspace[] = [];
+    try { recent = await tauriApi.listRecentWorkspaces(); } catch (e) { console.error('Failed to list recent workspaces', e); }
+
+    const overlay = document.createElement('div');
+    overlay.className = 'modal-overlay show';
+    const modal = document.createElement('div'); modal.className = 'modal';
+    const title = document.createElement('h3'); title.textContent = t('workspace.recent.title');
+    const list = document.createElement('div'); list.style.display = 'flex'; list.style.flexDirection = 'column'; list.style.gap = '6px';
+    list.style.maxHeight = '60vh'; list.style.overflow = 'auto'; list.style.marginTop = '8px';
+    const cleanup = () => { overlay.remove(); };
+
+    const render = () => {
+      list.replaceChildren();
+      if (recent.length === 0) {
+        const empty = document.createElement('div'); empty.textContent = t('workspace.recent.empty');
+        list.appendChild(empty);
+        return;
+      }
+      for (const entry of recent) {
+        const row = document.createElement('div'); row.style.display = 'flex'; row.style.gap = '8px'; row.style.alignItems = 'center';
+        const open = document.createElement('button'); open.className = 'btn'; open.style.flex = '1 1 auto'; open.style.textAlign = 'left';
+        open.textContent = entry.name; open.title = entry.path;
+        const remove = document.createElement('button'); remove.className = 'btn'; remove.textContent = '×'; remove.title = t('workspace.recent.remove');
+        row.append(open, remove);
+        open.addEventListener('click', async () => { cleanup(); await this.openWorkspaceAt(entry.path); });
+        remove.addEventListener('click', async () => {
+          try { recent = await tauriApi.removeRecentWorkspace(entry.path); render(); }
+          catch (e) { console.error('Failed to remove recent workspace', e); }
+        });
+        list.appendChild(row);
       }
-    } catch (e) { console.error('Failed to open file:', e); alert(t('alert.failedToOpenFile', { error: String(e) })); }
+    };
+    render();
+
+    const actions = document.createElement('div'); actions.className = 'actions'; actions.style.marginTop = '10px';
+    const btnCancel = document.createElement('button'); btnCancel.className = 'btn'; btnCancel.textContent = t('button.cancel');
+    actions.append(btnCancel);
+    btnCancel.addEventListener('click', cleanup);
+    overlay.addEventListener('click', (e) => { if (e.target === overlay) cleanup(); });
+
+    modal.append(title, list, actions);
+    overlay.append(modal);
+    document.body.append(overlay);
+  }
+
+  private applyWorkspaceDefaults(opened: OpenedWorkspace) {
+    this.workspacePath = opened.path;
+    const ai = opened.workspace.ai;
+    if (ai.model) this.aiOverrides.model = ai.model;
+    if (ai.effort && ['minimal', 'low', 'medium', 'high'].includes(ai.effort)) {
+      this.aiOverrides.effort = ai.effort as 'minimal' | 'low' | 'medium' | 'high';
+    }
+  }
+
+  /** Ask what to do with unsaved tabs; false when the user cancels or a save fails */
+  private async settleUnsavedTabs(): Promise<boolean> {
+    const dirty = tabsStore.getState().tabs.filter(t => t.isDirty);
+    if (dirty.length === 0) return true;
+    const choice = await this.askUnsavedMulti(dirty.length);
+    if (choice === 'cancel') return false;
+    if (choice === 'save') {
+      for (const tab of dirty) { tabsStore.setActiveTab(tab.id); await this.saveActiveFile(); if (tabsStore.getTab(tab.id)?.isDirty) return false; }
+    }
+    return true;
+  }
+
+  /** Close every tab without prompting; callers settle unsaved buffers first */
+  private dropAllTabs() {
+    for (const tab of tabsStore.getState().tabs) {
+      try { document.getElementById(`editor-${tab.id}`)?.remove(); } catch {}
+      tabsStore.closeTab(tab.id);
+    }
+    this.updateWelcomeState();
   }
 
   async saveActiveFile() {
@@ -471,10 +709,12 @@ export class App {
         tab.path = filePath; tabsStore.updateTab(tab.id, { path: filePath, name: this.basename(filePath) });
       }
       const content = tab.editor.getValue();
-      await tauriApi.writeFile(filePath, content);
+      const version = await writeUnlessChanged(filePath, content, tab.format, tab.version);
+      if (!version) return;
+      tabsStore.updateTab(tab.id, { version });
       tabsStore.saveTab(tab.id, content);
-      if (this.draftsDir) { try { await tauriApi.removeFile(`${this.draftsDir}/${tab.id}.json`); } catch {} }
-    } catch (e) { console.error('saveActiveFile failed', e); }
+      if (filePath === this.settingsPath) await reloadSettings();
+    } catch (e) { console.error('saveActiveFile failed', e); alert(t('alert.failedToSaveFile', { error: tauriApi.errorMessage(e) })); }
   }
 
   async saveActiveFileAs() {
@@ -484,11 +724,10 @@ export class App {
       const saved = await tauriApi.saveFileDialog(defaultName); if (!saved) return;
       const filePath = saved;
       const content = tab.editor.getValue();
-      await tauriApi.writeFile(filePath, content);
-      tabsStore.updateTab(tab.id, { path: filePath, name: this.basename(filePath) });
+      const version = await writeTextFile(filePath, content, tab.format);
+      tabsStore.updateTab(tab.id, { path: filePath, name: this.basename(filePath), version });
       tabsStore.saveTab(tab.id, content);
-      if (this.draftsDir) { try { await tauriApi.removeFile(`${this.draftsDir}/${tab.id}.json`); } catch {} }
-    } catch (e) { console.error('saveActiveFileAs failed', e); }
+    } catch (e) { console.error('saveActiveFileAs failed', e); alert(t('alert.failedToSaveFile', { error: tauriApi.errorMessage(e) })); }
   }
 
   async closeTab(tabId: string) {
@@ -497,10 +736,7 @@ export class App {
       const choice = await this.askUnsavedSingle(tab.name);
       if (choice === 'cancel') return;
       if (choice === 'save') { await this.saveActiveFile(); }
-      if (choice === 'discard' && this.draftsDir) { try { await tauriApi.removeFile(`${this.draftsDir}/${tab.id}.json`); } catch {} }
     }
-    // Always attempt to remove draft file on close (avoid restoring closed tabs)
-    if (this.draftsDir) { try { await tauriApi.removeFile(`${this.draftsDir}/${tab.id}.json`); } catch {} }
     // Remove editor DOM for this tab if present
     try { const el = document.getElementById(`editor-${tabId}`); if (el) el.remove(); } catch {}
     tabsStore.closeTab(tabId);
@@ -509,8 +745,6 @@ export class App {
   async closeAllTabs() {
     const ids = tabsStore.getState().tabs.map(t => t.id);
     for (const id of ids) { await this.closeTab(id); }
-    // Ensure drafts dir is cleared so nothing is restored next launch
-    if (this.draftsDir) { try { await tauriApi.clearDir(this.draftsDir); } catch {} }
   }
 
   async closeOtherTabs(keepId: string) {
@@ -530,18 +764,17 @@ export class App {
 
   private async handleQuitRequest() {
     const dirty = tabsStore.getState().tabs.filter(t => t.isDirty);
-    if (dirty.length === 0) { try { localStorage.setItem('editrion.cleanExit', 'true'); } catch {} await tauriApi.quitApp(); return; }
+    if (dirty.length === 0) { await this.saveSession(true); await tauriApi.quitApp(); return; }
     const choice = await this.askUnsavedMulti(dirty.length);
     if (choice === 'cancel') return;
     if (choice === 'save') {
       for (const tab of dirty) { tabsStore.setActiveTab(tab.id); await this.saveActiveFile(); if (tab.isDirty) return; }
-      try { localStorage.setItem('editrion.cleanExit', 'true'); } catch {}
+      await this.saveSession(true);
       await tauriApi.quitApp();
       return;
     }
-    // discard: clear drafts dir so closed tabs are not restored next launch
-    if (this.draftsDir) { try { await tauriApi.clearDir(this.draftsDir); } catch {} }
-    try { localStorage.setItem('editrion.cleanExit', 'true'); } catch {}
+    // discard: keep the tabs but not their unsaved changes
+    await this.saveSession(true, true);
     await tauriApi.quitApp();
   }
 
@@ -573,7 +806,7 @@ export class App {
       row.style.display = 'flex'; row.style.gap = '12px';
       const efforts: Array<'minimal'|'low'|'medium'|'high'> = ['minimal','low','medium','high'];
       let eff: 'minimal'|'low'|'medium'|'high' = 'minimal';
-      try { const raw = localStorage.getItem('editrion.aiOverrides'); const saved = raw ? JSON.parse(raw) : {}; if (saved.effort && efforts.includes(saved.effort)) eff = saved.effort; } catch {}
+      const savedEffort = getSettings().ai.effort; if (savedEffort && efforts.includes(savedEffort)) eff = savedEffort;
       efforts.forEach(v => {
         const lbl = document.createElement('label'); lbl.style.display = 'flex'; lbl.style.alignItems = 'center'; lbl.style.gap = '6px';
         const rb = document.createElement('input'); rb.type = 'radio'; rb.name = 'eff_default'; rb.value = v; if (eff === v) rb.checked = true; rb.addEventListener('change', () => { eff = v; });
@@ -592,7 +825,7 @@ export class App {
       const cleanup = () => { overlay.remove(); resolve(); };
       btnCancel.addEventListener('click', cleanup);
       btnSave.addEventListener('click', () => {
-        try { const raw = localStorage.getItem('editrion.aiOverrides'); const saved = raw ? JSON.parse(raw) : {}; saved.effort = eff; localStorage.setItem('editrion.aiOverrides', JSON.stringify(saved)); } catch {}
+        setSetting('ai.effort', eff).catch(e => console.error('Failed to save reasoning effort', e));
         cleanup();
       });
       overlay.addEventListener('click', (e) => { if (e.target === overlay) cleanup(); });
@@ -603,22 +836,46 @@ export class App {
   private isImagePath(path: string): boolean {
     return /(\.png|\.jpe?g|\.gif|\.webp|\.bmp|\.svg)$/i.test(path);
   }
-  private saveAiOverrides() { try { localStorage.setItem('editrion.aiOverrides', JSON.stringify(this.aiOverrides)); } catch {} }
-  private setAiOverrideModel(model: string) { this.aiOverrides.model = model; this.saveAiOverrides(); }
-  private setAiOverrideEffort(effort: 'minimal'|'low'|'medium'|'high') { this.aiOverrides.effort = effort; this.saveAiOverrides(); }
-  private setAiOverrideSummary(summary: 'auto'|'concise'|'detailed'|'none') { this.aiOverrides.summary = summary; this.saveAiOverrides(); }
-  private setAiOverrideVerbosity(verbosity: 'low'|'medium'|'high') { this.aiOverrides.verbosity = verbosity; this.saveAiOverrides(); }
+  private saveAiOverride(key: 'model' | 'effort' | 'summary' | 'verbosity') {
+    setSetting(`ai.${key}`, this.aiOverrides[key] ?? null).catch(e => console.error(`Failed to save ai.${key}`, e));
+  }
+  private setAiOverrideModel(model: string) { this.aiOverrides.model = model; this.saveAiOverride('model'); }
+  private setAiOverrideEffort(effort: 'minimal'|'low'|'medium'|'high') { this.aiOverrides.effort = effort; this.saveAiOverride('effort'); }
+  private setAiOverrideSummary(summary: 'auto'|'concise'|'detailed'|'none') { this.aiOverrides.summary = summary; this.saveAiOverride('summary'); }
+  private setAiOverrideVerbosity(verbosity: 'low'|'medium'|'high') { this.aiOverrides.verbosity = verbosity; this.saveAiOverride('verbosity'); }
+
+  private applyThemeMode(settings: Settings) {
+    const isLight = settings.themeMode ? settings.themeMode === 'light' : settings.theme.includes('light');
+    this.appliedThemeMode = isLight ? 'light' : 'dark';
+    themeManager.setTheme(this.appliedThemeMode);
+  }
+
+  private onSettingsChanged(settings: Settings) {
+    if (settings.themeMode !== this.appliedThemeMode) this.applyThemeMode(settings);
+    if (getLocale() !== this.appliedLocale) { this.appliedLocale = getLocale(); this.updateNativeMenuLabels(); }
+    this.reportSettingsProblems();
+  }
+
+  /** Point at new mistakes in config files being edited in a tab; the last valid version stays in effect */
+  private reportSettingsProblems() {
+    const open = new Set(tabsStore.getState().tabs.map(tab => tab.path).filter(Boolean));
+    const current = new Set<string>();
+    for (const p of getSettingsProblems()) {
+      const id = `${p.file}:${p.line}:${p.column}:${p.message}`;
+      current.add(id);
+      if (this.reportedProblems.has(id) || !open.has(p.file)) continue;
+      alert(t('alert.invalidConfigFile', { name: this.basename(p.file), line: p.line ?? '?', column: p.column ?? '?', message: p.message }));
+    }
+    this.reportedProblems = current;
+  }
 
-  resetAllSettings() {
+  async resetAllSettings() {
     try {
       const ok = confirm(t('confirm.resetSettings') || 'Reset all settings to defaults?');
       if (!ok) return;
-      const keys: string[] = []; for (let i = 0; i < localStorage.length; i++) { const k = localStorage.key(i); if (k) keys.push(k); }
-      for (const k of keys) { if (k === 'editrion-settings' || k.startsWith('editrion.')) { try { localStorage.removeItem(k); } catch {} } }
-      themeManager.setTheme('dark');
-      localStorage.setItem('editrion.theme', 'dark');
-      localStorage.setItem('editrion.themeMode', 'dark');
-      setLocale('en'); applyTranslations(); this.updateNativeMenuLabels();
+      // Theme and language follow through the settings-changed event
+      await resetSettings();
+      this.aiOverrides = {};
       try { this.fileExplorer.clearAllRoots(); } catch {}
       // No success alert per UX: one confirm is enough
     } catch (e) { console.error('Failed to reset settings:', e); }
@@ -697,9 +954,9 @@ export class App {
     const title = document.createElement('h3'); title.textContent = t('ai.settings.manageTemplates') || 'Manage Templates';
     const body = document.createElement('div'); body.style.maxHeight = '60vh'; body.style.overflow = 'auto'; body.style.marginTop = '8px';
 
-    // Load current macros via Editor API or localStorage fallback
+    // Load current macros via Editor API or settings fallback
     let macros: Array<{ id?: string; name: string; instruction: string; effort?: any }> = [];
-    try { macros = this.editor.getMacros(); } catch { try { const raw = localStorage.getItem('editrion.aiTemplates'); macros = raw ? JSON.parse(raw) : []; } catch {} }
+    try { macros = this.editor.getMacros(); } catch { macros = getSettings().ai.templates.map(m => ({ ...m })); }
     if (!Array.isArray(macros)) macros = [];
 
     const list = document.createElement('div'); list.style.display = 'flex'; list.style.flexDirection = 'column'; list.style.gap = '10px';
@@ -753,7 +1010,7 @@ export class App {
     btnCancel2.addEventListener('click', cleanup);
     btnSave2.addEventListener('click', () => {
       try { this.editor.setMacros(macros as any); } catch {
-        try { localStorage.setItem('editrion.aiTemplates', JSON.stringify(macros)); } catch {}
+        setSetting('ai.templates', macros).catch(e => console.error('Failed to save AI templates', e));
       }
       cleanup();
     });
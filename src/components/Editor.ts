@@ -1,12 +1,14 @@
 import * as monaco from 'monaco-editor';
 import { themeManager } from '../services/themeManager';
-import type { Tab } from '../types';
+import type { AiCompleteEvent, AiEventPayload, AiStreamEvent, Tab } from '../types';
 import { tabsStore } from '../store/tabsStore';
 import { appStore } from '../store/appStore';
 import { tauriApi } from '../services/tauriApi';
 import { t } from '../services/i18n';
 import { listen } from '@tauri-apps/api/event';
 import { getShortcuts, toMonacoKeyChord } from '../services/shortcuts';
+import { getSettings, onSettingsChanged, setSetting } from '../services/settings';
+import { readTextFile, writeUnlessChanged } from '../services/fileSave';
 
 export class Editor {
   private container: HTMLElement;
@@ -21,12 +23,15 @@ export class Editor {
   constructor(container: HTMLElement) {
     this.container = container;
     this.setupResizeObserver();
+    // The backend keeps these in sync with ai_templates.json next to the Codex config
     this.aiTemplates = this.loadMacros();
-    // Try loading macros from persistent config file too (works in dev/prod)
-    this.tryLoadMacrosFromFile().then(() => {
-      // After async load, ensure actions exist on all editors
+    // Templates edited in another window, in settings.json or in ai_templates.json
+    onSettingsChanged(settings => {
+      const templates = Array.isArray(settings.ai.templates) ? settings.ai.templates : [];
+      if (JSON.stringify(templates) === JSON.stringify(this.aiTemplates)) return;
+      this.aiTemplates = templates.map(m => ({ ...m }));
       this.registerMacrosOnAllEditors();
-    }).catch(()=>{});
+    });
   }
 
   // Breaks input into small segments preferring whitespace boundaries
@@ -45,28 +50,9 @@ export class Editor {
     return out;
   }
 
-  // Attempts to strip system/meta lines; keep only plain output (preserve spaces and blank lines)
-  private sanitizeAiChunk(s: string): string {
-    s = s.replace(/\r\n?/g, '\n').replace(/\x1b\[[0-9;]*m/g, '');
-    const shouldRemove = (line: string): boolean => {
-      const ts = line.trimStart();
-      if (ts.startsWith('```')) return true;
-      if (/^[-=_]{3,}\s*$/i.test(ts)) return true; // separators like --------
-      if (/^---\s*INPUT\s+START\s*---/i.test(ts)) return true;
-      if (/^---\s*INPUT\s+END\s*---/i.test(ts)) return true;
-      if (/^Return only the transformed text\.?$/i.test(ts)) return true;
-      if (/^User instructions\s*:/i.test(ts)) return true;
-      if (/^(?:[-=_]{3,}\s*)?(workdir|provider|approval|sandbox|reasoning(\s+effort|\s+summaries)?|usage|model|tokens?|runId)\s*[:=]/i.test(ts)) return true;
-      if (/^\[[0-9]{4}-[0-9]{2}-[0-9]{2}[^\]]*\]/.test(ts)) return true; // timestamp/meta lines
-      if (/^codex\s*$/i.test(ts)) return true;
-      return false;
-    };
-    const out: string[] = [];
-    for (const line of s.split('\n')) {
-      if (shouldRemove(line)) continue;
-      out.push(line);
-    }
-    return out.join('\n');
+  // Models sometimes wrap the answer in a Markdown code block; drop the fence lines
+  private stripCodeFences(s: string): string {
+    return s.replace(/\r\n?/g, '\n').split('\n').filter(line => !line.trimStart().startsWith('```')).join('\n');
   }
   createEditor(tab: Tab, content: string = ''): monaco.editor.IStandaloneCodeEditor {
     // Create a dedicated DOM element per tab and keep it in the editor container (hidden by default)
@@ -134,16 +120,12 @@ export class Editor {
   }
 
   private loadMacros(): Array<{ id: string; name: string; instruction: string; effort?: any }> {
-    try {
-      const raw = localStorage.getItem('editrion.aiTemplates');
-      const arr = raw ? JSON.parse(raw) : [];
-      if (Array.isArray(arr)) return arr;
-    } catch {}
-    return [];
+    const arr = getSettings().ai.templates;
+    return Array.isArray(arr) ? arr.map(m => ({ ...m })) : [];
   }
 
   private saveMacros() {
-    try { localStorage.setItem('editrion.aiTemplates', JSON.stringify(this.aiTemplates)); } catch {}
+    setSetting('ai.templates', this.aiTemplates).catch(e => console.error('Failed to save AI templates', e));
     // Also persist to app config dir so it's available across sessions and build/dev origins
     this.trySaveMacrosToFile().catch(()=>{});
   }
@@ -166,20 +148,6 @@ export class Editor {
     this.registerMacrosOnAllEditors();
   }
 
-  private async tryLoadMacrosFromFile(): Promise<void> {
-    try {
-      const cfgPath = await tauriApi.getCodexConfigPath();
-      const dir = cfgPath.replace(/[/\\][^/\\]*$/, '');
-      const path = `${dir}/ai_templates.json`;
-      const raw = await tauriApi.readFile(path);
-      const arr = JSON.parse(raw);
-      if (Array.isArray(arr)) {
-        this.aiTemplates = arr;
-        try { localStorage.setItem('editrion.aiTemplates', JSON.stringify(arr)); } catch {}
-      }
-    } catch {}
-  }
-
   private async trySaveMacrosToFile(): Promise<void> {
     try {
       const cfgPath = await tauriApi.getCodexConfigPath();
@@ -236,9 +204,7 @@ export class Editor {
   }
 
   private registerMacroActions(editor: monaco.editor.IStandaloneCodeEditor, tab: Tab) {
-    const defaultEffort = (() => {
-      try { const raw = localStorage.getItem('editrion.aiOverrides'); const saved = raw ? JSON.parse(raw) : {}; return saved.effort as any; } catch { return undefined; }
-    })();
+    const defaultEffort = getSettings().ai.effort ?? undefined;
     for (const tpl of this.aiTemplates) {
       const actionId = `codex.macro.${tpl.id}`;
       // Avoid duplicate registration per editor instance
@@ -341,38 +307,10 @@ export class Editor {
           const unsubs: Array<() => void> = [];
           const addUnsub = (fn: () => void) => unsubs.push(fn);
 
-          let metaDone = false;
+          // Text of the current, incomplete line; fences can only be recognised once a line is complete
           let pending = '';
-          const onStream = await listen<any>('codex-stream', (ev) => {
-            const p = ev.payload as { runId?: string; channel?: 'stdout'|'stderr'; data?: string };
-            if (!p || p.runId !== runId) return;
-            if (p.channel !== 'stdout') return;
-            let chunk = (p.data || ''); if (!chunk) return;
-            chunk = chunk.replace(/\r\n?/g, '\n');
-            pending += chunk;
-            let textOut = '';
-            if (!metaDone) {
-              const m = pending.match(/---\s*INPUT\s+END\s*---/i);
-              if (!m) {
-                // keep only tail to limit memory, markers won't be longer than 200 chars
-                pending = pending.slice(Math.max(0, pending.length - 512));
-                return;
-              }
-              // Drop everything up to and including the END marker
-              const idx = (m.index ?? 0) + m[0].length;
-              pending = pending.slice(idx);
-              // Also drop optional guidance line and timestamp/codex headers immediately following
-              // Remove one guidance line
-              pending = pending.replace(/^\s*Return only the transformed text\.?\s*/i, '');
-              // Remove leading timestamp lines and single 'codex' line
-              pending = pending.replace(/^\[[^\]]+\].*\n?/g, '');
-              pending = pending.replace(/^\s*codex\s*\n?/i, '');
-              metaDone = true;
-            }
-            if (metaDone && pending) {
-              textOut = this.sanitizeAiChunk(pending);
-              pending = '';
-            }
+          const insertStreamed = (text: string) => {
+            let textOut = text;
             if (!textOut) return;
             if (!insertedAny) {
               const trimmedLeft = textOut.replace(/^\s+/, '');
@@ -393,15 +331,36 @@ export class Editor {
               if (!userMoved && model) { const pos = model.getPositionAt(insertOffset); programmaticEdit = true; editor.setPosition(pos); programmaticEdit = false; }
               if (textOut.length > 0) insertedAny = true;
             }
+          };
+
+          const onEvent = await listen<AiEventPayload>('ai-event', (ev) => {
+            const p = ev.payload;
+            if (!p || p.runId !== runId || p.event.type !== 'textDelta') return;
+            pending += p.event.text.replace(/\r\n?/g, '\n');
+            const end = pending.lastIndexOf('\n');
+            if (end < 0) return;
+            const lines = pending.slice(0, end + 1);
+            pending = pending.slice(end + 1);
+            insertStreamed(this.stripCodeFences(lines));
+          });
+          addUnsub(() => { onEvent(); });
+
+          const onStream = await listen<AiStreamEvent>('ai-stream', (ev) => {
+            const p = ev.payload;
+            if (!p || p.runId !== runId) return;
+            // Progress and warnings: keep the latest one at hand on the status label
+            if (p.channel === 'stderr' && p.data.trim()) label.title = p.data.trim();
           });
           addUnsub(() => { onStream(); });
 
-          const onDone = await listen<any>('codex-complete', (ev) => {
-            const p = ev.payload as { runId?: string; ok?: boolean; output?: string; error?: string };
+          const onDone = await listen<any>('ai-complete', (ev) => {
+            const p = ev.payload as AiCompleteEvent;
             if (!p || p.runId !== runId) return;
             unsubs.forEach(fn => fn());
             if (p.ok) {
-              const result = this.sanitizeAiChunk((p.output ?? ''));
+              insertStreamed(this.stripCodeFences(pending));
+              pending = '';
+              const result = this.stripCodeFences(p.text);
               if (!model) return;
               const base = outBuf.length > 0 ? outBuf : result;
               const finalText = base.replace(/\r\n/g, '\n').replace(/\r/g, '\n');
@@ -435,10 +394,10 @@ export class Editor {
                 }
               }
             }
-            else {
-              const err = (p.error ?? '').toString();
-              console.warn('Codex failed:', err);
-              alert('AI failed to run. Please ensure the Codex CLI is installed and available in PATH.\n\n' + err);
+            else if (p.status === 'failed') {
+              const err = p.stderr || p.stdout;
+              console.warn('AI run failed:', err);
+              alert('AI failed to run. Please ensure the selected AI CLI is installed and available in PATH.\n\n' + err);
             }
             try { document.body.removeChild(streamBox); } catch {}
             try { cursorDisp.dispose(); } catch {}
@@ -446,7 +405,7 @@ export class Editor {
           addUnsub(() => { onDone(); });
 
           btnCancel.addEventListener('click', async () => {
-            try { await tauriApi.codexCancel(runId); } catch {}
+            try { await tauriApi.aiCancel(runId); } catch {}
             try { document.body.removeChild(streamBox); } catch {}
             unsubs.forEach(fn => fn());
           });
@@ -454,7 +413,7 @@ export class Editor {
           // Kick off
           const cfg: Record<string, string> = {};
           if (effValue) cfg['model_reasoning_effort'] = effValue;
-          await tauriApi.codexExecStream(prompt, cwd, runId, undefined, cfg);
+          await tauriApi.aiExecStream(getSettings().ai.provider, prompt, cwd, runId, undefined, cfg);
         } catch (e) {
           console.error('AI action failed:', e);
         }
@@ -519,11 +478,8 @@ export class Editor {
       effRow.style.gap = '12px';
       const efforts: Array<'minimal'|'low'|'medium'|'high'> = ['minimal','low','medium','high'];
       let effValue: ''|'minimal'|'low'|'medium'|'high' = '';
-      try {
-        const raw = localStorage.getItem('editrion.aiOverrides');
-        const saved = raw ? JSON.parse(raw) : {};
-        if (saved && typeof saved.effort === 'string' && efforts.includes(saved.effort)) effValue = saved.effort;
-      } catch {}
+      const savedEffort = getSettings().ai.effort;
+      if (savedEffort && efforts.includes(savedEffort)) effValue = savedEffort;
       if (effValue === '') effValue = 'minimal';
       for (const v of efforts) {
         const lbl = document.createElement('label');
@@ -628,12 +584,7 @@ export class Editor {
       btnCancel.addEventListener('click', () => cleanup(null));
       btnRun.addEventListener('click', () => {
         btnRun.disabled = true; btnCancel.disabled = true; instr.disabled = true;
-        try {
-          const raw = localStorage.getItem('editrion.aiOverrides');
-          const saved = raw ? JSON.parse(raw) : {};
-          saved.effort = effValue || 'minimal';
-          localStorage.setItem('editrion.aiOverrides', JSON.stringify(saved));
-        } catch {}
+        setSetting('ai.effort', effValue || 'minimal').catch(e => console.error('Failed to save reasoning effort', e));
         cleanup({ instruction: instr.value || 'Translate to Ukrainian', effort: effValue || undefined });
       });
       overlay.addEventListener('click', (e) => { if (e.target === overlay) cleanup(null); });
@@ -728,11 +679,13 @@ export class Editor {
 
     try {
       const content = this.currentEditor.getValue();
-      await tauriApi.writeFile(tab.path, content);
+      const version = await writeUnlessChanged(tab.path, content, tab.format, tab.version);
+      if (!version) return;
+      tabsStore.updateTab(tab.id, { version });
       tabsStore.saveTab(tab.id, content);
     } catch (error) {
       console.error('Failed to save file:', error);
-      // Could show notification or emit error event
+      alert(t('alert.failedToSaveFile', { error: tauriApi.errorMessage(error) }));
     }
   }
 
@@ -745,7 +698,14 @@ export class Editor {
         // Load file content lazily if not preloaded
         this.loadFileContent(tab);
       }
-      this.createEditor(tab, content);
+      const editor = this.createEditor(tab, content);
+      if (tab.pendingView) {
+        const view = tab.pendingView;
+        editor.setPosition({ lineNumber: view.line, column: view.column });
+        editor.setScrollTop(view.scrollTop);
+        editor.setScrollLeft(view.scrollLeft);
+        tabsStore.updateTab(tab.id, { pendingView: undefined });
+      }
     } else {
       // Ensure editor element exists for this tab
       const node = tab.editor.getDomNode();
@@ -796,11 +756,11 @@ export class Editor {
     if (!tab.path) return;
 
     try {
-      const content = await tauriApi.readFile(tab.path);
+      const { content, version, format } = await readTextFile(tab.path);
       if (tab.editor) {
         tab.editor.setValue(content);
       }
-      tabsStore.updateTab(tab.id, { originalContent: content });
+      tabsStore.updateTab(tab.id, { originalContent: content, version, format });
     } catch (error) {
       console.error('Failed to load file:', error);
     }
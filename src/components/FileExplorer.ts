@@ -1,5 +1,9 @@
 import { tauriApi } from '../services/tauriApi';
-import type { FileItem } from '../types';
+import { getSettings, setSetting } from '../services/settings';
+import type { DirEntry, FsEventPayload, ReadDirOptions } from '../types';
+
+// Keep build output and dependencies (target/, node_modules/) out of the tree
+const TREE_OPTIONS: ReadDirOptions = { hideIgnored: true };
 
 export class FileExplorer {
   private container: HTMLElement;
@@ -10,6 +14,7 @@ export class FileExplorer {
   private contextTargetPath: string | null = null;
   private roots: string[] = [];
   private onOpenFile: (path: string, name: string) => void;
+  onRootsChanged?: () => void;
 
   constructor(onOpenFile: (path: string, name: string) => void) {
     this.container = document.getElementById('folder-tree')!;
@@ -63,8 +68,9 @@ export class FileExplorer {
     const roots = Array.from(this.container.querySelectorAll<HTMLElement>('[data-root="true"]'));
     for (const el of roots) {
       if (el.dataset.path === path) {
-        const next = el.nextElementSibling as HTMLElement | null;
-        if (next && next.classList.contains('subtree') && next.dataset.parentPath === path) next.remove();
+        const sub = this.subtreeOf(el);
+        this.unwatchExpanded(el, sub);
+        sub?.remove();
         el.remove();
         break;
       }
@@ -73,16 +79,15 @@ export class FileExplorer {
     this.saveRoots();
   }
 
-  private renderTree(entries: FileItem[], container: HTMLElement) {
+  private renderTree(entries: DirEntry[], container: HTMLElement) {
     container.innerHTML = '';
-    entries.sort((a, b) => (a.is_dir === b.is_dir) ? a.name.localeCompare(b.name) : (a.is_dir ? -1 : 1));
     const frag = document.createDocumentFragment();
     for (const entry of entries) {
       const el = document.createElement('div');
-      el.className = entry.is_dir ? 'folder-item collapsed' : 'file-item';
+      el.className = entry.isDir ? 'folder-item collapsed' : 'file-item';
       el.textContent = entry.name;
       el.dataset.path = entry.path;
-      if (entry.is_dir) el.addEventListener('click', (e) => { e.stopPropagation(); this.toggleFolder(el); });
+      if (entry.isDir) el.addEventListener('click', (e) => { e.stopPropagation(); this.toggleFolder(el); });
       else el.addEventListener('click', () => this.onOpenFile(entry.path, entry.name));
       frag.appendChild(el);
     }
@@ -92,28 +97,69 @@ export class FileExplorer {
   private async toggleFolder(element: HTMLElement) {
     const path = element.dataset.path!;
     const isExpanded = element.classList.contains('expanded');
-    const next = element.nextElementSibling as HTMLElement | null;
-    const hasSubtree = next && next.classList.contains('subtree') && next.dataset.parentPath === path;
+    const sub = this.subtreeOf(element);
     if (isExpanded) {
-      element.classList.remove('expanded'); element.classList.add('collapsed'); if (hasSubtree && next) next.remove(); return;
+      this.unwatchExpanded(element, sub);
+      element.classList.remove('expanded'); element.classList.add('collapsed'); sub?.remove(); return;
     }
     element.classList.remove('collapsed'); element.classList.add('expanded');
     try {
-      const entries = await tauriApi.readDir(path);
+      const entries = await tauriApi.readDir(path, TREE_OPTIONS);
       const sub = document.createElement('div');
       sub.className = 'subtree';
       sub.dataset.parentPath = path;
       sub.style.paddingLeft = '16px';
       this.renderTree(entries, sub);
       element.parentNode!.insertBefore(sub, element.nextSibling);
+      // Expanded folders follow changes on disk
+      tauriApi.watchPaths([path]).catch(e => console.warn('Failed to watch folder', e));
     } catch (e) { console.error('Failed to load subdirectory', e); }
   }
 
+  private subtreeOf(element: HTMLElement): HTMLElement | null {
+    const next = element.nextElementSibling as HTMLElement | null;
+    return next && next.classList.contains('subtree') && next.dataset.parentPath === element.dataset.path ? next : null;
+  }
+
+  private findFolder(path: string): HTMLElement | null {
+    return this.container.querySelector<HTMLElement>(`.folder-item[data-path="${CSS.escape(path)}"]`);
+  }
+
+  /** Stop watching an expanded folder and every folder expanded inside it */
+  private unwatchExpanded(element: HTMLElement, sub: HTMLElement | null) {
+    if (!element.classList.contains('expanded')) return;
+    const paths = [element.dataset.path!];
+    sub?.querySelectorAll<HTMLElement>('.folder-item.expanded').forEach(el => paths.push(el.dataset.path!));
+    tauriApi.unwatchPaths(paths).catch(e => console.warn('Failed to unwatch folders', e));
+  }
+
+  /** Refresh expanded folders whose listing changed on disk */
+  onFsEvent(event: FsEventPayload) {
+    const dirs = event.kind === 'renamed' ? [this.dirname(event.from), this.dirname(event.to)] : [event.path];
+    for (const dir of new Set(dirs)) this.refreshFolder(dir);
+  }
+
+  private async refreshFolder(path: string) {
+    const element = this.findFolder(path);
+    const sub = element && element.classList.contains('expanded') ? this.subtreeOf(element) : null;
+    if (!sub) return;
+    try {
+      const entries = await tauriApi.readDir(path, TREE_OPTIONS);
+      // Folders expanded inside stay expanded; they are watched again on expanding
+      const reopen = Array.from(sub.querySelectorAll<HTMLElement>('.folder-item.expanded')).map(el => el.dataset.path!);
+      if (reopen.length) tauriApi.unwatchPaths(reopen).catch(e => console.warn('Failed to unwatch folders', e));
+      this.renderTree(entries, sub);
+      for (const child of reopen) {
+        const el = this.findFolder(child);
+        if (el) await this.toggleFolder(el);
+      }
+    } catch (e) { console.warn('Failed to refresh folder', path, e); }
+  }
+
   private restoreRoots() {
     try {
-      const raw = localStorage.getItem('editrion.projectRoots');
-      if (!raw) return;
-      const paths: string[] = JSON.parse(raw);
+      const paths = getSettings().projectRoots;
+      if (paths.length === 0) return;
       const unique = Array.from(new Set(paths)).filter(Boolean);
       this.roots = [];
       unique.forEach(p => this.addRoot(p, false));
@@ -123,16 +169,28 @@ export class FileExplorer {
   }
 
   private saveRoots() {
-    try { localStorage.setItem('editrion.projectRoots', JSON.stringify(this.roots)); } catch {}
+    setSetting('projectRoots', this.roots).catch(e => console.error('Failed to save project roots', e));
+    tauriApi.indexWorkspace(this.roots).catch(e => console.error('Failed to index workspace', e));
+    this.onRootsChanged?.();
+  }
+
+  getRoots(): string[] {
+    return [...this.roots];
+  }
+
+  /** Replace all opened folders, e.g. with those of a restored session */
+  setRoots(paths: string[]) {
+    this.clearAllRoots();
+    Array.from(new Set(paths)).filter(Boolean).forEach(p => this.addRoot(p));
   }
 
   clearAllRoots() {
     // Remove all root elements and their subtrees
     const roots = Array.from(this.container.querySelectorAll<HTMLElement>('[data-root="true"]'));
     for (const el of roots) {
-      const path = el.dataset.path || '';
-      const next = el.nextElementSibling as HTMLElement | null;
-      if (next && next.classList.contains('subtree') && next.dataset.parentPath === path) next.remove();
+      const sub = this.subtreeOf(el);
+      this.unwatchExpanded(el, sub);
+      sub?.remove();
       el.remove();
     }
     this.roots = [];
@@ -152,4 +210,5 @@ export class FileExplorer {
   private hideContextMenu() { this.ctxMenu.classList.add('hidden'); this.contextTargetPath = null; }
 
   private basename(path: string): string { const parts = path.split(/[/\\]/); return parts.pop() || path; }
+  private dirname(path: string): string { return path.replace(/[/\\][^/\\]*$/, ''); }
 }
@@ -0,0 +1,45 @@
+// Reading and saving text files in their own encoding, and saving files that another
+// program may have changed since they were loaded.
+import { t } from './i18n';
+import { tauriApi } from './tauriApi';
+import type { FileVersion, TextFormat } from '../types';
+
+/** Read a text file with its detected encoding and line breaks, to be passed back on save */
+export async function readTextFile(path: string): Promise<{ content: string; version: FileVersion; format: TextFormat }> {
+  const file = await tauriApi.readFileWithEncoding(path);
+  const format = { encoding: file.encoding, bom: file.hasBom, lineEnding: file.lineEnding, finalNewline: file.finalNewline };
+  return { content: file.content, version: file.version, format };
+}
+
+// Local history failures are shown once; saving keeps working without it
+let historyErrorShown = false;
+
+/** Write `content` in `format`, or as UTF-8 with the editor's line breaks without one */
+export async function writeTextFile(path: string, content: string, format?: TextFormat, expected?: FileVersion): Promise<FileVersion> {
+  const saved = await tauriApi.writeFileWithEncoding(
+    path, content, format?.encoding, format?.bom, format?.lineEnding, format?.finalNewline, expected
+  );
+  if (saved.historyError) {
+    console.warn('Local history did not keep the previous version of', path, saved.historyError);
+    if (!historyErrorShown) {
+      historyErrorShown = true;
+      alert(t('alert.localHistoryFailed', { error: saved.historyError.message }));
+    }
+  }
+  return saved.version;
+}
+
+/**
+ * Write `content` unless the file changed on disk since `expected` was read; asks
+ * before overwriting the other change. `null` when the user keeps the file on disk.
+ */
+export async function writeUnlessChanged(path: string, content: string, format?: TextFormat, expected?: FileVersion): Promise<FileVersion | null> {
+  try {
+    return await writeTextFile(path, content, format, expected);
+  } catch (e) {
+    if (!tauriApi.isAppError(e, 'conflict')) throw e;
+    const name = path.split(/[/\\]/).pop() || path;
+    if (!confirm(t('confirm.overwriteChangedFile', { name }))) return null;
+    return await writeTextFile(path, content, format);
+  }
+}
@@ -1,3 +1,5 @@
+import { getSettings, onSettingsChanged, setSetting } from './settings';
+
 type Dict = Record<string, string>;
 
 type Locale = 'en' | 'uk' | 'es' | 'fr' | 'ja' | 'de';
@@ -21,18 +23,29 @@ function loadDictionaries() {
 
 export function initI18n() {
   loadDictionaries();
-  const saved = localStorage.getItem('editrion.locale');
+  applyLocale(resolveLocale(getSettings().locale));
+  onSettingsChanged(settings => {
+    const locale = resolveLocale(settings.locale);
+    if (locale !== currentLocale) applyLocale(locale);
+  });
+}
+
+/** The saved locale, or a guess from the system language when there is none */
+function resolveLocale(saved: string | null): Locale {
   const nav = (navigator.language || 'en').toLowerCase();
   const candidates: Locale[] = ['uk', 'es', 'fr', 'ja', 'de', 'en'];
-  const guess: Locale = (['en', 'uk', 'es', 'fr', 'ja', 'de'] as const).includes((saved as any))
+  return (['en', 'uk', 'es', 'fr', 'ja', 'de'] as const).includes((saved as any))
     ? (saved as Locale)
     : (candidates.find(c => nav.startsWith(c)) || 'en');
-  setLocale(guess);
 }
 
 export function setLocale(locale: Locale) {
+  applyLocale(locale);
+  setSetting('locale', locale).catch(e => console.error('Failed to save locale', e));
+}
+
+function applyLocale(locale: Locale) {
   currentLocale = locale;
-  localStorage.setItem('editrion.locale', locale);
   document.documentElement.lang = locale;
   applyTranslations();
   const select = document.getElementById('lang-select') as HTMLSelectElement | null;
@@ -0,0 +1,119 @@
+// Settings are owned by the backend (`settings.json` in the app config dir, with
+// workspace overrides on top) and the Codex config.toml. This module keeps a
+// synchronous copy for the UI, refreshed from every `settings-changed` event.
+import { listen } from '@tauri-apps/api/event';
+import { tauriApi } from './tauriApi';
+import type { Settings, SettingsProblem, SettingsSnapshot } from '../types';
+
+// Mirrors the defaults in the backend schema; only used if it cannot be reached
+const fallback: Settings = {
+  theme: 'dark',
+  themeMode: 'dark',
+  customThemes: {},
+  locale: null,
+  projectRoots: [],
+  shortcuts: {},
+  ai: { provider: 'codex', customCommand: [], model: null, effort: null, summary: null, verbosity: null, templates: [] },
+};
+
+let current: Settings = fallback;
+let codex: Record<string, unknown> = {};
+let problems: SettingsProblem[] = [];
+const listeners = new Set<(settings: Settings, changed: string[]) => void>();
+
+function apply(snapshot: SettingsSnapshot) {
+  current = snapshot.settings;
+  codex = snapshot.codex;
+  problems = snapshot.problems;
+  if (problems.length > 0) console.warn('Ignored invalid settings:', problems);
+  listeners.forEach(cb => cb(current, snapshot.changed));
+}
+
+/** Load settings before the UI is built, moving any left in localStorage to the backend */
+export async function initSettings(): Promise<void> {
+  await listen<SettingsSnapshot>('settings-changed', e => apply(e.payload));
+  try {
+    const legacy = readLegacySettings();
+    if (legacy) {
+      apply(await tauriApi.importSettings(legacy));
+      clearLegacySettings();
+    } else {
+      apply(await tauriApi.getSettings());
+    }
+  } catch (e) { console.error('Failed to load settings:', e); }
+}
+
+export function getSettings(): Settings {
+  return current;
+}
+
+/** Codex config.toml as JSON, reloaded by the backend whenever the file is saved */
+export function getCodexConfig(): Record<string, unknown> {
+  return codex;
+}
+
+export function getSettingsProblems(): SettingsProblem[] {
+  return problems;
+}
+
+/** Store one setting by dotted key. Rejects with an `invalidSetting` error the schema refuses. */
+export async function setSetting(key: string, value: unknown): Promise<void> {
+  apply(await tauriApi.setSetting(key, value));
+}
+
+/** Back to defaults for `key`, or for everything */
+export async function resetSettings(key?: string): Promise<void> {
+  apply(await tauriApi.resetSettings(key));
+}
+
+/** Re-read the settings file, e.g. after it was edited in a tab */
+export async function reloadSettings(): Promise<void> {
+  apply(await tauriApi.getSettings());
+}
+
+/** `changed` lists the dotted keys that changed; it is empty when settings were reloaded on request */
+export function onSettingsChanged(cb: (settings: Settings, changed: string[]) => void): () => void {
+  listeners.add(cb);
+  return () => listeners.delete(cb);
+}
+
+// Keys used before settings moved to the backend
+const LEGACY_KEYS = [
+  'editrion-settings',
+  'editrion.theme',
+  'editrion.themeMode',
+  'editrion.customThemes',
+  'editrion.locale',
+  'editrion.projectRoots',
+  'editrion.shortcuts',
+  'editrion.aiOverrides',
+  'editrion.aiTemplates',
+];
+
+function readLegacySettings(): Record<string, unknown> | null {
+  const raw = (key: string) => { try { return localStorage.getItem(key); } catch { return null; } };
+  const json = (key: string) => { try { const v = raw(key); return v ? JSON.parse(v) : undefined; } catch { return undefined; } };
+  if (!LEGACY_KEYS.some(k => raw(k) != null)) return null;
+
+  // The backend drops anything that does not match the schema
+  const out: Record<string, unknown> = {};
+  const old = json('editrion-settings') || {};
+  const theme = raw('editrion.theme') ?? old.theme;
+  if (theme) out.theme = theme;
+  if (raw('editrion.themeMode')) out.themeMode = raw('editrion.themeMode');
+  if (json('editrion.customThemes')) out.customThemes = json('editrion.customThemes');
+  const locale = raw('editrion.locale') ?? old.locale;
+  if (locale) out.locale = locale;
+  const roots = json('editrion.projectRoots') ?? old.openFolders;
+  if (roots) out.projectRoots = roots;
+  if (json('editrion.shortcuts')) out.shortcuts = json('editrion.shortcuts');
+  const ai: Record<string, unknown> = { ...(old.aiOverrides || {}), ...(json('editrion.aiOverrides') || {}) };
+  const templates = json('editrion.aiTemplates');
+  if (templates) ai.templates = templates;
+  if (Object.keys(ai).length > 0) out.ai = ai;
+  return out;
+}
+
+function clearLegacySettings() {
+  for (const key of LEGACY_KEYS) { try { localStorage.removeItem(key); } catch {} }
+}
@@ -1,7 +1,8 @@
 // Centralized keyboard shortcuts with simple override support.
-// Users can override via localStorage key: 'editrion.shortcuts'
+// Users can override via the `shortcuts` setting in settings.json
 // Example:
-// localStorage.setItem('editrion.shortcuts', JSON.stringify({ save: 'Mod+S', duplicateSelection: 'Mod+Shift+D' }))
+// "shortcuts": { "save": "Mod+S", "duplicateSelection": "Mod+Shift+D" }
+import { getSettings } from './settings';
 
 export type ShortcutMap = Record<string, string | string[]>;
 
@@ -24,15 +25,10 @@ const defaults: ShortcutMap = {
 };
 
 export function getShortcuts(): ShortcutMap {
-  try {
-    const raw = localStorage.getItem('editrion.shortcuts');
-    if (raw) {
-      const user = JSON.parse(raw);
-      if (user && typeof user === 'object') {
-        return mergeMaps(defaults, user as ShortcutMap);
-      }
-    }
-  } catch {}
+  const user = getSettings().shortcuts;
+  if (user && typeof user === 'object') {
+    return mergeMaps(defaults, user as ShortcutMap);
+  }
   return { ...defaults };
 }
 
@@ -1,33 +1,290 @@
 import { invoke } from '@tauri-apps/api/core';
 import { save, open } from '@tauri-apps/plugin-dialog';
-import type { FileItem } from '../types';
+import type {
+  AiProviderId,
+  AppErrorPayload,
+  ApprovedFile,
+  DecodedFile,
+  DirEntry,
+  FileMatch,
+  FileStat,
+  FileVersion,
+  HistoryEntry,
+  HistorySnapshot,
+  LargeFileHandle,
+  LargeFileSearchHit,
+  LargeFileStatus,
+  LineEnding,
+  OpenedWorkspace,
+  OverwritePolicy,
+  ProviderInfo,
+  ReadDirOptions,
+  RecentWorkspace,
+  Removal,
+  SavedFile,
+  FileSearchQuery,
+  ReplaceBackup,
+  ReplaceOutcome,
+  ReplacePreview,
+  Session,
+  SearchSummary,
+  SettingsSnapshot,
+  TrashItem,
+  VersionedText,
+  Workspace,
+  WorkspaceIndexStatus,
+} from '../types';
 
 // Centralized API service for all Tauri commands
 
 export class TauriAPI {
   // File System Operations
-  async readFile(path: string): Promise<string> {
-    return await invoke('read_file', { path });
+  async readFile(path: string, maxBytes?: number, truncate?: boolean): Promise<string> {
+    const file = await this.readFileVersioned(path, maxBytes, truncate);
+    return file.content;
   }
 
-  async writeFile(path: string, content: string): Promise<void> {
-    await invoke('write_file', { path, content });
+  async readFileVersioned(path: string, maxBytes?: number, truncate?: boolean): Promise<VersionedText> {
+    return await invoke('read_file', { path, maxBytes, truncate });
   }
 
-  async readDir(path: string): Promise<FileItem[]> {
-    return await invoke('read_dir', { path });
+  async statFile(path: string): Promise<FileStat> {
+    return await invoke('stat_file', { path });
+  }
+
+  async writeFile(
+    path: string,
+    content: string,
+    lineEnding?: LineEnding,
+    finalNewline?: boolean,
+    expectedVersion?: FileVersion
+  ): Promise<SavedFile> {
+    return await invoke('write_file', { path, content, lineEnding, finalNewline, expectedVersion });
+  }
+
+  async readFileWithEncoding(path: string, maxBytes?: number, truncate?: boolean): Promise<DecodedFile> {
+    return await invoke('read_file_with_encoding', { path, maxBytes, truncate });
+  }
+
+  async writeFileWithEncoding(
+    path: string,
+    content: string,
+    encoding?: string,
+    bom?: boolean,
+    lineEnding?: LineEnding,
+    finalNewline?: boolean,
+    expectedVersion?: FileVersion
+  ): Promise<SavedFile> {
+    return await invoke('write_file_with_encoding', {
+      path, content, encoding, bom, lineEnding, finalNewline, expectedVersion
+    });
+  }
+
+  async readDir(path: string, options?: ReadDirOptions): Promise<DirEntry[]> {
+    return await invoke('read_dir', { path, options });
   }
 
   async createNewFile(): Promise<string> {
     return await invoke('create_new_file');
   }
 
-  async removeFile(path: string): Promise<void> {
-    await invoke('remove_file', { path });
+  /** Moves to the trash unless `permanent` or the platform has none; the trash entry is for undo */
+  async removeFile(path: string, permanent?: boolean): Promise<Removal> {
+    return await invoke('remove_file', { path, permanent });
+  }
+
+  async clearDir(path: string, permanent?: boolean): Promise<Removal> {
+    return await invoke('clear_dir', { path, permanent });
+  }
+
+  async createDir(path: string): Promise<void> {
+    await invoke('create_dir', { path });
+  }
+
+  async renamePath(from: string, to: string, overwrite?: OverwritePolicy): Promise<void> {
+    await invoke('rename_path', { from, to, overwrite });
+  }
+
+  async copyPath(from: string, to: string, overwrite?: OverwritePolicy): Promise<void> {
+    await invoke('copy_path', { from, to, overwrite });
+  }
+
+  async duplicatePath(path: string): Promise<string> {
+    return await invoke('duplicate_path', { path });
+  }
+
+  // Trash
+  async trashPath(path: string): Promise<TrashItem> {
+    return await invoke('trash_path', { path });
+  }
+
+  async listTrash(): Promise<TrashItem[]> {
+    return await invoke('list_trash');
+  }
+
+  async restoreFromTrash(id: string, overwrite?: OverwritePolicy): Promise<string> {
+    return await invoke('restore_from_trash', { id, overwrite });
+  }
+
+  async purgeFromTrash(id: string): Promise<void> {
+    await invoke('purge_from_trash', { id });
+  }
+
+  // Local history: snapshots taken before every save
+  async listFileHistory(path: string): Promise<HistoryEntry[]> {
+    return await invoke('list_file_history', { path });
+  }
+
+  async readHistorySnapshot(path: string, id: string): Promise<HistorySnapshot> {
+    return await invoke('read_history_snapshot', { path, id });
+  }
+
+  async restoreHistorySnapshot(path: string, id: string, expectedVersion?: FileVersion): Promise<FileVersion> {
+    return await invoke('restore_history_snapshot', { path, id, expectedVersion });
+  }
+
+  // Large files (paged reading)
+  async largeFileOpen(path: string): Promise<LargeFileHandle> {
+    return await invoke('large_file_open', { path });
+  }
+
+  async largeFileLines(id: string, start: number, end: number): Promise<string[]> {
+    return await invoke('large_file_lines', { id, start, end });
+  }
+
+  async largeFileStatus(id: string): Promise<LargeFileStatus> {
+    return await invoke('large_file_status', { id });
+  }
+
+  async largeFileSearch(
+    id: string,
+    query: string,
+    fromOffset?: number,
+    caseSensitive?: boolean
+  ): Promise<LargeFileSearchHit | null> {
+    return await invoke('large_file_search', { id, query, fromOffset, caseSensitive });
+  }
+
+  async largeFileClose(id: string): Promise<void> {
+    await invoke('large_file_close', { id });
+  }
+
+  // File system watching (events: fs-changed, fs-removed, fs-renamed)
+  async watchPaths(paths: string[]): Promise<void> {
+    await invoke('watch_paths', { paths });
+  }
+
+  async unwatchPaths(paths: string[]): Promise<void> {
+    await invoke('unwatch_paths', { paths });
+  }
+
+  // Workspace index / Go to File (events: workspace-index)
+  async indexWorkspace(roots: string[]): Promise<WorkspaceIndexStatus[]> {
+    return await invoke('index_workspace', { roots });
+  }
+
+  async workspaceIndexStatus(): Promise<WorkspaceIndexStatus[]> {
+    return await invoke('workspace_index_status');
+  }
+
+  async findFiles(query: string, limit?: number): Promise<FileMatch[]> {
+    return await invoke('find_files', { query, limit });
+  }
+
+  // Find in files (events: search-stream, search-complete)
+  async searchInFiles(root: string, query: FileSearchQuery, runId?: string): Promise<SearchSummary> {
+    return await invoke('search_in_files', { runId: runId || this.generateRunId(), root, query });
+  }
+
+  async searchCancel(runId: string): Promise<void> {
+    await invoke('search_cancel', { runId });
   }
 
-  async clearDir(path: string): Promise<void> {
-    await invoke('clear_dir', { path });
+  // Replace in files: preview, apply all-or-nothing, undo from backup
+  async replacePreview(root: string, query: FileSearchQuery, replacement: string): Promise<ReplacePreview> {
+    return await invoke('replace_preview', { root, query, replacement });
+  }
+
+  async replaceApply(query: FileSearchQuery, replacement: string, files: ApprovedFile[]): Promise<ReplaceOutcome> {
+    return await invoke('replace_apply', { query, replacement, files });
+  }
+
+  async replaceUndo(backupId: string): Promise<string[]> {
+    return await invoke('replace_undo', { backupId });
+  }
+
+  async listReplaceBackups(): Promise<ReplaceBackup[]> {
+    return await invoke('list_replace_backups');
+  }
+
+  async discardReplaceBackup(backupId: string): Promise<void> {
+    await invoke('discard_replace_backup', { backupId });
+  }
+
+  // Hot exit: tabs, unsaved buffers, folders and window geometry
+  async saveSession(session: Session): Promise<void> {
+    await invoke('save_session', { session });
+  }
+
+  /** Also applies the saved window geometry */
+  async restoreSession(): Promise<Session | null> {
+    return await invoke('restore_session');
+  }
+
+  async discardSession(): Promise<void> {
+    await invoke('discard_session');
+  }
+
+  // Workspaces; the session passed when leaving one is stored in its file
+  async openWorkspace(path: string): Promise<OpenedWorkspace> {
+    return await invoke('open_workspace', { path });
+  }
+
+  /** Saves to the current workspace file when `path` is omitted */
+  async saveWorkspace(workspace: Workspace, path?: string): Promise<OpenedWorkspace> {
+    return await invoke('save_workspace', { path, workspace });
+  }
+
+  async switchWorkspace(path: string, session?: Session): Promise<OpenedWorkspace> {
+    return await invoke('switch_workspace', { path, session });
+  }
+
+  async closeWorkspace(session?: Session): Promise<void> {
+    await invoke('close_workspace', { session });
+  }
+
+  async currentWorkspace(): Promise<OpenedWorkspace | null> {
+    return await invoke('current_workspace');
+  }
+
+  async listRecentWorkspaces(): Promise<RecentWorkspace[]> {
+    return await invoke('list_recent_workspaces');
+  }
+
+  async removeRecentWorkspace(path: string): Promise<RecentWorkspace[]> {
+    return await invoke('remove_recent_workspace', { path });
+  }
+
+  // Settings (events: settings-changed); `key` is dotted, e.g. `ai.effort`
+  async getSettings(): Promise<SettingsSnapshot> {
+    return await invoke('settings_get');
+  }
+
+  async setSetting(key: string, value: unknown): Promise<SettingsSnapshot> {
+    return await invoke('settings_set', { key, value });
+  }
+
+  /** Resets every setting when `key` is omitted */
+  async resetSettings(key?: string): Promise<SettingsSnapshot> {
+    return await invoke('settings_reset', { key });
+  }
+
+  async importSettings(values: Record<string, unknown>): Promise<SettingsSnapshot> {
+    return await invoke('settings_import', { values });
+  }
+
+  async getSettingsPath(): Promise<string> {
+    return await invoke('settings_path');
   }
 
   // Application Commands
@@ -49,61 +306,64 @@ export class TauriAPI {
   }
 
   // External CLI (AI Integration)
-  async codexExecStream(
-    prompt: string, 
-    cwd?: string, 
-    runId?: string, 
+  /** Output arrives as `ai-stream` events; the run ends with an `ai-complete` event */
+  async aiExecStream(
+    provider: AiProviderId,
+    prompt: string,
+    cwd?: string,
+    runId?: string,
     model?: string,
     config?: Record<string, string>
   ): Promise<void> {
-    await invoke('codex_exec_stream', { 
-      prompt, 
-      cwd, 
-      runId: runId || this.generateRunId(), 
-      model, 
-      config 
+    await invoke('ai_exec_stream', {
+      provider,
+      prompt,
+      cwd,
+      runId: runId || this.generateRunId(),
+      model,
+      config
     });
   }
 
-  async claudeExecStream(
-    prompt: string, 
-    cwd?: string, 
-    runId?: string, 
-    model?: string,
-    config?: Record<string, string>
-  ): Promise<void> {
-    await invoke('claude_exec_stream', { 
-      prompt, 
-      cwd, 
-      runId: runId || this.generateRunId(), 
-      model, 
-      config 
-    });
+  async aiLoginStream(provider: AiProviderId, runId?: string): Promise<void> {
+    await invoke('ai_login_stream', { provider, runId: runId || this.generateRunId() });
   }
 
-  async codexLoginStream(runId?: string): Promise<void> {
-    await invoke('codex_login_stream', { runId: runId || this.generateRunId() });
+  async aiCancel(runId: string): Promise<void> {
+    await invoke('ai_cancel', { runId });
   }
 
-  async claudeLoginStream(runId?: string): Promise<void> {
-    await invoke('claude_login_stream', { runId: runId || this.generateRunId() });
+  async listProviders(): Promise<ProviderInfo[]> {
+    return await invoke('list_providers');
   }
 
-  async codexCancel(runId: string): Promise<void> {
-    await invoke('codex_cancel', { runId });
+  // Configuration
+  async getCodexConfigPath(): Promise<string> {
+    return await invoke('codex_config_path');
   }
 
-  async claudeCancel(runId: string): Promise<void> {
-    await invoke('claude_cancel', { runId });
+  /** Value at a key path such as `profiles.work.model`; null when unset */
+  async getCodexConfigValue(key: string): Promise<unknown> {
+    return await invoke('codex_config_get', { key });
   }
 
-  // Configuration
-  async getCodexConfigPath(): Promise<string> {
-    return await invoke('codex_config_path');
+  /** The whole config.toml; rejects with an `invalidToml` error when it does not parse */
+  async readCodexConfig(): Promise<Record<string, unknown>> {
+    return await invoke('codex_config_read');
+  }
+
+  async listCodexProfiles(): Promise<string[]> {
+    return await invoke('codex_config_profiles');
+  }
+
+  /** `key` may be a path such as `profiles.work.model`; resolves to the value as stored */
+  async setCodexConfig(key: string, value: string): Promise<unknown> {
+    return await invoke('codex_config_set', { key, value });
   }
 
-  async setCodexConfig(key: string, value: string): Promise<void> {
-    await invoke('codex_config_set', { key, value });
+  /** Resolves to whether the key was set */
+  async unsetCodexConfig(key: string): Promise<boolean> {
+    return await invoke('codex_config_unset', { key });
   }
 
   // Menu Operations
@@ -144,6 +404,17 @@ export class TauriAPI {
     return `run-${Date.now()}-${Math.random().toString(36).substr(2, 9)}`;
   }
 
+  // Backend errors arrive as { kind, message, details? }
+  isAppError(error: unknown, kind?: string): error is AppErrorPayload {
+    if (!error || typeof error !== 'object') return false;
+    const e = error as Partial<AppErrorPayload>;
+    return typeof e.kind === 'string' && typeof e.message === 'string' && (!kind || e.kind === kind);
+  }
+
+  errorMessage(error: unknown): string {
+    return this.isAppError(error) ? error.message : String(error);
+  }
+
   // Error handling wrapper
   async safeInvoke<T>(command: string, args?: Record<string, any>): Promise<T | null> {
     try {
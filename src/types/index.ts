@@ -1,9 +1,130 @@
 import * as monaco from 'monaco-editor';
 
-export interface FileItem {
+export interface DirEntry {
   name: string;
   path: string;
-  is_dir: boolean;
+  isDir: boolean;
+  isSymlink: boolean;
+  symlinkTarget: string | null;
+  size: number;
+  modified: number | null;
+  readonly: boolean;
+  mode: number | null;
+  hidden: boolean;
+  ignored: boolean;
+}
+
+export type DirSort = 'natural' | 'name' | 'modified' | 'size' | 'none';
+
+export interface ReadDirOptions {
+  sort?: DirSort;
+  foldersFirst?: boolean;
+  showHidden?: boolean;
+  hideIgnored?: boolean;
+}
+
+export type OverwritePolicy = 'fail' | 'overwrite';
+
+export interface TrashItem {
+  id: string;
+  originalPath: string;
+  deletedAt: string;
+  isDir: boolean;
+}
+
+/** `permanent` when there was no trash to use, or it was asked for */
+export interface Removal {
+  trashItem: TrashItem | null;
+  permanent: boolean;
+}
+
+// Local history (`list_file_history`); ids are unique per file
+export interface HistoryEntry {
+  id: string;
+  timestamp: number;
+  size: number;
+  hash: string;
+}
+
+export interface HistorySnapshot {
+  content: string;
+  encoding: string;
+  hasBom: boolean;
+  hadErrors: boolean;
+}
+
+export type LineEnding = 'lf' | 'crlf' | 'cr' | 'mixed' | 'none';
+
+/** Identifies the file contents a buffer was loaded from; sent back on save */
+export interface FileVersion {
+  mtime: number | null;
+  size: number;
+  hash: string;
+  /** Only the start of the file was read; the buffer cannot be saved over it */
+  truncated?: boolean;
+}
+
+export interface VersionedText {
+  content: string;
+  version: FileVersion;
+}
+
+/** Result of `write_file` and `write_file_with_encoding` */
+export interface SavedFile {
+  version: FileVersion;
+  /** The file was saved, but its previous contents are missing from local history */
+  historyError?: AppErrorPayload;
+}
+
+export interface DecodedFile {
+  content: string;
+  encoding: string;
+  hasBom: boolean;
+  hadErrors: boolean;
+  lineEnding: LineEnding;
+  finalNewline: boolean;
+  version: FileVersion;
+}
+
+/** How a file is stored on disk; sent back on save so it is written the same way */
+export interface TextFormat {
+  encoding: string;
+  bom: boolean;
+  lineEnding?: LineEnding;
+  finalNewline?: boolean;
+}
+
+export interface FileStat {
+  path: string;
+  size: number;
+  isDir: boolean;
+  isSymlink: boolean;
+  symlinkTarget: string | null;
+  readonly: boolean;
+  modified: number | null;
+  mime: string;
+  isBinary: boolean;
+  invalidUtf8Ratio: number;
+  exceedsReadLimit: boolean;
+}
+
+export interface LargeFileHandle {
+  id: string;
+  path: string;
+  size: number;
+}
+
+export interface LargeFileStatus {
+  lines: number;
+  complete: boolean;
+  indexedBytes: number;
+  size: number;
+}
+
+export interface LargeFileSearchHit {
+  offset: number;
+  line: number | null;
+  column: number | null;
 }
 
 export interface Tab {
@@ -15,6 +136,52 @@ export interface Tab {
   // Keep track of search highlight decorations for this tab
   searchDecorationIds?: string[];
   originalContent?: string;
+  // Disk version the buffer was loaded from or last saved as
+  version?: FileVersion;
+  // Encoding and line breaks the file was read with; new files are saved as UTF-8
+  format?: TextFormat;
+  // Cursor and scroll from a restored session, applied when the editor is created
+  pendingView?: ViewState;
+}
+
+// Hot exit (`save_session`, `restore_session`)
+export interface ViewState {
+  line: number;
+  column: number;
+  scrollTop: number;
+  scrollLeft: number;
+}
+
+export interface SessionTab {
+  id: string;
+  name: string;
+  path: string;
+  // Only for tabs with unsaved changes
+  content: string | null;
+  version: FileVersion | null;
+  format: TextFormat | null;
+  view: ViewState | null;
+}
+
+export interface WindowGeometry {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  maximized: boolean;
+  fullscreen: boolean;
+}
+
+export interface Session {
+  version?: number;
+  savedAt?: number;
+  cleanExit: boolean;
+  roots: string[];
+  workspace?: string | null;
+  tabs: SessionTab[];
+  activeTab: string | null;
+  // Taken from the window by the backend when omitted
+  window?: WindowGeometry | null;
 }
 
 export interface SearchOptions {
@@ -23,16 +190,254 @@ export interface SearchOptions {
   regex: boolean;
 }
 
+// Project-wide search (`search_in_files`)
+export interface FileSearchQuery {
+  pattern: string;
+  isRegex?: boolean;
+  caseSensitive?: boolean;
+  wholeWord?: boolean;
+  include?: string[];
+  exclude?: string[];
+  useIgnoreFiles?: boolean;
+  contextLines?: number;
+  maxResults?: number;
+  maxFileSize?: number;
+}
+
+export interface SearchLineMatch {
+  /** 0-based */
+  line: number;
+  text: string;
+  truncated: boolean;
+  /** 0-based columns in characters */
+  ranges: { column: number; length: number }[];
+  contextBefore: string[];
+  contextAfter: string[];
+}
+
+export interface SearchFileResult {
+  path: string;
+  matches: SearchLineMatch[];
+}
+
+export interface SearchSummary {
+  filesSearched: number;
+  filesMatched: number;
+  matches: number;
+  limitHit: boolean;
+  cancelled: boolean;
+}
+
+export interface SearchStreamPayload {
+  runId: string;
+  files: SearchFileResult[];
+}
+
+export type SearchCompletePayload =
+  | { runId: string; ok: true; summary: SearchSummary }
+  | { runId: string; ok: false; error: AppErrorPayload };
+
+// Project-wide replace (`replace_preview`, `replace_apply`)
+export interface ReplaceEdit {
+  id: number;
+  line: number;
+  column: number;
+  length: number;
+  replacement: string;
+}
+
+export interface DiffLine {
+  kind: 'context' | 'removed' | 'added';
+  text: string;
+}
+
+export interface DiffHunk {
+  oldStart: number;
+  oldLines: number;
+  newStart: number;
+  newLines: number;
+  lines: DiffLine[];
+}
+
+export interface ReplaceFilePreview {
+  path: string;
+  version: FileVersion;
+  edits: ReplaceEdit[];
+  hunks: DiffHunk[];
+}
+
+export interface ReplacePreview {
+  files: ReplaceFilePreview[];
+  skipped: { path: string; reason: string }[];
+  limitHit: boolean;
+}
+
+export interface ApprovedFile {
+  path: string;
+  version: FileVersion;
+  // Ids of the edits to apply; all when omitted
+  edits?: number[];
+}
+
+export interface ReplaceOutcome {
+  backupId: string;
+  files: string[];
+  replacements: number;
+}
+
+export interface ReplaceBackup {
+  id: string;
+  createdAt: string;
+  pattern: string;
+  replacement: string;
+  entries: { path: string; backup: string; before: FileVersion; afterHash: string }[];
+}
+
+// Workspaces (`.editrion-workspace` files)
+export interface WorkspaceFolder {
+  path: string;
+  name?: string | null;
+}
+
+export interface Workspace {
+  version?: number;
+  folders: WorkspaceFolder[];
+  settings: Record<string, unknown>;
+  exclude: string[];
+  ai: { model?: string | null; effort?: string | null; cwd?: string | null };
+  session?: Session | null;
+}
+
+export interface OpenedWorkspace {
+  path: string;
+  name: string;
+  workspace: Workspace;
+}
+
+export interface RecentWorkspace {
+  path: string;
+  name: string;
+  openedAt: number;
+}
+
 export interface Theme {
   name: string;
   isDark: boolean;
 }
 
-export interface AppSettings {
+export type AiProviderId = 'codex' | 'claude' | 'custom';
+
+export interface ProviderInfo {
+  id: AiProviderId;
+  name: string;
+  /** Whether the executable was found */
+  available: boolean;
+  path: string | null;
+  canLogin: boolean;
+}
+
+/** Payload of `ai-stream` events */
+export interface AiStreamEvent {
+  runId: string;
+  provider: AiProviderId;
+  channel: 'stdout' | 'stderr';
+  data: string;
+}
+
+/** What the AI CLI reported, parsed from its JSON output */
+export type AiEvent =
+  | { type: 'textDelta'; text: string }
+  | { type: 'reasoningDelta'; text: string }
+  | { type: 'toolCall'; id: string | null; name: string; input: unknown }
+  | { type: 'fileEdit'; path: string; kind: 'add' | 'update' | 'delete'; diff: string | null }
+  | { type: 'usage'; inputTokens: number; cachedInputTokens: number; outputTokens: number }
+  | { type: 'finalMessage'; text: string }
+  | { type: 'error'; message: string };
+
+/** Payload of `ai-event` events */
+export interface AiEventPayload {
+  runId: string;
+  provider: AiProviderId;
+  event: AiEvent;
+}
+
+export type AiRunLifecycle =
+  | { phase: 'spawned'; pid: number }
+  | { phase: 'firstByte'; latencyMs: number }
+  /** `code` is null when the process was killed by `signal` */
+  | { phase: 'exited'; code: number | null; signal: number | null; durationMs: number };
+
+/** Payload of `ai-lifecycle` events */
+export interface AiLifecycleEvent {
+  runId: string;
+  provider: AiProviderId;
+  event: AiRunLifecycle;
+}
+
+/** `cancelled` when stopped through `aiCancel` */
+export type AiRunStatus = 'succeeded' | 'failed' | 'cancelled';
+
+/** Payload of `ai-complete` events */
+export interface AiCompleteEvent {
+  runId: string;
+  provider: AiProviderId;
+  /** Same as `status === 'succeeded'` */
+  ok: boolean;
+  status: AiRunStatus;
+  /** The answer: the final message, or the streamed text */
+  text: string;
+  /** Raw output; JSON lines for Codex and Claude */
+  stdout: string;
+  stderr: string;
+  exitCode: number | null;
+  signal: number | null;
+  durationMs: number;
+}
+
+// Settings: defaults, then the user settings file, then the open workspace
+export interface AiTemplate {
+  id: string;
+  name: string;
+  instruction: string;
+  effort?: 'minimal' | 'low' | 'medium' | 'high';
+}
+
+export interface Settings {
   theme: string;
-  locale: string;
-  openFolders: string[];
-  aiOverrides?: Record<string, string>;
+  themeMode: 'dark' | 'light';
+  customThemes: Record<string, any>;
+  /** null follows the system language */
+  locale: 'en' | 'uk' | 'es' | 'fr' | 'ja' | 'de' | null;
+  projectRoots: string[];
+  shortcuts: Record<string, string | string[]>;
+  ai: {
+    provider: AiProviderId;
+    /** Executable and arguments of the `custom` provider; `{model}` and `{prompt}` are substituted */
+    customCommand: string[];
+    model: string | null;
+    effort: 'minimal' | 'low' | 'medium' | 'high' | null;
+    summary: 'auto' | 'concise' | 'detailed' | 'none' | null;
+    verbosity: 'low' | 'medium' | 'high' | null;
+    templates: AiTemplate[];
+  };
+}
+
+/** An invalid entry that was skipped; line and column are 1-based */
+export interface SettingsProblem {
+  file: string;
+  key: string | null;
+  line: number | null;
+  column: number | null;
+  message: string;
+}
+
+export interface SettingsSnapshot {
+  settings: Settings;
+  /** Parsed Codex config.toml; the last valid version while the file has errors */
+  codex: Record<string, unknown>;
+  problems: SettingsProblem[];
+  /** Dotted keys changed since the previous event (`codex.` prefixed for config.toml) */
+  changed: string[];
 }
 
 // Events
@@ -41,6 +446,27 @@ export interface MenuEvent {
   payload?: any;
 }
 
+export interface WorkspaceIndexStatus {
+  root: string;
+  files: number;
+  complete: boolean;
+  watching: boolean;
+}
+
+export interface FileMatch {
+  root: string;
+  path: string;
+  relativePath: string;
+  score: number;
+  /** Character indices into `relativePath` to highlight */
+  positions: number[];
+}
+
+export type FsEventPayload =
+  | { kind: 'changed'; path: string }
+  | { kind: 'removed'; path: string }
+  | { kind: 'renamed'; from: string; to: string };
+
 // AI Integration
 export interface AIRequest {
   prompt: string;
@@ -61,4 +487,11 @@ export interface TauriResponse<T = any> {
 export interface TauriError {
   message: string;
   code?: string;
+}
+
+// Serialized `AppError` from the backend
+export interface AppErrorPayload {
+  kind: string;
+  message: string;
+  details?: Record<string, any>;
 }
\ No newline at end of file